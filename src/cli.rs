@@ -1,24 +1,94 @@
 use anyhow::Result;
 use itertools::Itertools;
-use std::io::Write;
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{BufRead, IsTerminal, Write};
 
 use crate::{
-    config::{cli::Command, Config},
+    config::{
+        cli::{Command, PlaylistCommand, QueueCommand, SearchArgs, StatusBar},
+        theme::properties::{Property, PropertyFile, PropertyKind, PropertyKindFile, SongFormat, SongFormatFile},
+        Config,
+    },
     context::AppContext,
     mpd::{
-        commands::{volume::Bound, IdleEvent},
-        mpd_client::{Filter, MpdClient, Tag},
+        capabilities::{Capabilities, Feature},
+        commands::{volume::Bound, IdleEvent, Song, Status},
+        errors::{ErrorCode, MpdError, MpdFailureResponse},
+        mpd_client::{Filter, FilterKind, MpdClient, QueueMoveTarget, SingleOrRange, Tag},
     },
-    shared::macros::status_error,
+    shared::{ext::mpd_client::MpdClientExt, ipc, macros::status_error},
     WorkRequest,
 };
-use anyhow::bail;
+use anyhow::{anyhow, bail, Context};
 
 impl Command {
+    /// Replaces paths of commands which add songs with paths read from stdin, one per line, if '-'
+    /// is given in place of a path or if no path is given and stdin is not a terminal. Only used
+    /// when running from the command line, not from the command mode of the TUI.
+    pub fn with_stdin_paths(self) -> Result<Self> {
+        Ok(match self {
+            Command::Add { files } if files.is_empty() && !std::io::stdin().is_terminal() => Command::Add {
+                files: read_stdin_paths()?,
+            },
+            Command::Add { files } => Command::Add {
+                files: replace_stdin_paths(files)?,
+            },
+            Command::Playlist {
+                command: PlaylistCommand::Add { name, files, position },
+            } => Command::Playlist {
+                command: PlaylistCommand::Add {
+                    name,
+                    files: replace_stdin_paths(files)?,
+                    position,
+                },
+            },
+            Command::Queue {
+                command: QueueCommand::Insert { files, position },
+            } => Command::Queue {
+                command: QueueCommand::Insert {
+                    files: replace_stdin_paths(files)?,
+                    position,
+                },
+            },
+            cmd => cmd,
+        })
+    }
+
+    /// Whether the command keeps printing until it is interrupted. Such commands wait for changes
+    /// without a read timeout and cannot be used from the command mode of the TUI.
+    pub fn runs_until_interrupted(&self) -> bool {
+        match self {
+            Command::Watch { .. } => true,
+            Command::Status { output } | Command::Song { path: None, output } => output.follow,
+            _ => false,
+        }
+    }
+
+    /// Exit code documented by the command for the given error, None when the error should be
+    /// reported as a generic failure
+    pub fn exit_code(&self, err: &anyhow::Error) -> Option<i32> {
+        match (self, err.downcast_ref::<MpdError>()?) {
+            (
+                Command::Playlist { .. },
+                MpdError::Mpd(MpdFailureResponse {
+                    code: ErrorCode::NoExist,
+                    ..
+                }),
+            ) => Some(2),
+            (
+                Command::Playlist { .. },
+                MpdError::Mpd(MpdFailureResponse {
+                    code: ErrorCode::Exist, ..
+                }),
+            ) => Some(3),
+            _ => None,
+        }
+    }
+
     pub fn execute<F, C>(
         self,
         client: &mut C,
-        _config: &'static Config,
+        config: &'static Config,
         mut request_work: F,
     ) -> Result<(), anyhow::Error>
     where
@@ -35,7 +105,7 @@ impl Command {
 
                 if wait {
                     loop {
-                        client.idle(Some(IdleEvent::Update))?;
+                        client.idle(&[IdleEvent::Update])?;
                         let crate::mpd::commands::Status { updating_db, .. } = client.get_status()?;
                         match updating_db {
                             Some(current_id) if current_id > job_id => break,
@@ -59,9 +129,44 @@ impl Command {
             Command::Random { value } => client.random((value).into())?,
             Command::Single { value } => client.single((value).into())?,
             Command::Consume { value } => client.consume((value).into())?,
+            Command::Crossfade { value: Some(value) } => client.crossfade(value)?,
+            Command::Crossfade { value: None } => println!("{}", client.get_status()?.xfade.unwrap_or_default()),
+            Command::MixRampDb { value: Some(value) } => client.mixrampdb(value)?,
+            Command::MixRampDb { value: None } => {
+                println!("{}", client.get_status()?.mixrampdb.unwrap_or_else(|| "0".to_owned()));
+            }
+            Command::MixRampDelay { value: Some(value) } if value == "off" => client.mixrampdelay(None)?,
+            Command::MixRampDelay { value: Some(value) } => client.mixrampdelay(Some(value.parse()?))?,
+            Command::MixRampDelay { value: None } => {
+                println!(
+                    "{}",
+                    client.get_status()?.mixrampdelay.unwrap_or_else(|| "off".to_owned())
+                );
+            }
+            Command::ReplayGain { mode: Some(mode) } => client.replay_gain_mode(mode.into())?,
+            Command::ReplayGain { mode: None } => println!("{}", client.replay_gain_status()?.mode.to_mpd_value()),
             Command::Seek { value } => client.seek_current(value.parse()?)?,
             Command::Clear => client.clear()?,
-            Command::Add { file } => client.add(&file)?,
+            Command::Add { files } => {
+                if files.is_empty() {
+                    bail!("No path to add was given");
+                }
+                let capabilities = if files.iter().any(|file| file.contains("://")) {
+                    Some(Capabilities::fetch(client)?)
+                } else {
+                    None
+                };
+                for file in &files {
+                    if let (Some((scheme, _)), Some(capabilities)) = (file.split_once("://"), &capabilities) {
+                        if !capabilities.supports_url_handler(&format!("{scheme}://")) {
+                            bail!("MPD does not support playing '{scheme}://' urls");
+                        }
+                    }
+                }
+                client.add_multiple(&files.iter().map(String::as_str).collect_vec())?;
+            }
+            Command::Queue { command } => command.execute(client)?,
+            Command::Playlist { command } => command.execute(client)?,
             Command::AddYt { url } => {
                 request_work(WorkRequest::DownloadYoutube { url }, client);
             }
@@ -71,20 +176,36 @@ impl Command {
             Command::Theme { .. } => bail!("Cannot use theme command here."),
             Command::Version => bail!("Cannot use version command here."),
             Command::DebugInfo => bail!("Cannot use debuginfo command here."),
+            Command::Remote { pid, command } => ipc::send(pid, &command.try_into()?)?,
             Command::ToggleOutput { id } => client.toggle_output(id)?,
             Command::EnableOutput { id } => client.enable_output(id)?,
             Command::DisableOutput { id } => client.disable_output(id)?,
-            Command::Status => println!("{}", serde_json::ser::to_string(&client.get_status()?)?),
-            Command::Song { path: Some(paths) } if paths.len() == 1 => {
-                let path = &paths[0];
-                if let Some(song) = client.find_one(&[Filter::new(Tag::File, path.as_str())])? {
-                    println!("{}", serde_json::ser::to_string(&song)?);
-                } else {
-                    println!("Song with path '{path}' not found.");
-                    std::process::exit(1);
-                }
+            Command::Status { output } => {
+                let mut format = output
+                    .format
+                    .as_deref()
+                    .map(|format| LineFormat::new(format, config.server_name()))
+                    .transpose()?;
+                follow(client, output.follow, |client| {
+                    let status = client.get_status()?;
+                    if let Some(format) = &mut format {
+                        let song = client.get_current_song()?;
+                        format.print(song.as_ref(), &status, output.bar)?;
+                    } else {
+                        println!("{}", serde_json::ser::to_string(&status)?);
+                    }
+                    Ok(())
+                })?;
             }
-            Command::Song { path: Some(paths) } => {
+            Command::Song {
+                path: Some(paths),
+                output,
+            } => {
+                let mut format = output
+                    .format
+                    .as_deref()
+                    .map(|format| LineFormat::new(format, config.server_name()))
+                    .transpose()?;
                 let mut songs = Vec::new();
                 for path in &paths {
                     if let Some(song) = client.find_one(&[Filter::new(Tag::File, path.as_str())])? {
@@ -94,19 +215,60 @@ impl Command {
                         std::process::exit(1);
                     }
                 }
-                println!("{}", serde_json::ser::to_string(&songs)?);
-            }
-            Command::Song { path: None } => {
-                let current_song = client.get_current_song()?;
-                if let Some(song) = current_song {
-                    println!("{}", serde_json::ser::to_string(&song)?);
+
+                if let Some(format) = &mut format {
+                    let status = client.get_status()?;
+                    for song in &songs {
+                        format.print(Some(song), &status, output.bar)?;
+                    }
+                } else if let [song] = songs.as_slice() {
+                    println!("{}", serde_json::ser::to_string(song)?);
                 } else {
-                    std::process::exit(1);
+                    println!("{}", serde_json::ser::to_string(&songs)?);
+                }
+            }
+            Command::Song { path: None, output } => {
+                let mut format = output
+                    .format
+                    .as_deref()
+                    .map(|format| LineFormat::new(format, config.server_name()))
+                    .transpose()?;
+                follow(client, output.follow, |client| {
+                    let current_song = client.get_current_song()?;
+                    match (&mut format, current_song) {
+                        (Some(format), song) => format.print(song.as_ref(), &client.get_status()?, output.bar)?,
+                        (None, Some(song)) => println!("{}", serde_json::ser::to_string(&song)?),
+                        // Nothing is playing right now but there might be later
+                        (None, None) if output.follow => println!("null"),
+                        (None, None) => std::process::exit(1),
+                    }
+                    Ok(())
+                })?;
+            }
+            Command::Search(args) => args.execute(client, false)?,
+            Command::Find(args) => args.execute(client, true)?,
+            Command::Watch { subsystem } => {
+                let subsystems = subsystem.into_iter().map(Into::into).collect_vec();
+                loop {
+                    let events = client.idle(&subsystems)?;
+                    let status = client.get_status()?;
+                    let song = client.get_current_song()?;
+                    for event in events {
+                        println!(
+                            "{}",
+                            serde_json::ser::to_string(&WatchEvent {
+                                event,
+                                status: &status,
+                                song: song.as_ref(),
+                            })?
+                        );
+                    }
                 }
             }
             Command::Mount { ref name, ref path } => client.mount(name, path)?,
             Command::Unmount { ref name } => client.unmount(name)?,
             Command::ListMounts => println!("{}", serde_json::ser::to_string(&client.list_mounts()?)?),
+            Command::SendMessage { channel, content } => client.send_message(&channel, &content)?,
             Command::AlbumArt { output } => {
                 let Some(song) = client.get_current_song()? else {
                     std::process::exit(3);
@@ -212,3 +374,480 @@ pub fn create_env<'a>(
 
     Ok(result)
 }
+
+impl QueueCommand {
+    fn execute(self, client: &mut impl MpdClient) -> Result<()> {
+        match self {
+            QueueCommand::List => {}
+            QueueCommand::Move { from, to, id: true } => {
+                let to = supported_position(client, to.parse()?)?;
+                client.move_id(from.parse()?, to)?;
+            }
+            QueueCommand::Move { from, to, id: false } => {
+                let to = supported_position(client, to.parse()?)?;
+                client.move_in_queue(&from.parse()?, to)?;
+            }
+            QueueCommand::Delete { songs, id: true } => {
+                client.delete_ids(&songs.iter().map(|id| id.parse()).collect::<Result<Vec<u32>, _>>()?)?;
+            }
+            QueueCommand::Delete { songs, id: false } => {
+                let mut ranges = songs
+                    .iter()
+                    .map(|range| range.parse())
+                    .collect::<Result<Vec<SingleOrRange>>>()?;
+                // Delete from the end so positions of the remaining songs do not shift
+                ranges.sort_by_key(|range| std::cmp::Reverse(range.start));
+                for range in &ranges {
+                    client.delete_from_queue(range)?;
+                }
+            }
+            QueueCommand::Insert { files, position } => {
+                let files = files.iter().map(String::as_str).collect_vec();
+                let ids = if let Some(position) = position {
+                    let position = supported_position(client, position.parse()?)?;
+                    client.add_ids(&files, Some(position))?
+                } else {
+                    let status = client.get_status()?;
+                    let capabilities = Capabilities::fetch(client)?;
+                    client.insert_after_current(&files, &status, &capabilities)?
+                };
+                println!("{}", serde_json::ser::to_string(&ids.0)?);
+                return Ok(());
+            }
+            QueueCommand::Shuffle { range } => client.shuffle(range.map(|range| range.parse()).transpose()?)?,
+            QueueCommand::Swap {
+                first,
+                second,
+                id: true,
+            } => client.swap_ids(first, second)?,
+            QueueCommand::Swap {
+                first,
+                second,
+                id: false,
+            } => client.swap(first as usize, second as usize)?,
+        }
+
+        println!(
+            "{}",
+            serde_json::ser::to_string(&client.playlist_info()?.unwrap_or_default())?
+        );
+        Ok(())
+    }
+}
+
+impl PlaylistCommand {
+    fn execute(self, client: &mut impl MpdClient) -> Result<()> {
+        match self {
+            PlaylistCommand::List => println!("{}", serde_json::ser::to_string(&client.list_playlists()?)?),
+            PlaylistCommand::Show { name } => {
+                println!(
+                    "{}",
+                    serde_json::ser::to_string(&client.list_playlist_info(&name, None)?)?
+                );
+            }
+            PlaylistCommand::Load { name } => client.load_playlist(&name)?,
+            PlaylistCommand::Save { name, mode } => client.save_queue_as_playlist(&name, mode.map(Into::into))?,
+            PlaylistCommand::Rename { name, new_name } => client.rename_playlist(&name, &new_name)?,
+            PlaylistCommand::Delete { name } => client.delete_playlist(&name)?,
+            PlaylistCommand::Add { name, files, position } => {
+                for (idx, file) in files.iter().enumerate() {
+                    client.add_to_playlist(&name, file, position.map(|position| position + idx))?;
+                }
+            }
+            PlaylistCommand::Remove { name, songs } => {
+                let mut ranges = songs
+                    .iter()
+                    .map(|range| range.parse())
+                    .collect::<Result<Vec<SingleOrRange>>>()?;
+                // Remove from the end so positions of the remaining songs do not shift
+                ranges.sort_by_key(|range| std::cmp::Reverse(range.start));
+                for range in &ranges {
+                    client.delete_from_playlist(&name, range)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Positions relative to the current song need MPD 0.23, older versions get the same position
+/// counted from the start of the queue
+fn supported_position(client: &mut impl MpdClient, position: QueueMoveTarget) -> Result<QueueMoveTarget> {
+    if matches!(position, QueueMoveTarget::Absolute(_))
+        || Capabilities::fetch(client)?.supports(Feature::RelativePositions)
+    {
+        return Ok(position);
+    }
+    position
+        .into_absolute(client.get_status()?.song)
+        .context("Positions relative to the current song can only be used while a song is playing")
+}
+
+impl SearchArgs {
+    /// Runs `find` when `exact_case` is set and `search` otherwise
+    fn execute(self, client: &mut impl MpdClient, exact_case: bool) -> Result<()> {
+        let kind = self.mode.map_or(
+            if exact_case {
+                FilterKind::Exact
+            } else {
+                FilterKind::Contains
+            },
+            Into::into,
+        );
+        let filters = self
+            .filters
+            .iter()
+            .map(|filter| match filter.split_once('=') {
+                Some((tag, value)) if !tag.is_empty() => Ok(Filter::new_with_kind(tag, value, kind)),
+                _ => Err(anyhow!("Invalid filter '{filter}', expected TAG=VALUE")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.add || self.insert {
+            let status = client.get_status()?;
+            // Songs are appended when there is no current song to insert them after
+            let current = status.song.filter(|_| self.insert);
+            match (current, exact_case) {
+                (Some(current), _) if !Capabilities::fetch(client)?.supports(Feature::RelativePositions) => {
+                    // Adding at a position needs MPD 0.23, the songs are appended and moved instead
+                    if exact_case {
+                        client.find_add(&filters)?;
+                    } else {
+                        client.search_add(&filters)?;
+                    }
+                    let (start, end) = (
+                        status.playlistlength as usize,
+                        client.get_status()?.playlistlength as usize,
+                    );
+                    if end > start {
+                        client.move_in_queue(
+                            &SingleOrRange::range(start, end),
+                            QueueMoveTarget::Absolute(current as usize + 1),
+                        )?;
+                    }
+                }
+                (Some(_), true) => client.find_add_at(&filters, QueueMoveTarget::RelativeAdd(0))?,
+                (Some(_), false) => client.search_add_at(&filters, QueueMoveTarget::RelativeAdd(0))?,
+                (None, true) => client.find_add(&filters)?,
+                (None, false) => client.search_add(&filters)?,
+            }
+            return Ok(());
+        }
+
+        let songs = if exact_case {
+            client.find(&filters)?
+        } else {
+            client.search(&filters)?
+        };
+        if let Some(format) = self.format {
+            let format: SongFormat = SongFormatFile(parse_format(&format)?).try_into()?;
+            for song in &songs {
+                println!(
+                    "{}",
+                    format
+                        .0
+                        .iter()
+                        .filter_map(|property| property.as_string(Some(song)))
+                        .join("")
+                );
+            }
+        } else {
+            println!("{}", serde_json::ser::to_string(&songs)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a format given on the command line. Optional values do not have to be wrapped in
+/// `Some` just like in the theme.
+fn parse_format<T: DeserializeOwned>(format: &str) -> Result<T> {
+    Ok(ron::Options::default()
+        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+        .from_str(format)?)
+}
+
+/// Prints once and then again after every change in the player or mixer subsystem if `follow` is set
+fn follow<C: MpdClient>(client: &mut C, follow: bool, mut print: impl FnMut(&mut C) -> Result<()>) -> Result<()> {
+    print(client)?;
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        client.idle(&[IdleEvent::Player, IdleEvent::Mixer])?;
+        print(client)?;
+    }
+}
+
+/// Single line output of the status and song commands rendered from the same properties as the
+/// header
+struct LineFormat {
+    properties: Vec<&'static Property<'static, PropertyKind>>,
+    /// Displayed by the `Server` status property
+    server: &'static str,
+    /// Whether anything was printed yet, status bar protocols expect a header before the first line
+    started: bool,
+}
+
+impl LineFormat {
+    fn new(format: &str, server: &'static str) -> Result<Self> {
+        Ok(Self {
+            properties: parse_format::<Vec<PropertyFile<PropertyKindFile>>>(format)?
+                .into_iter()
+                .map(TryInto::try_into)
+                .try_collect()?,
+            server,
+            started: false,
+        })
+    }
+
+    fn format(&self, song: Option<&Song>, status: &Status) -> String {
+        self.properties
+            .iter()
+            .filter_map(|property| property.as_span(song, status, self.server))
+            .flat_map(|spans| spans.either(|span| vec![span], |spans| spans))
+            .map(|span| span.content)
+            .join("")
+    }
+
+    fn print(&mut self, song: Option<&Song>, status: &Status, bar: Option<StatusBar>) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        self.write(&mut stdout, song, status, bar)?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        out: &mut impl Write,
+        song: Option<&Song>,
+        status: &Status,
+        bar: Option<StatusBar>,
+    ) -> Result<()> {
+        let text = self.format(song, status);
+        let started = std::mem::replace(&mut self.started, true);
+        match bar {
+            None => writeln!(out, "{text}")?,
+            Some(StatusBar::Waybar) => {
+                let class = status.state.as_ref().to_lowercase();
+                let percentage = (status.elapsed.as_millis() * 100)
+                    .checked_div(status.duration.as_millis())
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "{}",
+                    serde_json::json!({
+                        "text": text,
+                        "tooltip": text,
+                        "alt": class,
+                        "class": class,
+                        "percentage": u64::try_from(percentage).unwrap_or(100),
+                    })
+                )?;
+            }
+            Some(StatusBar::I3bar) => {
+                // The protocol is a header followed by an endless array of status lines
+                if !started {
+                    writeln!(out, "{}", serde_json::json!({ "version": 1 }))?;
+                    writeln!(out, "[")?;
+                }
+                writeln!(out, "{},", serde_json::json!([{ "name": "rmpc", "full_text": text }]))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Line printed by the watch command for every idle event
+#[derive(Serialize)]
+struct WatchEvent<'a> {
+    event: IdleEvent,
+    status: &'a Status,
+    song: Option<&'a Song>,
+}
+
+fn read_stdin_paths() -> Result<Vec<String>> {
+    Ok(std::io::stdin()
+        .lock()
+        .lines()
+        .map_ok(|line| line.trim().to_owned())
+        .filter_ok(|line| !line.is_empty())
+        .collect::<Result<_, _>>()?)
+}
+
+fn replace_stdin_paths(files: Vec<String>) -> Result<Vec<String>> {
+    if !files.iter().any(|file| file == "-") {
+        return Ok(files);
+    }
+
+    let mut stdin_paths = Some(read_stdin_paths()?);
+    Ok(files
+        .into_iter()
+        .flat_map(|file| {
+            if file == "-" {
+                stdin_paths.take().unwrap_or_default()
+            } else {
+                vec![file]
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::HashMap;
+
+    use clap::Parser;
+    use test_case::test_case;
+
+    use super::LineFormat;
+    use crate::{
+        config::cli::{Args, Command, PlaylistCommand, SearchArgs, StatusBar},
+        mpd::{
+            client::Client,
+            commands::{Song, State, Status},
+            mpd_client::MpdClient,
+        },
+        tests::fixtures::{
+            config,
+            mpd_server::{FakeMpdServer, FakeSong},
+        },
+    };
+
+    fn run(command: PlaylistCommand, client: &mut Client<'_>) -> Result<(), Option<i32>> {
+        let cmd = Command::Playlist { command };
+        cmd.clone()
+            .execute(client, Box::leak(Box::new(config())), |_, _| {})
+            .map_err(|err| cmd.exit_code(&err))
+    }
+
+    #[test]
+    fn playlist_commands_exit_with_documented_codes() {
+        let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac"), FakeSong::new("02.flac")]);
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        client.add("01.flac").unwrap();
+
+        let add = PlaylistCommand::Add {
+            name: "list".to_owned(),
+            files: vec!["01.flac".to_owned(), "02.flac".to_owned(), "01.flac".to_owned()],
+            position: None,
+        };
+        assert_eq!(run(add, &mut client), Ok(()));
+        let remove = PlaylistCommand::Remove {
+            name: "list".to_owned(),
+            songs: vec!["0".to_owned(), "2".to_owned()],
+        };
+        assert_eq!(run(remove, &mut client), Ok(()));
+        assert_eq!(server.state().playlists["list"], ["02.flac"]);
+
+        let load_missing = PlaylistCommand::Load {
+            name: "missing".to_owned(),
+        };
+        assert_eq!(run(load_missing, &mut client), Err(Some(2)));
+        let save_existing = PlaylistCommand::Save {
+            name: "list".to_owned(),
+            mode: None,
+        };
+        assert_eq!(run(save_existing, &mut client), Err(Some(3)));
+        let remove_invalid = PlaylistCommand::Remove {
+            name: "list".to_owned(),
+            songs: vec!["2:1".to_owned()],
+        };
+        assert_eq!(run(remove_invalid, &mut client), Err(None));
+    }
+
+    #[test_case("0.24.0"; "with position")]
+    #[test_case("0.22.0"; "with append and move")]
+    fn search_inserts_matching_songs_after_current_song(version: &'static str) {
+        let server = FakeMpdServer::start().with_library([
+            FakeSong::new("01.flac").with_tag("Artist", "Foo"),
+            FakeSong::new("02.flac").with_tag("Artist", "Bar"),
+            FakeSong::new("03.flac").with_tag("Artist", "foobar"),
+        ]);
+        server.state().version = version;
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        client.add_multiple(&["02.flac", "02.flac"]).unwrap();
+        client.play_pos(0).unwrap();
+
+        Command::Search(SearchArgs {
+            filters: vec!["artist=foo".to_owned()],
+            mode: None,
+            format: None,
+            add: false,
+            insert: true,
+        })
+        .execute(&mut client, Box::leak(Box::new(config())), |_, _| {})
+        .unwrap();
+
+        let queue = server
+            .state()
+            .queue
+            .iter()
+            .map(|song| song.file.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(queue, ["02.flac", "01.flac", "03.flac", "02.flac"]);
+        let moved = server
+            .state()
+            .commands
+            .iter()
+            .any(|command| command.starts_with("move "));
+        assert_eq!(moved, version == "0.22.0");
+    }
+
+    #[test]
+    fn formats_line_from_header_properties() {
+        let format = LineFormat::new(
+            r#"[(kind: Property(Song(Title)), default: (kind: Text("none"))), (kind: Text(" | ")), (kind: Property(Status(State))), (kind: Text(" @ ")), (kind: Property(Status(Server)))]"#,
+            "nas",
+        )
+        .unwrap();
+        let song = Song {
+            metadata: HashMap::from([("title".to_owned(), "foo".to_owned())]),
+            ..Default::default()
+        };
+        let status = Status {
+            state: State::Pause,
+            ..Default::default()
+        };
+
+        assert_eq!(format.format(Some(&song), &status), "foo | Paused @ nas");
+        assert_eq!(format.format(None, &status), "none | Paused @ nas");
+    }
+
+    #[test]
+    fn only_watch_and_follow_run_until_interrupted() {
+        let runs_until_interrupted = |args: &[&str]| {
+            Args::try_parse_from(["rmpc"].iter().chain(args))
+                .unwrap()
+                .command
+                .unwrap()
+                .runs_until_interrupted()
+        };
+
+        assert!(runs_until_interrupted(&["watch"]));
+        assert!(runs_until_interrupted(&["status", "--follow"]));
+        assert!(runs_until_interrupted(&["song", "--follow"]));
+        assert!(!runs_until_interrupted(&["status"]));
+        assert!(!runs_until_interrupted(&["song", "--path", "01.flac"]));
+    }
+
+    #[test]
+    fn i3bar_output_starts_with_protocol_header() {
+        let mut format = LineFormat::new(r"[(kind: Property(Status(State)))]", "nas").unwrap();
+        let status = Status::default();
+        let mut out = Vec::new();
+
+        format.write(&mut out, None, &status, Some(StatusBar::I3bar)).unwrap();
+        format.write(&mut out, None, &status, Some(StatusBar::I3bar)).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"version\":1}\n[\n[{\"full_text\":\"Stopped\",\"name\":\"rmpc\"}],\n[{\"full_text\":\"Stopped\",\"name\":\"rmpc\"}],\n"
+        );
+    }
+}
@@ -34,6 +34,14 @@ impl<'a> Default for MpdAddress<'a> {
     }
 }
 
+impl<'a> MpdAddress<'a> {
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            MpdAddress::IpAndPort(addr) | MpdAddress::SocketPath(addr) => addr,
+        }
+    }
+}
+
 impl MpdAddress<'static> {
     pub fn resolve(
         addr_from_cli: Option<String>,
@@ -56,7 +64,10 @@ impl MpdAddress<'static> {
         (cfg_addr, cfg_pw)
     }
 
-    fn resolve_config(addr: String, pw: Option<String>) -> (MpdAddress<'static>, Option<MpdPassword<'static>>) {
+    pub(super) fn resolve_config(
+        addr: String,
+        pw: Option<String>,
+    ) -> (MpdAddress<'static>, Option<MpdPassword<'static>>) {
         let expanded = tilde_expand(&addr);
         let addr = if expanded.starts_with('/') {
             MpdAddress::SocketPath(expanded.into_owned().leak())
@@ -13,6 +13,12 @@ pub struct Args {
     #[arg(short, long)]
     /// Override the MPD password
     pub password: Option<String>,
+    #[arg(long, value_name = "NAME")]
+    /// Connect to the server profile with the given name from the config file
+    pub profile: Option<String>,
+    #[arg(long, value_name = "FILE")]
+    /// Record all communication with MPD into the given file. Useful for bug reports, the password is not recorded.
+    pub transcript: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Clone, Debug, PartialEq)]
@@ -23,6 +29,8 @@ pub enum Command {
         /// If provided, print the current config instead of the default one.
         #[arg(short, long, default_value = "false")]
         current: bool,
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
     },
     /// Prints the default theme. Can be used to bootstrap your theme file.
     Theme {
@@ -91,6 +99,19 @@ pub enum Command {
     Single { value: OnOffOneshot },
     /// On, off or oneshot
     Consume { value: OnOffOneshot },
+    /// Sets crossfade between songs in seconds, 0 disables it. Prints current crossfade if no
+    /// argument is given.
+    Crossfade { value: Option<u32> },
+    /// Sets the mixramp threshold in decibels. Prints current threshold if no argument is given.
+    MixRampDb {
+        #[arg(allow_negative_numbers(true))]
+        value: Option<f32>,
+    },
+    /// Sets the mixramp delay in seconds, 'off' disables mixramp. Prints current delay if no
+    /// argument is given.
+    MixRampDelay { value: Option<String> },
+    /// Sets the replay gain mode. Prints current mode if no argument is given.
+    ReplayGain { mode: Option<ReplayGainMode> },
     /// Seeks current song(seconds), relative if prefixed by + or -
     Seek {
         #[arg(allow_negative_numbers(true))]
@@ -98,10 +119,26 @@ pub enum Command {
     },
     /// Clear the current queue
     Clear,
-    /// Add a song to the current queue. Relative to music database root. '/' to add all files to the queue
-    Add { file: String },
+    /// Add songs to the current queue. Relative to music database root. '/' to add all files to the queue.
+    /// Paths are read from stdin, one per line, if '-' is given or if no path is given and stdin is not a terminal.
+    Add { files: Vec<String> },
     /// Add a song from youtube to the current queue.
     AddYt { url: String },
+    /// Manage stored playlists.
+    /// Exit codes:
+    ///   * 0: Success
+    ///   * 1: Error
+    ///   * 2: Playlist or song not found
+    ///   * 3: Playlist already exists
+    Playlist {
+        #[command(subcommand)]
+        command: PlaylistCommand,
+    },
+    /// Inspect and edit the current queue
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
     /// List MPD outputs
     Outputs,
     /// Toggle MPD output on or off
@@ -122,13 +159,32 @@ pub enum Command {
     /// List MPD decoder plugins
     Decoders,
     /// Prints various information like the playback status
-    Status,
+    Status {
+        #[command(flatten)]
+        output: OutputArgs,
+    },
     /// Prints info about the current song.
     /// If --path specified, prints information about the song at the given path instead.
     /// If --path is specified multiple times, prints an array containing all the songs.
     Song {
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "follow")]
         path: Option<Vec<String>>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Searches the database for songs matching all TAG=VALUE filters, ignoring case. Prints the
+    /// songs as JSON unless --format is given.
+    Search(SearchArgs),
+    /// Finds songs in the database matching all TAG=VALUE filters, respecting case. Prints the
+    /// songs as JSON unless --format is given.
+    Find(SearchArgs),
+    /// Prints a JSON object with the event, status and current song on every change reported by
+    /// MPD until interrupted
+    Watch {
+        /// Only report changes in the given subsystems, can be given multiple times or as a comma
+        /// separated list. Reports all changes if not given.
+        #[arg(short, long, value_delimiter = ',')]
+        subsystem: Vec<Subsystem>,
     },
     /// Mounts supported storage to MPD
     Mount { name: String, path: String },
@@ -136,6 +192,133 @@ pub enum Command {
     Unmount { name: String },
     /// List currently mounted storages
     ListMounts,
+    /// Sends a message to the given MPD channel. Running rmpc instances subscribed to the
+    /// channel show it in the status bar or pass it to the configured command.
+    SendMessage {
+        /// Name of the channel
+        channel: String,
+        /// Text of the message
+        content: String,
+    },
+    /// Controls running rmpc instances through their control socket. The command is sent to all
+    /// running instances unless --pid is given.
+    Remote {
+        /// Process id of the rmpc instance to control
+        #[arg(long)]
+        pid: Option<u32>,
+        #[command(subcommand)]
+        command: RemoteCommand,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum ConfigCommand {
+    /// Checks the config and the theme it uses for errors and conflicting keybinds. Exits with 1
+    /// when any problem is found.
+    Validate,
+    /// Rewrites the config file to replace deprecated options with their current equivalent. The
+    /// original file is kept next to it with a .bak extension. Comments are not preserved.
+    Migrate {
+        /// Only print the changes and the migrated config without modifying the config file
+        #[arg(long, default_value = "false")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum QueueCommand {
+    /// Prints all songs in the queue as JSON
+    List,
+    /// Moves songs to a different position in the queue. Prints the resulting queue as JSON.
+    Move {
+        /// Position or START:END range of positions of the songs to move. Id of the song if --id is given.
+        from: String,
+        /// Target position, relative to the current song if prefixed by + or -
+        #[arg(allow_hyphen_values(true))]
+        to: String,
+        /// Treat FROM as a song id instead of a position
+        #[arg(long, default_value = "false")]
+        id: bool,
+    },
+    /// Deletes songs from the queue. Prints the resulting queue as JSON.
+    Delete {
+        /// Positions or START:END ranges of positions of the songs to delete. Ids of the songs if --id is given.
+        #[arg(required = true)]
+        songs: Vec<String>,
+        /// Treat SONGS as song ids instead of positions
+        #[arg(long, default_value = "false")]
+        id: bool,
+    },
+    /// Inserts songs right after the currently playing song or at the given position. Prints ids of
+    /// the inserted songs as JSON. Paths are read from stdin, one per line, if '-' is given.
+    Insert {
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Position to insert the songs at, relative to the current song if prefixed by + or -
+        #[arg(short, long, allow_hyphen_values(true))]
+        position: Option<String>,
+    },
+    /// Shuffles the whole queue or only the given START:END range of positions. Prints the
+    /// resulting queue as JSON.
+    Shuffle { range: Option<String> },
+    /// Swaps two songs in the queue. Prints the resulting queue as JSON.
+    Swap {
+        /// Position of the first song. Id of the song if --id is given.
+        first: u32,
+        /// Position of the second song. Id of the song if --id is given.
+        second: u32,
+        /// Treat FIRST and SECOND as song ids instead of positions
+        #[arg(long, default_value = "false")]
+        id: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum RemoteCommand {
+    /// Runs a global action the same way as when configured in keybinds, eg. `NextTab` or `SwitchToTab("Library")`
+    Action { action: String },
+    /// Simulates a key press, eg. 'j' or '<C-p>'. The key is handled by whatever is focused in the
+    /// running instance, including pane specific keybinds.
+    Key { key: String },
+    /// Switches to the tab with the given name
+    SwitchTab { name: String },
+    /// Shows a message in the status bar
+    Status {
+        message: String,
+        #[arg(short, long, default_value = "info")]
+        level: StatusLevel,
+    },
+    /// Opens the given modal
+    Modal { modal: ModalKind },
+    /// Switches to the tab with the queue and selects the currently playing song
+    JumpToCurrent,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum StatusLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum ModalKind {
+    /// List of all keybinds
+    Help,
+    /// Tags of the currently playing song
+    SongInfo,
+    /// MPD outputs
+    Outputs,
+    /// MPD decoder plugins
+    Decoders,
+    /// MPD partitions
+    Partitions,
+    /// Crossfade, mixramp and replay gain settings
+    PlaybackOptions,
+    /// Server profiles from the config
+    Servers,
 }
 
 #[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
@@ -156,6 +339,154 @@ pub enum OnOffOneshot {
     Oneshot,
 }
 
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum PlaylistCommand {
+    /// Prints names and modification times of all stored playlists as JSON
+    List,
+    /// Prints all songs in the playlist as JSON
+    Show { name: String },
+    /// Appends songs from the playlist to the current queue
+    Load { name: String },
+    /// Saves the current queue as a playlist
+    Save {
+        name: String,
+        /// What to do when the playlist already exists. Fails by default. Requires MPD 0.24 or newer.
+        #[arg(short, long)]
+        mode: Option<SaveMode>,
+    },
+    /// Renames the playlist
+    Rename { name: String, new_name: String },
+    /// Deletes the playlist
+    Delete { name: String },
+    /// Adds songs to the playlist, creating it if it does not exist. Paths are read from stdin, one
+    /// per line, if '-' is given.
+    Add {
+        name: String,
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Position in the playlist to insert the songs at, appends to its end if not given
+        #[arg(short, long)]
+        position: Option<usize>,
+    },
+    /// Removes songs from the playlist
+    Remove {
+        name: String,
+        /// Positions or START:END ranges of positions of the songs to remove
+        #[arg(required = true)]
+        songs: Vec<String>,
+    },
+}
+
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct OutputArgs {
+    /// Print a single line in the given format instead of JSON. Uses the same syntax as the header
+    /// in the theme, eg. '[(kind: Property(Song(Title))), (kind: Text(" - ")), (kind: Property(Status(Elapsed)))]'
+    #[arg(short, long)]
+    pub format: Option<String>,
+    /// Print again whenever playback or volume changes
+    #[arg(long, default_value = "false")]
+    pub follow: bool,
+    /// Wrap the formatted line in JSON understood by the given status bar
+    #[arg(long, requires = "format")]
+    pub bar: Option<StatusBar>,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum StatusBar {
+    /// Custom module of waybar with "return-type": "json"
+    Waybar,
+    /// A block of the i3bar protocol
+    I3bar,
+}
+
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct SearchArgs {
+    /// Filters in the TAG=VALUE format, eg. artist=foo. 'any' matches any tag and 'file' the path of the song.
+    #[arg(required = true)]
+    pub filters: Vec<String>,
+    /// How values are matched. Defaults to exact for find and contains for search.
+    #[arg(short, long)]
+    pub mode: Option<FilterKind>,
+    /// Print each song on its own line in the given format instead of JSON. Uses the same syntax
+    /// as song formats in the theme, eg. '[(kind: Property(Artist)), (kind: Text(" - ")), (kind: Property(Title))]'
+    #[arg(short, long)]
+    pub format: Option<String>,
+    /// Append the matching songs to the queue instead of printing them
+    #[arg(long, default_value = "false", conflicts_with = "insert")]
+    pub add: bool,
+    /// Insert the matching songs right after the currently playing song instead of printing them
+    #[arg(long, default_value = "false")]
+    pub insert: bool,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum FilterKind {
+    /// The value has to match exactly
+    Exact,
+    /// The value has to contain the given text
+    Contains,
+    /// The value has to start with the given text
+    StartsWith,
+    /// The value has to match the given regular expression
+    Regex,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+#[clap(rename_all = "snake_case")]
+pub enum Subsystem {
+    /// Playback was started, stopped or seeked or tags of the current song changed
+    Player,
+    /// Volume changed
+    Mixer,
+    /// The queue changed
+    Playlist,
+    /// Options like repeat, random, crossfade or replay gain changed
+    Options,
+    /// The database was modified after an update
+    Database,
+    /// A database update started or finished
+    Update,
+    /// A stored playlist was modified, renamed, created or deleted
+    StoredPlaylist,
+    /// An output was added, removed or modified
+    Output,
+    /// A partition was added, removed or changed
+    Partition,
+    /// The sticker database was modified
+    Sticker,
+    /// A client subscribed to or unsubscribed from a channel
+    Subscription,
+    /// A message was received on a subscribed channel
+    Message,
+    /// A neighbor was found or lost
+    Neighbor,
+    /// The mount list changed
+    Mount,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum SaveMode {
+    /// Create a new playlist, fails if it already exists
+    Create,
+    /// Append the queue to the existing playlist
+    Append,
+    /// Replace the existing playlist with the queue
+    Replace,
+}
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum ReplayGainMode {
+    /// Disable replay gain
+    Off,
+    /// Use track gain
+    Track,
+    /// Use album gain
+    Album,
+    /// Use album gain when playing in order and track gain when random is on
+    Auto,
+}
+
 fn get_default_config_path() -> PathBuf {
     let mut path = PathBuf::new();
     if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
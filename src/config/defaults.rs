@@ -36,6 +36,13 @@ pub fn mpd_port() -> String {
     "6600".to_string()
 }
 
+pub fn default_channels() -> Vec<super::ChannelFile> {
+    vec![super::ChannelFile {
+        name: "rmpc".to_owned(),
+        on_message: None,
+    }]
+}
+
 pub fn disabled_album_art_protos() -> Vec<String> {
     ["http://", "https://"].into_iter().map(|p| p.to_owned()).collect()
 }
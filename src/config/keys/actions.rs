@@ -1,7 +1,10 @@
 use itertools::Itertools;
 use strum::Display;
 
-use crate::config::{tabs::TabName, utils::tilde_expand};
+use crate::{
+    config::{tabs::TabName, utils::tilde_expand},
+    mpd::capabilities::Feature,
+};
 
 use super::ToDescription;
 
@@ -14,6 +17,9 @@ pub enum GlobalAction {
     ShowCurrentSongInfo,
     ShowOutputs,
     ShowDecoders,
+    ShowPartitions,
+    ShowPlaybackOptions,
+    ShowServers,
     NextTrack,
     PreviousTrack,
     Stop,
@@ -26,10 +32,15 @@ pub enum GlobalAction {
     VolumeDown,
     SeekForward,
     SeekBack,
+    CrossfadeUp,
+    CrossfadeDown,
+    CycleReplayGainMode,
     CommandMode,
     NextTab,
     PreviousTab,
     SwitchToTab(TabName),
+    RateCurrentSong(u8),
+    RateSelectedSong(u8),
     Command {
         command: &'static str,
         description: Option<&'static str>,
@@ -47,6 +58,9 @@ pub enum GlobalActionFile {
     ShowCurrentSongInfo,
     ShowOutputs,
     ShowDecoders,
+    ShowPartitions,
+    ShowPlaybackOptions,
+    ShowServers,
     NextTrack,
     PreviousTrack,
     Stop,
@@ -59,6 +73,9 @@ pub enum GlobalActionFile {
     VolumeDown,
     SeekForward,
     SeekBack,
+    CrossfadeUp,
+    CrossfadeDown,
+    CycleReplayGainMode,
     NextTab,
     PreviousTab,
     SwitchToTab(String),
@@ -69,6 +86,8 @@ pub enum GlobalActionFile {
     PlaylistsTab,
     SearchTab,
     CommandMode,
+    RateCurrentSong(u8),
+    RateSelectedSong(u8),
     Command {
         command: String,
         description: Option<String>,
@@ -85,6 +104,9 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::Quit => GlobalAction::Quit,
             GlobalActionFile::ShowOutputs => GlobalAction::ShowOutputs,
             GlobalActionFile::ShowDecoders => GlobalAction::ShowDecoders,
+            GlobalActionFile::ShowPartitions => GlobalAction::ShowPartitions,
+            GlobalActionFile::ShowPlaybackOptions => GlobalAction::ShowPlaybackOptions,
+            GlobalActionFile::ShowServers => GlobalAction::ShowServers,
             GlobalActionFile::ShowCurrentSongInfo => GlobalAction::ShowCurrentSongInfo,
             GlobalActionFile::CommandMode => GlobalAction::CommandMode,
             GlobalActionFile::Command { command, description } => GlobalAction::Command {
@@ -103,6 +125,9 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::SeekBack => GlobalAction::SeekBack,
             GlobalActionFile::VolumeDown => GlobalAction::VolumeDown,
             GlobalActionFile::VolumeUp => GlobalAction::VolumeUp,
+            GlobalActionFile::CrossfadeUp => GlobalAction::CrossfadeUp,
+            GlobalActionFile::CrossfadeDown => GlobalAction::CrossfadeDown,
+            GlobalActionFile::CycleReplayGainMode => GlobalAction::CycleReplayGainMode,
             GlobalActionFile::PreviousTab => GlobalAction::PreviousTab,
             GlobalActionFile::NextTab => GlobalAction::NextTab,
             GlobalActionFile::ToggleConsume => GlobalAction::ToggleConsume,
@@ -113,6 +138,8 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::AlbumsTab => GlobalAction::SwitchToTab("Albums".into()),
             GlobalActionFile::PlaylistsTab => GlobalAction::SwitchToTab("Playlists".into()),
             GlobalActionFile::SearchTab => GlobalAction::SwitchToTab("Search".into()),
+            GlobalActionFile::RateCurrentSong(rating) => GlobalAction::RateCurrentSong(rating),
+            GlobalActionFile::RateSelectedSong(rating) => GlobalAction::RateSelectedSong(rating),
             GlobalActionFile::ExternalCommand { command, description } => GlobalAction::ExternalCommand {
                 command: command
                     .into_iter()
@@ -125,12 +152,27 @@ impl From<GlobalActionFile> for GlobalAction {
     }
 }
 
+impl GlobalAction {
+    /// Server feature the action depends on, actions with unsupported features are disabled
+    pub fn required_feature(&self) -> Option<Feature> {
+        match self {
+            GlobalAction::RateCurrentSong(_) | GlobalAction::RateSelectedSong(_) => Some(Feature::Stickers),
+            GlobalAction::ShowPartitions => Some(Feature::Partitions),
+            GlobalAction::ShowDecoders => Some(Feature::Decoders),
+            _ => None,
+        }
+    }
+}
+
 impl ToDescription for GlobalAction {
     fn to_description(&self) -> &str {
         match self {
             GlobalAction::Quit => "Exit rmpc",
             GlobalAction::ShowOutputs => "Show MPD outputs config",
             GlobalAction::ShowDecoders => "Show MPD decoder plugins",
+            GlobalAction::ShowPartitions => "Show MPD partitions and switch between them",
+            GlobalAction::ShowPlaybackOptions => "Show and edit crossfade, MixRamp and ReplayGain settings",
+            GlobalAction::ShowServers => "Show server profiles and switch between them",
             GlobalAction::ShowCurrentSongInfo => "Show metadata of the currently playing song in a modal popup",
             GlobalAction::ToggleRepeat => "Toggle repeat",
             GlobalAction::ToggleSingle => {
@@ -146,6 +188,9 @@ impl ToDescription for GlobalAction {
             GlobalAction::PreviousTrack => "Play previous track in the queue",
             GlobalAction::SeekForward => "Seek currently playing track forwards",
             GlobalAction::SeekBack => "Seek currently playing track backwards",
+            GlobalAction::CrossfadeUp => "Increase crossfade by one second",
+            GlobalAction::CrossfadeDown => "Decrease crossfade by one second",
+            GlobalAction::CycleReplayGainMode => "Cycle ReplayGain mode between off, track, album and auto",
             GlobalAction::NextTab => "Switch to next tab",
             GlobalAction::PreviousTab => "Switch to previous tab",
             GlobalAction::SwitchToTab(TabName("Queue")) => "Switch directly to Queue tab",
@@ -155,6 +200,8 @@ impl ToDescription for GlobalAction {
             GlobalAction::SwitchToTab(TabName("Playlists")) => "Switch directly to Playlists tab",
             GlobalAction::SwitchToTab(TabName("Search")) => "Switch directly to Search tab",
             GlobalAction::SwitchToTab(name) => format!("Switch directly to {name} tab").leak(),
+            GlobalAction::RateCurrentSong(_) => "Rate currently playing song, rating of 0 removes it",
+            GlobalAction::RateSelectedSong(_) => "Rate selected or marked songs, rating of 0 removes it",
             GlobalAction::ShowHelp => "Show keybinds",
             GlobalAction::CommandMode => "Enter command mode",
             GlobalAction::Command { description: None, .. } => "Execute a command",
@@ -174,60 +221,94 @@ impl ToDescription for GlobalAction {
 // Albums actions
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum AlbumsActionsFile {}
+pub enum AlbumsActionsFile {
+    AddShuffled,
+}
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum AlbumsActions {}
+pub enum AlbumsActions {
+    AddShuffled,
+}
 
 impl From<AlbumsActionsFile> for AlbumsActions {
-    fn from(_value: AlbumsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: AlbumsActionsFile) -> Self {
+        match value {
+            AlbumsActionsFile::AddShuffled => AlbumsActions::AddShuffled,
+        }
     }
 }
 
 impl ToDescription for AlbumsActions {
     fn to_description(&self) -> &str {
-        ""
+        match self {
+            AlbumsActions::AddShuffled => {
+                "Add songs of the album under cursor or of the marked albums to the queue in random order"
+            }
+        }
     }
 }
 
 // Artists actions
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum ArtistsActionsFile {}
+pub enum ArtistsActionsFile {
+    AddShuffled,
+}
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum ArtistsActions {}
+pub enum ArtistsActions {
+    AddShuffled,
+}
 
 impl ToDescription for ArtistsActions {
     fn to_description(&self) -> &str {
-        ""
+        match self {
+            ArtistsActions::AddShuffled => {
+                "Add songs of the item under cursor or of the marked items to the queue in random order"
+            }
+        }
     }
 }
 
 impl From<ArtistsActionsFile> for ArtistsActions {
-    fn from(_value: ArtistsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: ArtistsActionsFile) -> Self {
+        match value {
+            ArtistsActionsFile::AddShuffled => ArtistsActions::AddShuffled,
+        }
     }
 }
 
 // Directories actions
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum DirectoriesActionsFile {}
+pub enum DirectoriesActionsFile {
+    Update,
+    Rescan,
+}
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum DirectoriesActions {}
+pub enum DirectoriesActions {
+    Update,
+    Rescan,
+}
 
 impl ToDescription for DirectoriesActions {
     fn to_description(&self) -> &str {
-        ""
+        match self {
+            DirectoriesActions::Update => "Update MPD's database for the directory or song under cursor",
+            DirectoriesActions::Rescan => {
+                "Update MPD's database for the directory or song under cursor. Also rescans unmodified files"
+            }
+        }
     }
 }
 
 impl From<DirectoriesActionsFile> for DirectoriesActions {
-    fn from(_value: DirectoriesActionsFile) -> Self {
-        unreachable!()
+    fn from(value: DirectoriesActionsFile) -> Self {
+        match value {
+            DirectoriesActionsFile::Update => DirectoriesActions::Update,
+            DirectoriesActionsFile::Rescan => DirectoriesActions::Rescan,
+        }
     }
 }
 
@@ -348,6 +429,8 @@ pub enum CommonActionFile {
     Confirm,
     FocusInput,
     AddAll,
+    InsertAfterCurrent,
+    AddWithPriority,
 }
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
@@ -377,6 +460,8 @@ pub enum CommonAction {
     Confirm,
     FocusInput,
     AddAll,
+    InsertAfterCurrent,
+    AddWithPriority,
 }
 
 impl ToDescription for CommonAction {
@@ -398,6 +483,8 @@ impl ToDescription for CommonAction {
             CommonAction::Select => "Mark current item as selected in the browser, useful for example when you want to add multiple songs to a playlist",
             CommonAction::Add => "Add item to queue",
             CommonAction::AddAll => "Add all items to queue",
+            CommonAction::InsertAfterCurrent => "Insert item into the queue right after the currently playing song",
+            CommonAction::AddWithPriority => "Add item to queue with the highest priority so it is played next even in random mode",
             CommonAction::Delete => "Delete. For example a playlist, song from a playlist or wipe the current queue",
             CommonAction::Rename => "Rename. Currently only for playlists",
             CommonAction::Close => "Close/Stop whatever action is currently going on. Cancel filter, close a modal, etc.",
@@ -435,6 +522,8 @@ impl From<CommonActionFile> for CommonAction {
             CommonActionFile::Confirm => CommonAction::Confirm,
             CommonActionFile::FocusInput => CommonAction::FocusInput,
             CommonActionFile::AddAll => CommonAction::AddAll,
+            CommonActionFile::InsertAfterCurrent => CommonAction::InsertAfterCurrent,
+            CommonActionFile::AddWithPriority => CommonAction::AddWithPriority,
             CommonActionFile::PaneUp => CommonAction::PaneUp,
             CommonActionFile::PaneDown => CommonAction::PaneDown,
             CommonActionFile::PaneLeft => CommonAction::PaneLeft,
@@ -446,37 +535,59 @@ impl From<CommonActionFile> for CommonAction {
 // Playlist actions
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum PlaylistsActionsFile {}
+pub enum PlaylistsActionsFile {
+    NewPlaylist,
+    Duplicate,
+}
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum PlaylistsActions {}
+pub enum PlaylistsActions {
+    NewPlaylist,
+    Duplicate,
+}
 
 impl ToDescription for PlaylistsActions {
     fn to_description(&self) -> &str {
-        ""
+        match self {
+            PlaylistsActions::NewPlaylist => "Create a new empty playlist",
+            PlaylistsActions::Duplicate => "Create a copy of the playlist under cursor or of the opened playlist",
+        }
     }
 }
 
 impl From<PlaylistsActionsFile> for PlaylistsActions {
-    fn from(_value: PlaylistsActionsFile) -> Self {
-        unreachable!()
+    fn from(value: PlaylistsActionsFile) -> Self {
+        match value {
+            PlaylistsActionsFile::NewPlaylist => PlaylistsActions::NewPlaylist,
+            PlaylistsActionsFile::Duplicate => PlaylistsActions::Duplicate,
+        }
     }
 }
 
+// Search actions
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone)]
-pub enum SearchActionsFile {}
+pub enum SearchActionsFile {
+    ResetForm,
+}
 
 #[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum SearchActions {}
+pub enum SearchActions {
+    ResetForm,
+}
 
 impl ToDescription for SearchActions {
     fn to_description(&self) -> &str {
-        ""
+        match self {
+            SearchActions::ResetForm => "Clear all search inputs and results",
+        }
     }
 }
 
 impl From<SearchActionsFile> for SearchActions {
-    fn from(_value: SearchActionsFile) -> Self {
-        unreachable!()
+    fn from(value: SearchActionsFile) -> Self {
+        match value {
+            SearchActionsFile::ResetForm => SearchActions::ResetForm,
+        }
     }
 }
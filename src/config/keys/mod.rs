@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
 use actions::{
-    AlbumsActionsFile, ArtistsActionsFile, CommonActionFile, DirectoriesActionsFile, GlobalActionFile,
-    PlaylistsActionsFile, QueueActionsFile,
+    AlbumsActionsFile, ArtistsActionsFile, CommonActionFile, DirectoriesActionsFile, PlaylistsActionsFile,
+    QueueActionsFile, SearchActionsFile,
 };
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use serde::{Deserialize, Serialize};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_with::serde_as;
 
 #[cfg(debug_assertions)]
 pub use actions::LogsActions;
@@ -13,8 +15,8 @@ pub use actions::LogsActions;
 use actions::LogsActionsFile;
 
 pub use actions::{
-    AlbumsActions, ArtistsActions, CommonAction, DirectoriesActions, GlobalAction, PlaylistsActions, QueueActions,
-    SearchActions,
+    AlbumsActions, ArtistsActions, CommonAction, DirectoriesActions, GlobalAction, GlobalActionFile, PlaylistsActions,
+    QueueActions, SearchActions,
 };
 pub use key::Key;
 
@@ -37,25 +39,34 @@ pub struct KeyConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct KeyConfigFile {
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_by_key")]
     pub global: HashMap<Key, GlobalActionFile>,
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_by_key")]
     pub navigation: HashMap<Key, CommonActionFile>,
-    // pub albums: HashMap<AlbumsActions, Vec<Key>>,
-    // pub artists: HashMap<ArtistsActions, Vec<Key>>,
-    // pub directories: HashMap<DirectoriesActions, Vec<Key>>,
-    // pub playlists: HashMap<PlaylistsActions, Vec<Key>>,
-    // pub search: HashMap<SearchActions, Vec<Key>>,
+    #[serde(default, serialize_with = "sorted_by_key")]
+    pub albums: HashMap<Key, AlbumsActionsFile>,
+    #[serde(default, serialize_with = "sorted_by_key")]
+    pub artists: HashMap<Key, ArtistsActionsFile>,
+    #[serde(default, serialize_with = "sorted_by_key")]
+    pub directories: HashMap<Key, DirectoriesActionsFile>,
+    #[serde(default, serialize_with = "sorted_by_key")]
+    pub playlists: HashMap<Key, PlaylistsActionsFile>,
+    #[serde(default, serialize_with = "sorted_by_key")]
+    pub search: HashMap<Key, SearchActionsFile>,
     #[cfg(debug_assertions)]
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_by_key")]
     pub logs: HashMap<Key, LogsActionsFile>,
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_by_key")]
     pub queue: HashMap<Key, QueueActionsFile>,
 }
 
+/// Keeps the order of keybinds stable when the config is written back
+fn sorted_by_key<S: Serializer, V: Serialize>(map: &HashMap<Key, V>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().sorted_by_cached_key(|(key, _)| key.to_string()))
+}
+
 impl Default for KeyConfigFile {
     #[rustfmt::skip]
-    #[allow(unused_imports)]
     fn default() -> Self {
         use GlobalActionFile as G;
         use CommonActionFile as C;
@@ -63,6 +74,7 @@ impl Default for KeyConfigFile {
         use ArtistsActionsFile as Ar;
         use DirectoriesActionsFile  as D;
         use PlaylistsActionsFile as P;
+        use SearchActionsFile as S;
         use KeyCode as K;
         use KeyModifiers as M;
         #[cfg(debug_assertions)]
@@ -76,6 +88,7 @@ impl Default for KeyConfigFile {
                 (Key { key: K::Char('I'), modifiers: M::SHIFT }, G::ShowCurrentSongInfo),
                 (Key { key: K::Char('O'), modifiers: M::SHIFT }, G::ShowOutputs),
                 (Key { key: K::Char('P'), modifiers: M::SHIFT }, G::ShowDecoders),
+                (Key { key: K::Char('X'), modifiers: M::SHIFT }, G::ShowPlaybackOptions),
                 (Key { key: K::Char('>'), modifiers: M::NONE  }, G::NextTrack),
                 (Key { key: K::Char('<'), modifiers: M::NONE  }, G::PreviousTrack),
                 (Key { key: K::Char('s'), modifiers: M::NONE  }, G::Stop),
@@ -122,6 +135,8 @@ impl Default for KeyConfigFile {
                 (Key { key: K::Char(' '), modifiers: M::NONE    }, C::Select),
                 (Key { key: K::Char('a'), modifiers: M::NONE    }, C::Add),
                 (Key { key: K::Char('A'), modifiers: M::SHIFT   }, C::AddAll),
+                (Key { key: K::Char('e'), modifiers: M::NONE    }, C::InsertAfterCurrent),
+                (Key { key: K::Char('E'), modifiers: M::SHIFT   }, C::AddWithPriority),
                 (Key { key: K::Char('D'), modifiers: M::SHIFT   }, C::Delete),
                 (Key { key: K::Char('r'), modifiers: M::NONE    }, C::Rename),
                 (Key { key: K::Char('c'), modifiers: M::CONTROL }, C::Close),
@@ -129,14 +144,23 @@ impl Default for KeyConfigFile {
                 (Key { key: K::Enter,     modifiers: M::NONE    }, C::Confirm),
                 (Key { key: K::Char('i'), modifiers: M::NONE    }, C::FocusInput),
             ]),
-            // albums: HashMap::from([
-            // ]),
-            // artists: HashMap::from([
-            // ]),
-            // directories: HashMap::from([
-            // ]),
-            // playlists: HashMap::from([
-            // ]),
+            albums: HashMap::from([
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, Al::AddShuffled),
+            ]),
+            artists: HashMap::from([
+                (Key { key: K::Char('S'), modifiers: M::SHIFT   }, Ar::AddShuffled),
+            ]),
+            directories: HashMap::from([
+                (Key { key: K::Char('u'), modifiers: M::NONE    }, D::Update),
+                (Key { key: K::Char('U'), modifiers: M::SHIFT   }, D::Rescan),
+            ]),
+            playlists: HashMap::from([
+                (Key { key: K::Char('n'), modifiers: M::CONTROL }, P::NewPlaylist),
+                (Key { key: K::Char('y'), modifiers: M::NONE    }, P::Duplicate),
+            ]),
+            search: HashMap::from([
+                (Key { key: K::Char('R'), modifiers: M::SHIFT   }, S::ResetForm),
+            ]),
             #[cfg(debug_assertions)]
             logs: HashMap::from([
                 (Key { key: K::Char('D'), modifiers: M::SHIFT   }, L::Clear),
@@ -155,20 +179,41 @@ impl Default for KeyConfigFile {
     }
 }
 
+impl KeyConfigFile {
+    /// Replaces deprecated actions with their current equivalent. Returns a description of every
+    /// replaced action.
+    pub fn migrate(&mut self) -> Vec<String> {
+        self.global
+            .iter_mut()
+            .filter_map(|(key, action)| {
+                let tab = match action {
+                    GlobalActionFile::QueueTab => "Queue",
+                    GlobalActionFile::DirectoriesTab => "Directories",
+                    GlobalActionFile::ArtistsTab => "Artists",
+                    GlobalActionFile::AlbumsTab => "Albums",
+                    GlobalActionFile::PlaylistsTab => "Playlists",
+                    GlobalActionFile::SearchTab => "Search",
+                    _ => return None,
+                };
+                let change = format!("`{action:?}` bound to '{key}' was replaced by `SwitchToTab(\"{tab}\")`");
+                *action = GlobalActionFile::SwitchToTab(tab.to_owned());
+                Some(change)
+            })
+            .sorted()
+            .collect()
+    }
+}
+
 impl From<KeyConfigFile> for KeyConfig {
     fn from(value: KeyConfigFile) -> Self {
         KeyConfig {
             global: value.global.into_iter().map(|(k, v)| (k, v.into())).collect(),
             navigation: value.navigation.into_iter().map(|(k, v)| (k, v.into())).collect(),
-            // albums: invert_map(value.albums),
-            // artists: invert_map(value.artists),
-            // directories: invert_map(value.directories),
-            // playlists: invert_map(value.playlists),
-            albums: HashMap::new(),
-            artists: HashMap::new(),
-            directories: HashMap::new(),
-            playlists: HashMap::new(),
-            search: HashMap::new(),
+            albums: value.albums.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            artists: value.artists.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            directories: value.directories.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            playlists: value.playlists.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            search: value.search.into_iter().map(|(k, v)| (k, v.into())).collect(),
             #[cfg(debug_assertions)]
             logs: value.logs.into_iter().map(|(k, v)| (k, v.into())).collect(),
             queue: value.queue.into_iter().map(|(k, v)| (k, v.into())).collect(),
@@ -176,6 +221,96 @@ impl From<KeyConfigFile> for KeyConfig {
     }
 }
 
+/// Keybinds in the order they are written in the config file including keys bound more than once
+/// which are silently dropped by [`KeyConfigFile`]. Only used to report conflicts.
+#[serde_as]
+#[derive(Debug, Default, Deserialize)]
+pub struct KeybindEntriesFile {
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    global: Vec<(Key, GlobalActionFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    navigation: Vec<(Key, CommonActionFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    albums: Vec<(Key, AlbumsActionsFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    artists: Vec<(Key, ArtistsActionsFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    directories: Vec<(Key, DirectoriesActionsFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    playlists: Vec<(Key, PlaylistsActionsFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    search: Vec<(Key, SearchActionsFile)>,
+    #[cfg(debug_assertions)]
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    logs: Vec<(Key, LogsActionsFile)>,
+    #[serde(default)]
+    #[serde_as(as = "serde_with::Map<_, _>")]
+    queue: Vec<(Key, QueueActionsFile)>,
+}
+
+impl KeybindEntriesFile {
+    /// Describes keys bound more than once in a section and global keybinds which never trigger
+    /// because panes handle the key first. Pane sections overriding navigation is intended.
+    pub fn conflicts(&self) -> Vec<String> {
+        fn duplicates<A: std::fmt::Debug>(section: &str, entries: &[(Key, A)]) -> Vec<String> {
+            entries
+                .iter()
+                .enumerate()
+                .filter(|(idx, (key, _))| entries[idx + 1..].iter().any(|(other, _)| other == key))
+                .map(|(_, (key, action))| {
+                    format!("Key '{key}' is bound more than once in keybinds.{section}, {action:?} is overridden")
+                })
+                .collect()
+        }
+
+        fn shadowed<A: std::fmt::Debug>(
+            global: &[(Key, GlobalActionFile)],
+            section: &str,
+            entries: &[(Key, A)],
+        ) -> Vec<String> {
+            global
+                .iter()
+                .filter_map(|(key, global_action)| {
+                    let (_, action) = entries.iter().rev().find(|(other, _)| other == key)?;
+                    Some(format!(
+                        "Key '{key}' bound to {global_action:?} in keybinds.global is shadowed by {action:?} in keybinds.{section}"
+                    ))
+                })
+                .collect()
+        }
+
+        let mut result = duplicates("global", &self.global);
+        result.extend(duplicates("navigation", &self.navigation));
+        result.extend(duplicates("albums", &self.albums));
+        result.extend(duplicates("artists", &self.artists));
+        result.extend(duplicates("directories", &self.directories));
+        result.extend(duplicates("playlists", &self.playlists));
+        result.extend(duplicates("search", &self.search));
+        #[cfg(debug_assertions)]
+        result.extend(duplicates("logs", &self.logs));
+        result.extend(duplicates("queue", &self.queue));
+        result.extend(shadowed(&self.global, "navigation", &self.navigation));
+        result.extend(shadowed(&self.global, "albums", &self.albums));
+        result.extend(shadowed(&self.global, "artists", &self.artists));
+        result.extend(shadowed(&self.global, "directories", &self.directories));
+        result.extend(shadowed(&self.global, "playlists", &self.playlists));
+        result.extend(shadowed(&self.global, "search", &self.search));
+        #[cfg(debug_assertions)]
+        result.extend(shadowed(&self.global, "logs", &self.logs));
+        result.extend(shadowed(&self.global, "queue", &self.queue));
+
+        result
+    }
+}
+
 impl From<KeyEvent> for Key {
     fn from(value: KeyEvent) -> Self {
         Self {
@@ -200,8 +335,8 @@ mod tests {
     #[cfg(debug_assertions)]
     use crate::config::keys::LogsActionsFile;
     use crate::config::keys::{
-        actions::{CommonActionFile, GlobalActionFile, QueueActionsFile},
-        CommonAction, GlobalAction, QueueActions,
+        actions::{CommonActionFile, DirectoriesActionsFile, GlobalActionFile, PlaylistsActionsFile, QueueActionsFile},
+        CommonAction, DirectoriesActions, GlobalAction, PlaylistsActions, QueueActions,
     };
 
     use super::{Key, KeyConfig, KeyConfigFile};
@@ -216,10 +351,11 @@ mod tests {
             logs: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, LogsActionsFile::Clear)]),
             queue: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, QueueActionsFile::Play),
                                   (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT, }, QueueActionsFile::Save)]),
-            // albums: HashMap::from([]),
-            // artists: HashMap::from([]),
-            // directories: HashMap::from([]),
-            // playlists: HashMap::from([]),
+            albums: HashMap::from([]),
+            artists: HashMap::from([]),
+            directories: HashMap::from([(Key { key: KeyCode::Char('u'), modifiers: KeyModifiers::NONE, }, DirectoriesActionsFile::Update)]),
+            playlists: HashMap::from([(Key { key: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, }, PlaylistsActionsFile::Duplicate)]),
+            search: HashMap::from([]),
             navigation: HashMap::from([
                 (Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL, }, CommonActionFile::Up),
                 (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT }, CommonActionFile::Up)
@@ -233,8 +369,8 @@ mod tests {
                                   (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT, }, QueueActions::Save)]),
             albums: HashMap::from([]),
             artists: HashMap::from([]),
-            directories: HashMap::from([]),
-            playlists: HashMap::from([]),
+            directories: HashMap::from([(Key { key: KeyCode::Char('u'), modifiers: KeyModifiers::NONE, }, DirectoriesActions::Update)]),
+            playlists: HashMap::from([(Key { key: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, }, PlaylistsActions::Duplicate)]),
             search: HashMap::from([]),
             navigation: HashMap::from([(Key { key: KeyCode::Char('a'), modifiers: KeyModifiers::CONTROL }, CommonAction::Up),
                                        (Key { key: KeyCode::Char('b'), modifiers: KeyModifiers::SHIFT }, CommonAction::Up)]),
@@ -0,0 +1,516 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    hash::Hash,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use super::{
+    keys::Key,
+    utils::{canonical_without_cycle, tilde_expand},
+    AlbumArtConfigFile, ConfigFile, KeyConfigFile, SearchFile,
+};
+
+/// Names of the fields a config file sets, including fields of nested structs. Needed because the
+/// deserialized [`ConfigFile`] cannot tell a default value from one set explicitly.
+#[derive(Debug, Default)]
+pub(super) struct FieldSet(HashMap<String, FieldSet>);
+
+impl FieldSet {
+    fn from_value(value: ron::Value) -> Self {
+        match value {
+            ron::Value::Map(map) => Self(
+                map.into_iter()
+                    .filter_map(|(key, value)| match key {
+                        ron::Value::String(key) => Some((key, Self::from_value(value))),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => Self::default(),
+        }
+    }
+
+    fn contains(&self, field: &str) -> bool {
+        self.0.contains_key(field)
+    }
+
+    fn get(&self, field: &str) -> Option<&FieldSet> {
+        self.0.get(field)
+    }
+
+    fn extend(&mut self, other: FieldSet) {
+        for (field, nested) in other.0 {
+            self.0.entry(field).or_default().extend(nested);
+        }
+    }
+
+    /// Returns whether the field was set
+    pub(super) fn remove(&mut self, field: &str) -> bool {
+        self.0.remove(field).is_some()
+    }
+
+    /// Marks the field at the given path of nested fields as set
+    pub(super) fn insert(&mut self, path: &[&str]) {
+        if let Some((field, nested)) = path.split_first() {
+            self.0.entry((*field).to_owned()).or_default().insert(nested);
+        }
+    }
+}
+
+/// Reads the config at the given path together with the files it includes and the host specific
+/// override `<name>.<hostname>.ron` next to it. Included files are applied first, the file which
+/// includes them on top of them and the host override last.
+pub(super) fn read(path: &Path) -> Result<ConfigFile> {
+    let mut layers = Vec::new();
+    collect_layers(path, &mut Vec::new(), &mut layers)?;
+    if let Some(host_path) = host_override_path(path).filter(|host_path| host_path.exists()) {
+        collect_layers(&host_path, &mut Vec::new(), &mut layers)
+            .with_context(|| format!("Failed to read host config '{}'", host_path.display()))?;
+    }
+
+    // Fields no file sets keep the defaults they get when deserializing a file
+    let mut layers = layers.into_iter();
+    let (mut result, mut result_fields) = layers.next().unwrap_or_default();
+    for (file, fields) in layers {
+        result.files.extend(file.files.iter().cloned());
+        merge(&mut result, &result_fields, file, &fields);
+        result_fields.extend(fields);
+    }
+    result.include = Vec::new();
+
+    Ok(result)
+}
+
+/// Reads only the given file without resolving includes together with the fields it sets
+pub(super) fn read_file(path: &Path) -> Result<(ConfigFile, FieldSet)> {
+    let content = std::fs::read_to_string(path)?;
+    let file: ConfigFile = ron::de::from_str(&content)?;
+    let fields = FieldSet::from_value(ron::de::from_str(&content)?);
+
+    Ok((file, fields))
+}
+
+/// Adds the layers of the file at the given path, its includes first. `stack` contains the files
+/// which include the current one to detect cycles.
+fn collect_layers(path: &Path, stack: &mut Vec<PathBuf>, layers: &mut Vec<(ConfigFile, FieldSet)>) -> Result<()> {
+    let canonical = canonical_without_cycle(path, stack, "Config files include each other")?;
+
+    let (mut file, fields) = read_file(path)?;
+    file.files = vec![path.to_path_buf()];
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    stack.push(canonical);
+    for include in &file.include {
+        let include_path = dir.join(tilde_expand(include).as_ref());
+        collect_layers(&include_path, stack, layers)
+            .with_context(|| format!("Failed to read included config '{}'", include_path.display()))?;
+    }
+    stack.pop();
+    layers.push((file, fields));
+
+    Ok(())
+}
+
+fn host_override_path(path: &Path) -> Option<PathBuf> {
+    let hostname = rustix::system::uname().nodename().to_string_lossy().into_owned();
+    let stem = path.file_stem()?.to_string_lossy();
+    let extension = path
+        .extension()
+        .map_or_else(String::new, |ext| format!(".{}", ext.to_string_lossy()));
+
+    Some(path.with_file_name(format!("{stem}.{hostname}{extension}")))
+}
+
+/// Applies fields set by the layer on top of the result. A field set for the first time replaces
+/// the default value. Fields already set by a previous layer are merged, keybinds are added to
+/// the existing ones and nested structs are merged field by field.
+fn merge(result: &mut ConfigFile, result_fields: &FieldSet, layer: ConfigFile, fields: &FieldSet) {
+    macro_rules! replace_set {
+        ($($field:ident),+ $(,)?) => {
+            $(if fields.contains(stringify!($field)) {
+                result.$field = $field;
+            })+
+        };
+    }
+
+    let ConfigFile {
+        address,
+        password,
+        cache_dir,
+        theme,
+        volume_step,
+        scrolloff,
+        wrap_navigation,
+        status_update_interval_ms,
+        select_current_song_on_change,
+        enable_mouse,
+        keybinds,
+        image_method,
+        album_art_max_size_px,
+        album_art,
+        on_song_change,
+        channels,
+        search,
+        tabs,
+        servers,
+        include: _,
+        files: _,
+    } = layer;
+
+    replace_set!(
+        address,
+        password,
+        cache_dir,
+        theme,
+        volume_step,
+        scrolloff,
+        wrap_navigation,
+        status_update_interval_ms,
+        select_current_song_on_change,
+        enable_mouse,
+        image_method,
+        album_art_max_size_px,
+        on_song_change,
+        channels,
+        tabs,
+        servers,
+    );
+
+    match (result_fields.get("keybinds"), fields.get("keybinds")) {
+        (Some(result_fields), Some(fields)) => {
+            let sections = (result_fields, fields);
+            merge_keybinds(&mut result.keybinds.global, keybinds.global, "global", sections);
+            merge_keybinds(
+                &mut result.keybinds.navigation,
+                keybinds.navigation,
+                "navigation",
+                sections,
+            );
+            #[cfg(debug_assertions)]
+            merge_keybinds(&mut result.keybinds.logs, keybinds.logs, "logs", sections);
+            merge_keybinds(&mut result.keybinds.queue, keybinds.queue, "queue", sections);
+            merge_keybinds(&mut result.keybinds.albums, keybinds.albums, "albums", sections);
+            merge_keybinds(&mut result.keybinds.artists, keybinds.artists, "artists", sections);
+            merge_keybinds(
+                &mut result.keybinds.directories,
+                keybinds.directories,
+                "directories",
+                sections,
+            );
+            merge_keybinds(
+                &mut result.keybinds.playlists,
+                keybinds.playlists,
+                "playlists",
+                sections,
+            );
+            merge_keybinds(&mut result.keybinds.search, keybinds.search, "search", sections);
+        }
+        (None, Some(_)) => result.keybinds = keybinds,
+        (_, None) => {}
+    }
+
+    match (result_fields.contains("search"), fields.get("search")) {
+        (true, Some(fields)) => {
+            let (result, search) = (&mut result.search, search);
+            if fields.contains("case_sensitive") {
+                result.case_sensitive = search.case_sensitive;
+            }
+            if fields.contains("mode") {
+                result.mode = search.mode;
+            }
+            if fields.contains("tags") {
+                result.tags = search.tags;
+            }
+        }
+        (false, Some(_)) => result.search = search,
+        (_, None) => {}
+    }
+
+    match (result_fields.contains("album_art"), fields.get("album_art")) {
+        (true, Some(fields)) => {
+            let (result, album_art) = (&mut result.album_art, album_art);
+            if fields.contains("method") {
+                result.method = album_art.method;
+            }
+            if fields.contains("max_size_px") {
+                result.max_size_px = album_art.max_size_px;
+            }
+            if fields.contains("disabled_protocols") {
+                result.disabled_protocols = album_art.disabled_protocols;
+            }
+        }
+        (false, Some(_)) => result.album_art = album_art,
+        (_, None) => {}
+    }
+}
+
+fn merge_keybinds<K: Eq + Hash, V>(
+    result: &mut HashMap<K, V>,
+    layer: HashMap<K, V>,
+    section: &str,
+    (result_fields, fields): (&FieldSet, &FieldSet),
+) {
+    match (result_fields.contains(section), fields.contains(section)) {
+        (true, true) => result.extend(layer),
+        (false, true) => *result = layer,
+        (_, false) => {}
+    }
+}
+
+/// Writes only the fields the file sets, so the values of the files it includes are kept when the
+/// result is read again. Keybinds, `search` and `album_art` are written field by field the same
+/// way as [`merge`] merges them.
+pub(super) fn to_ron(config: &ConfigFile, fields: &FieldSet) -> Result<String> {
+    macro_rules! write_set {
+        ($out:ident, $fields:ident, $depth:literal, $($field:ident),+ $(,)?) => {
+            $(if $fields.contains(stringify!($field)) {
+                write_field(&mut $out, $depth, stringify!($field), $field)?;
+            })+
+        };
+    }
+
+    let ConfigFile {
+        address,
+        password,
+        cache_dir,
+        theme,
+        volume_step,
+        scrolloff,
+        wrap_navigation,
+        status_update_interval_ms,
+        select_current_song_on_change,
+        enable_mouse,
+        keybinds,
+        image_method,
+        album_art_max_size_px,
+        album_art,
+        on_song_change,
+        channels,
+        search,
+        tabs,
+        servers,
+        include,
+        files: _,
+    } = config;
+
+    let mut out = String::from("#![enable(implicit_some)]\n(\n");
+    write_set!(
+        out,
+        fields,
+        1,
+        include,
+        address,
+        password,
+        cache_dir,
+        theme,
+        volume_step,
+        scrolloff,
+        wrap_navigation,
+        status_update_interval_ms,
+        select_current_song_on_change,
+        enable_mouse,
+        image_method,
+        album_art_max_size_px,
+        on_song_change,
+        channels,
+        tabs,
+        servers,
+    );
+
+    if let Some(fields) = fields.get("keybinds") {
+        let KeyConfigFile {
+            global,
+            navigation,
+            albums,
+            artists,
+            directories,
+            playlists,
+            search,
+            #[cfg(debug_assertions)]
+            logs,
+            queue,
+        } = keybinds;
+        let (global, navigation, albums, artists) =
+            (&sorted(global), &sorted(navigation), &sorted(albums), &sorted(artists));
+        let (directories, playlists, search, queue) = (
+            &sorted(directories),
+            &sorted(playlists),
+            &sorted(search),
+            &sorted(queue),
+        );
+
+        out.push_str("    keybinds: (\n");
+        write_set!(
+            out,
+            fields,
+            2,
+            global,
+            navigation,
+            albums,
+            artists,
+            directories,
+            playlists,
+            search,
+            queue
+        );
+        #[cfg(debug_assertions)]
+        {
+            let logs = &sorted(logs);
+            write_set!(out, fields, 2, logs);
+        }
+        out.push_str("    ),\n");
+    }
+
+    if let Some(fields) = fields.get("album_art") {
+        let AlbumArtConfigFile {
+            method,
+            max_size_px,
+            disabled_protocols,
+        } = album_art;
+        out.push_str("    album_art: (\n");
+        write_set!(out, fields, 2, method, max_size_px, disabled_protocols);
+        out.push_str("    ),\n");
+    }
+
+    if let Some(fields) = fields.get("search") {
+        let SearchFile {
+            case_sensitive,
+            mode,
+            tags,
+        } = search;
+        out.push_str("    search: (\n");
+        write_set!(out, fields, 2, case_sensitive, mode, tags);
+        out.push_str("    ),\n");
+    }
+    out.push_str(")\n");
+
+    Ok(out)
+}
+
+/// Keybinds sorted by key so they are written in a stable order
+fn sorted<V>(keybinds: &HashMap<Key, V>) -> BTreeMap<String, &V> {
+    keybinds.iter().map(|(key, action)| (key.to_string(), action)).collect()
+}
+
+fn write_field(out: &mut String, depth: usize, name: &str, value: &impl Serialize) -> Result<()> {
+    let indent = "    ".repeat(depth);
+    let value = ron::Options::default()
+        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+        .to_string_pretty(value, ron::ser::PrettyConfig::default())?
+        .replace('\n', &format!("\n{indent}"));
+    writeln!(out, "{indent}{name}: {value},")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        config::{
+            keys::{GlobalActionFile, Key},
+            ConfigFile, ImageMethodFile,
+        },
+        tests::fixtures::temp_dir::TempDir,
+    };
+
+    #[test]
+    fn includes_are_merged_field_by_field() {
+        let dir = TempDir::new("layers");
+        let path = dir.write(
+            "config.ron",
+            r#"#![enable(implicit_some)]
+            (
+                include: ["shared.ron"],
+                volume_step: 10,
+                keybinds: (global: {"x": Stop}),
+                search: (case_sensitive: true),
+            )"#,
+        );
+        dir.write(
+            "shared.ron",
+            r#"(
+                volume_step: 2,
+                scrolloff: 3,
+                keybinds: (
+                    global: {"q": Quit, "x": ToggleRandom},
+                    navigation: {"j": Down},
+                ),
+                search: (case_sensitive: false, mode: Exact, tags: [(label: "Genre", value: "genre")]),
+            )"#,
+        );
+
+        let config = ConfigFile::read(&path).unwrap();
+
+        assert_eq!(config.volume_step, 10);
+        assert_eq!(config.scrolloff, 3);
+        assert_eq!(config.keybinds.global.len(), 2);
+        assert_eq!(
+            config.keybinds.global[&"q".parse::<Key>().unwrap()],
+            GlobalActionFile::Quit
+        );
+        assert_eq!(
+            config.keybinds.global[&"x".parse::<Key>().unwrap()],
+            GlobalActionFile::Stop
+        );
+        assert_eq!(config.keybinds.navigation.len(), 1);
+        assert!(config.search.case_sensitive);
+        assert_eq!(config.search.tags.len(), 1);
+        assert_eq!(config.files.len(), 2);
+    }
+
+    #[test]
+    fn single_file_is_read_as_before() {
+        let content = r#"(volume_step: 7, keybinds: (global: {"q": Quit}))"#;
+        let dir = TempDir::new("layers");
+        let path = dir.write("config.ron", content);
+
+        let config = ConfigFile::read(&path).unwrap();
+
+        let expected = ConfigFile {
+            files: vec![path],
+            ..ron::de::from_str(content).unwrap()
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn migrate_keeps_values_of_included_files() {
+        let dir = TempDir::new("layers");
+        let path = dir.write(
+            "config.ron",
+            r#"(include: ["shared.ron"], image_method: Some(Kitty), keybinds: (global: {"1": QueueTab}))"#,
+        );
+        dir.write("shared.ron", r#"(volume_step: 2, keybinds: (global: {"q": Quit}))"#);
+
+        let (changes, migrated) = ConfigFile::migrate_file(&path).unwrap();
+        std::fs::write(&path, migrated).unwrap();
+        let config = ConfigFile::read(&path).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(config.volume_step, 2);
+        assert_eq!(config.album_art.method, ImageMethodFile::Kitty);
+        assert_eq!(config.image_method, None);
+        assert_eq!(config.keybinds.global.len(), 2);
+        assert_eq!(
+            config.keybinds.global[&"1".parse::<Key>().unwrap()],
+            GlobalActionFile::SwitchToTab("Queue".to_owned())
+        );
+        assert!(config.keybinds.navigation.is_empty());
+    }
+
+    #[test]
+    fn include_cycle_is_an_error() {
+        let dir = TempDir::new("layers");
+        let path = dir.write("config.ron", r#"(include: ["a.ron"])"#);
+        dir.write("a.ron", r#"(include: ["config.ron"])"#);
+
+        let err = ConfigFile::read(&path).unwrap_err();
+
+        assert!(format!("{err:#}").contains("include each other"), "{err:#}");
+    }
+}
@@ -5,9 +5,8 @@ use address::MpdPassword;
 use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
-use cli::{Args, OnOff, OnOffOneshot};
+use cli::{Args, FilterKind, ModalKind, OnOff, OnOffOneshot, ReplayGainMode, SaveMode, StatusLevel, Subsystem};
 use itertools::Itertools;
-use rustix::path::Arg;
 use search::SearchFile;
 use serde::{Deserialize, Serialize};
 use strum::Display;
@@ -18,9 +17,11 @@ pub mod address;
 pub mod cli;
 mod defaults;
 pub mod keys;
+mod layers;
 mod search;
 pub mod tabs;
 pub mod theme;
+pub mod validate;
 
 use crate::shared::image;
 use crate::shared::image::ImageProtocol;
@@ -89,8 +90,34 @@ pub struct Config {
     pub theme: UiConfig,
     pub album_art: AlbumArtConfig,
     pub on_song_change: Option<&'static [&'static str]>,
+    pub channels: &'static [Channel],
     pub search: Search,
     pub tabs: Tabs,
+    pub servers: Vec<ServerProfile>,
+}
+
+impl Config {
+    /// Tags rmpc always needs for browsing and sorting regardless of the configured formats
+    const REQUIRED_TAGS: [&'static str; 6] = ["Artist", "AlbumArtist", "Album", "Title", "Track", "Disc"];
+
+    /// Tags which are displayed or searched by the current configuration. Might contain values
+    /// which are not tags, eg. `any` from search, those are dropped when checked against the server.
+    pub fn song_tags(&self) -> Vec<&'static str> {
+        let mut tags = Self::REQUIRED_TAGS.to_vec();
+        self.theme.for_each_song_property(|prop| tags.extend(prop.tag()));
+        tags.extend(self.search.tags.iter().map(|tag| tag.value));
+
+        tags
+    }
+
+    /// Name of the server profile rmpc is connected to or the address if it does not belong to
+    /// any profile
+    pub fn server_name(&self) -> &'static str {
+        self.servers
+            .iter()
+            .find(|server| server.address == self.address && server.password == self.password)
+            .map_or_else(|| self.address.as_str(), |server| server.name)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -117,18 +144,30 @@ pub struct ConfigFile {
     enable_mouse: bool,
     #[serde(default)]
     keybinds: KeyConfigFile,
-    #[serde(default)]
+    /// Deprecated, replaced by `album_art.method`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     image_method: Option<ImageMethodFile>,
-    #[serde(default)]
-    album_art_max_size_px: Size,
+    /// Deprecated, replaced by `album_art.max_size_px`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    album_art_max_size_px: Option<Size>,
     #[serde(default)]
     pub album_art: AlbumArtConfigFile,
     #[serde(default)]
     on_song_change: Option<Vec<String>>,
+    #[serde(default = "defaults::default_channels")]
+    channels: Vec<ChannelFile>,
     #[serde(default)]
     search: SearchFile,
     #[serde(default)]
     tabs: TabsFile,
+    #[serde(default)]
+    servers: Vec<ServerProfileFile>,
+    /// Config files merged below this one, relative to its directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Files this config was read from
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -141,6 +180,37 @@ pub struct AlbumArtConfigFile {
     pub disabled_protocols: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ChannelFile {
+    name: String,
+    #[serde(default)]
+    on_message: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ServerProfileFile {
+    name: String,
+    address: String,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+/// Named MPD server rmpc can switch to at runtime or be started with via `--profile`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerProfile {
+    pub name: &'static str,
+    pub address: MpdAddress<'static>,
+    pub password: Option<MpdPassword<'static>>,
+}
+
+/// MPD channel rmpc subscribes to. Messages are shown in the status bar
+/// unless `on_message` command is configured.
+#[derive(Debug, Default, Clone)]
+pub struct Channel {
+    pub name: &'static str,
+    pub on_message: Option<&'static [&'static str]>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct AlbumArtConfig {
     pub method: ImageMethod,
@@ -160,14 +230,18 @@ impl Default for ConfigFile {
             cache_dir: None,
             image_method: None,
             select_current_song_on_change: false,
-            album_art_max_size_px: Size::default(),
+            album_art_max_size_px: None,
             album_art: AlbumArtConfigFile {
                 disabled_protocols: defaults::disabled_album_art_protos(),
                 ..Default::default()
             },
             on_song_change: None,
+            channels: defaults::default_channels(),
             search: SearchFile::default(),
             tabs: TabsFile::default(),
+            servers: Vec::new(),
+            include: Vec::new(),
+            files: Vec::new(),
             enable_mouse: true,
             wrap_navigation: false,
             password: None,
@@ -176,12 +250,48 @@ impl Default for ConfigFile {
 }
 
 impl ConfigFile {
-    pub fn read(path: &PathBuf) -> Result<Self> {
-        let file = std::fs::File::open(path)?;
-        let read = std::io::BufReader::new(file);
-        let config: ConfigFile = ron::de::from_reader(read)?;
+    /// Reads the config together with its includes and the override for the current host
+    pub fn read(path: &Path) -> Result<Self> {
+        layers::read(path).with_context(|| format!("Failed to read config file '{}'", path.display()))
+    }
 
-        Ok(config)
+    /// Migrates only the given file without resolving includes. Returns a description of every
+    /// change and the migrated file, which contains only the fields set in the original file so
+    /// it does not override values of the files it includes.
+    pub fn migrate_file(path: &Path) -> Result<(Vec<String>, String)> {
+        let (mut config, mut fields) = layers::read_file(path)?;
+        let changes = config.migrate();
+        if fields.remove("image_method") {
+            fields.insert(&["album_art", "method"]);
+        }
+        fields.remove("album_art_max_size_px");
+
+        Ok((changes, layers::to_ron(&config, &fields)?))
+    }
+
+    /// Moves values of deprecated fields to the fields which replaced them. Returns a description
+    /// of every change, empty if the config already follows the current schema.
+    pub fn migrate(&mut self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if let Some(method) = self.image_method.take() {
+            self.album_art.method = method;
+            changes.push("`image_method` was replaced by `album_art.method`".to_owned());
+        }
+        // The old field was never read, moving its value would change the size album art is shown at
+        if self.album_art_max_size_px.take().is_some() {
+            changes.push(
+                "`album_art_max_size_px` was removed because it had no effect, use `album_art.max_size_px` instead"
+                    .to_owned(),
+            );
+        }
+        changes.extend(self.keybinds.migrate());
+
+        changes
+    }
+
+    /// Deprecated fields and values this config uses
+    pub fn deprecations(&self) -> Vec<String> {
+        self.clone().migrate()
     }
 
     pub fn theme_path(&self, config_dir: &Path) -> Option<PathBuf> {
@@ -193,16 +303,8 @@ impl ConfigFile {
     }
 
     fn read_theme(&self, config_dir: &Path) -> Result<UiConfigFile> {
-        self.theme_path(config_dir).map_or_else(
-            || Ok(UiConfigFile::default()),
-            |path| {
-                let file = std::fs::File::open(&path)
-                    .with_context(|| format!("Failed to open theme file {:?}", path.to_string_lossy()))?;
-                let read = std::io::BufReader::new(file);
-                let theme: UiConfigFile = ron::de::from_reader(read)?;
-                Ok(theme)
-            },
-        )
+        self.theme_path(config_dir)
+            .map_or_else(|| Ok(UiConfigFile::default()), |path| UiConfigFile::read(&path))
     }
 
     pub fn into_config(
@@ -210,6 +312,7 @@ impl ConfigFile {
         config_path: Option<&Path>,
         address_cli: Option<String>,
         password_cli: Option<String>,
+        profile_cli: Option<String>,
         is_cli: bool,
     ) -> Result<Config> {
         let theme: UiConfig = config_path
@@ -218,8 +321,32 @@ impl ConfigFile {
             .unwrap_or_default()
             .try_into()?;
 
+        let deprecations = self.deprecations();
         let size = self.album_art.max_size_px;
-        let (address, password) = MpdAddress::resolve(address_cli, password_cli, self.address, self.password);
+        let servers = self
+            .servers
+            .into_iter()
+            .map(|server| {
+                let (address, password) = MpdAddress::resolve_config(server.address, server.password);
+                ServerProfile {
+                    name: server.name.leak(),
+                    address,
+                    password,
+                }
+            })
+            .collect_vec();
+        // Explicitly chosen profile takes precedence over everything but an address passed on the
+        // command line
+        let (address, password) = match profile_cli {
+            Some(name) if address_cli.is_none() => {
+                let server = servers
+                    .iter()
+                    .find(|server| server.name == name)
+                    .with_context(|| format!("Server profile '{name}' does not exist"))?;
+                (server.address, server.password)
+            }
+            _ => MpdAddress::resolve(address_cli, password_cli, self.address, self.password),
+        };
         let mut config = Config {
             theme,
             cache_dir: self.cache_dir.map(|v| -> &'static str {
@@ -259,12 +386,31 @@ impl ConfigFile {
                     .collect_vec()
                     .leak() as &'static [_]
             }),
+            channels: self
+                .channels
+                .into_iter()
+                .map(|channel| Channel {
+                    name: channel.name.leak(),
+                    on_message: channel.on_message.map(|arr| {
+                        arr.into_iter()
+                            .map(|v| tilde_expand(&v).into_owned().leak() as &'static str)
+                            .collect_vec()
+                            .leak() as &'static [_]
+                    }),
+                })
+                .collect_vec()
+                .leak(),
+            servers,
         };
 
         if is_cli {
             return Ok(config);
         }
 
+        for deprecation in deprecations {
+            status_warn!("Deprecated config: {deprecation}. Run 'rmpc config migrate' to update your config.");
+        }
+
         let is_tmux = tmux::is_inside_tmux();
         if is_tmux && !tmux::is_passthrough_enabled()? {
             tmux::enable_passthrough()?;
@@ -336,6 +482,83 @@ impl From<OnOffOneshot> for crate::mpd::commands::status::OnOffOneshot {
     }
 }
 
+impl From<ReplayGainMode> for crate::mpd::commands::ReplayGainMode {
+    fn from(value: ReplayGainMode) -> Self {
+        match value {
+            ReplayGainMode::Off => crate::mpd::commands::ReplayGainMode::Off,
+            ReplayGainMode::Track => crate::mpd::commands::ReplayGainMode::Track,
+            ReplayGainMode::Album => crate::mpd::commands::ReplayGainMode::Album,
+            ReplayGainMode::Auto => crate::mpd::commands::ReplayGainMode::Auto,
+        }
+    }
+}
+
+impl From<SaveMode> for crate::mpd::mpd_client::SaveMode {
+    fn from(value: SaveMode) -> Self {
+        match value {
+            SaveMode::Create => crate::mpd::mpd_client::SaveMode::Create,
+            SaveMode::Append => crate::mpd::mpd_client::SaveMode::Append,
+            SaveMode::Replace => crate::mpd::mpd_client::SaveMode::Replace,
+        }
+    }
+}
+
+impl From<FilterKind> for crate::mpd::mpd_client::FilterKind {
+    fn from(value: FilterKind) -> Self {
+        match value {
+            FilterKind::Exact => crate::mpd::mpd_client::FilterKind::Exact,
+            FilterKind::Contains => crate::mpd::mpd_client::FilterKind::Contains,
+            FilterKind::StartsWith => crate::mpd::mpd_client::FilterKind::StartsWith,
+            FilterKind::Regex => crate::mpd::mpd_client::FilterKind::Regex,
+        }
+    }
+}
+
+impl From<Subsystem> for crate::mpd::commands::IdleEvent {
+    fn from(value: Subsystem) -> Self {
+        match value {
+            Subsystem::Player => crate::mpd::commands::IdleEvent::Player,
+            Subsystem::Mixer => crate::mpd::commands::IdleEvent::Mixer,
+            Subsystem::Playlist => crate::mpd::commands::IdleEvent::Playlist,
+            Subsystem::Options => crate::mpd::commands::IdleEvent::Options,
+            Subsystem::Database => crate::mpd::commands::IdleEvent::Database,
+            Subsystem::Update => crate::mpd::commands::IdleEvent::Update,
+            Subsystem::StoredPlaylist => crate::mpd::commands::IdleEvent::StoredPlaylist,
+            Subsystem::Output => crate::mpd::commands::IdleEvent::Output,
+            Subsystem::Partition => crate::mpd::commands::IdleEvent::Partition,
+            Subsystem::Sticker => crate::mpd::commands::IdleEvent::Sticker,
+            Subsystem::Subscription => crate::mpd::commands::IdleEvent::Subscription,
+            Subsystem::Message => crate::mpd::commands::IdleEvent::Message,
+            Subsystem::Neighbor => crate::mpd::commands::IdleEvent::Neighbor,
+            Subsystem::Mount => crate::mpd::commands::IdleEvent::Mount,
+        }
+    }
+}
+
+impl From<StatusLevel> for crate::ui::Level {
+    fn from(value: StatusLevel) -> Self {
+        match value {
+            StatusLevel::Info => crate::ui::Level::Info,
+            StatusLevel::Warn => crate::ui::Level::Warn,
+            StatusLevel::Error => crate::ui::Level::Error,
+        }
+    }
+}
+
+impl From<ModalKind> for keys::GlobalActionFile {
+    fn from(value: ModalKind) -> Self {
+        match value {
+            ModalKind::Help => keys::GlobalActionFile::ShowHelp,
+            ModalKind::SongInfo => keys::GlobalActionFile::ShowCurrentSongInfo,
+            ModalKind::Outputs => keys::GlobalActionFile::ShowOutputs,
+            ModalKind::Decoders => keys::GlobalActionFile::ShowDecoders,
+            ModalKind::Partitions => keys::GlobalActionFile::ShowPartitions,
+            ModalKind::PlaybackOptions => keys::GlobalActionFile::ShowPlaybackOptions,
+            ModalKind::Servers => keys::GlobalActionFile::ShowServers,
+        }
+    }
+}
+
 pub trait Leak {
     fn leak(self) -> &'static Self;
 }
@@ -348,7 +571,10 @@ impl<T> Leak for T {
 
 pub mod utils {
     use std::borrow::Cow;
-    use std::path::MAIN_SEPARATOR;
+    use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+    use anyhow::{bail, Result};
+    use itertools::Itertools;
 
     use crate::shared::env::ENV;
 
@@ -370,6 +596,21 @@ pub mod utils {
         Cow::Borrowed(inp)
     }
 
+    /// Canonical path of a file which is read on behalf of the files in `stack`. Errors with
+    /// `description` followed by the chain of files when the file is already in `stack`.
+    pub fn canonical_without_cycle(path: &Path, stack: &[PathBuf], description: &str) -> Result<PathBuf> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if stack.contains(&canonical) {
+            bail!(
+                "{description}: {} -> {}",
+                stack.iter().map(|p| p.display().to_string()).join(" -> "),
+                canonical.display()
+            );
+        }
+
+        Ok(canonical)
+    }
+
     #[cfg(test)]
     #[allow(clippy::unwrap_used)]
     mod tests {
@@ -415,11 +656,15 @@ pub mod utils {
 #[allow(clippy::unwrap_used)]
 mod tests {
 
+    use itertools::Itertools;
     use walkdir::WalkDir;
 
     #[cfg(debug_assertions)]
     use crate::config::keys::KeyConfigFile;
-    use crate::config::{theme::UiConfigFile, ConfigFile};
+    use crate::config::{
+        address::MpdPassword, keys::GlobalActionFile, theme::UiConfigFile, ConfigFile, ImageMethodFile, MpdAddress,
+        Size,
+    };
 
     #[test]
     #[cfg(debug_assertions)]
@@ -450,6 +695,31 @@ mod tests {
         assert_eq!(config, f);
     }
 
+    #[test]
+    fn profile_selects_server() {
+        let file: ConfigFile = ron::de::from_str(
+            r#"(
+                servers: [
+                    (name: "desktop", address: "127.0.0.1:6600"),
+                    (name: "nas", address: "192.168.1.10:6600", password: Some("secret")),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        let config = file
+            .clone()
+            .into_config(None, None, None, Some("nas".to_owned()), true)
+            .unwrap();
+
+        assert_eq!(config.address, MpdAddress::IpAndPort("192.168.1.10:6600"));
+        assert_eq!(config.password, Some(MpdPassword("secret")));
+        assert_eq!(config.server_name(), "nas");
+        assert!(file
+            .into_config(None, None, None, Some("nope".to_owned()), true)
+            .is_err());
+    }
+
     #[test]
     fn example_theme_equals_default() {
         let theme = UiConfigFile::default();
@@ -463,6 +733,50 @@ mod tests {
         assert_eq!(theme, file);
     }
 
+    #[test]
+    fn migrated_config_is_read_back_unchanged() {
+        let path = format!(
+            "{}/assets/example_config.ron",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap()
+        );
+
+        let (changes, migrated) = ConfigFile::migrate_file(std::path::Path::new(&path)).unwrap();
+
+        let original: ConfigFile = ron::de::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let migrated: ConfigFile = ron::de::from_str(&migrated).unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(migrated, original);
+    }
+
+    #[test]
+    fn deprecated_fields_are_migrated() {
+        let mut config: ConfigFile = ron::de::from_str(
+            r#"#![enable(implicit_some)]
+            (
+                image_method: Kitty,
+                album_art_max_size_px: (width: 100, height: 200),
+                keybinds: (global: { "1": QueueTab, "q": Quit }),
+            )"#,
+        )
+        .unwrap();
+
+        let changes = config.migrate();
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(config.image_method, None);
+        assert_eq!(config.album_art.method, ImageMethodFile::Kitty);
+        assert_eq!(config.album_art_max_size_px, None);
+        assert_eq!(config.album_art.max_size_px, Size::default());
+        assert_eq!(
+            config.keybinds.global.values().sorted().collect_vec(),
+            [
+                &GlobalActionFile::Quit,
+                &GlobalActionFile::SwitchToTab("Queue".to_owned())
+            ]
+        );
+        assert!(config.migrate().is_empty());
+    }
+
     #[test]
     fn gallery_themes_are_valid() {
         let path = format!(
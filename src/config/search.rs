@@ -11,10 +11,11 @@ pub struct Search {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
 pub struct SearchFile {
-    case_sensitive: bool,
-    mode: FilterKindFile,
-    tags: Vec<SearchableTagFile>,
+    pub(super) case_sensitive: bool,
+    pub(super) mode: FilterKindFile,
+    pub(super) tags: Vec<SearchableTagFile>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -95,7 +96,7 @@ impl Default for SearchFile {
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
-enum FilterKindFile {
+pub(super) enum FilterKindFile {
     Exact,
     StartsWith,
     #[default]
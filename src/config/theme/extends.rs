@@ -0,0 +1,253 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+
+use crate::config::utils::canonical_without_cycle;
+
+use super::{
+    header::HeaderConfigFile, progress_bar::ProgressBarConfigFile, properties::SongFormatFile,
+    queue_table::QueueTableColumnsFile, scrollbar::ScrollbarConfigFile, StyleFile, SymbolsFile, TabBarFile,
+    UiConfigFile,
+};
+
+/// Only the `extends` field of a theme, used to find out how the rest of the theme is read
+#[derive(Debug, Default, Deserialize)]
+struct ThemeParent {
+    #[serde(default, deserialize_with = "present")]
+    extends: Option<String>,
+}
+
+/// Theme which overrides only some fields of the theme it extends. Every field is `Some` when it
+/// is present in the file, even fields which are optional in [`UiConfigFile`]. Unknown fields are
+/// rejected so that a field missing here is not silently ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::option_option)]
+struct ExtendingThemeFile {
+    /// Read through [`ThemeParent`], present here only to be allowed in the file
+    #[serde(default, deserialize_with = "present")]
+    #[allow(dead_code)]
+    extends: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    draw_borders: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    symbols: Option<SymbolsFile>,
+    #[serde(default, deserialize_with = "present")]
+    tab_bar: Option<TabBarFile>,
+    #[serde(default, deserialize_with = "present")]
+    progress_bar: Option<ProgressBarConfigFile>,
+    #[serde(default, deserialize_with = "present")]
+    scrollbar: Option<ScrollbarConfigFile>,
+    #[serde(default, deserialize_with = "present")]
+    browser_column_widths: Option<Vec<u16>>,
+    #[serde(default, deserialize_with = "present")]
+    browser_song_format: Option<SongFormatFile>,
+    #[serde(default, deserialize_with = "present")]
+    background_color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    text_color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    header_background_color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    modal_background_color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    borders_style: Option<Option<StyleFile>>,
+    #[serde(default, deserialize_with = "present")]
+    highlighted_item_style: Option<Option<StyleFile>>,
+    #[serde(default, deserialize_with = "present")]
+    current_item_style: Option<Option<StyleFile>>,
+    #[serde(default, deserialize_with = "present")]
+    highlight_border_style: Option<Option<StyleFile>>,
+    #[serde(default, deserialize_with = "present")]
+    show_song_table_header: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    song_table_format: Option<QueueTableColumnsFile>,
+    #[serde(default, deserialize_with = "present")]
+    header: Option<HeaderConfigFile>,
+    #[serde(default, deserialize_with = "present")]
+    default_album_art_path: Option<Option<String>>,
+}
+
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+impl ExtendingThemeFile {
+    fn apply_to(self, theme: &mut UiConfigFile) {
+        macro_rules! replace_present {
+            ($($field:ident),+ $(,)?) => {
+                let Self { extends: _, $($field),+ } = self;
+                $(if let Some(value) = $field {
+                    theme.$field = value;
+                })+
+            };
+        }
+
+        replace_present!(
+            draw_borders,
+            symbols,
+            tab_bar,
+            progress_bar,
+            scrollbar,
+            browser_column_widths,
+            browser_song_format,
+            background_color,
+            text_color,
+            header_background_color,
+            modal_background_color,
+            borders_style,
+            highlighted_item_style,
+            current_item_style,
+            highlight_border_style,
+            show_song_table_header,
+            song_table_format,
+            header,
+            default_album_art_path,
+        );
+    }
+}
+
+impl UiConfigFile {
+    /// Reads the theme at the given path. A theme with `extends` is applied on top of the theme
+    /// with that name in the same directory.
+    pub fn read(path: &Path) -> Result<Self> {
+        read_extending(path, &mut Vec::new())
+    }
+
+    /// Paths of the theme at the given path and of the themes it extends, as far as they can be read
+    pub fn files(path: &Path) -> Vec<PathBuf> {
+        let mut files = vec![path.to_path_buf()];
+        while let Some(parent) = std::fs::read_to_string(&files[files.len() - 1])
+            .ok()
+            .and_then(|content| ron::de::from_str::<ThemeParent>(&content).ok())
+            .and_then(|theme| theme.extends)
+        {
+            let parent_path = parent_path(path, &parent);
+            if files.contains(&parent_path) {
+                break;
+            }
+            files.push(parent_path);
+        }
+
+        files
+    }
+}
+
+fn parent_path(path: &Path, name: &str) -> PathBuf {
+    path.with_file_name(format!("{name}.ron"))
+}
+
+/// `stack` contains the themes which extend the current one to detect cycles
+fn read_extending(path: &Path, stack: &mut Vec<PathBuf>) -> Result<UiConfigFile> {
+    let canonical = canonical_without_cycle(path, stack, "Themes extend each other")?;
+
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read theme file '{}'", path.display()))?;
+    let parent: ThemeParent = ron::de::from_str(&content).map_err(|err| anyhow!("{}:{err}", path.display()))?;
+    let Some(parent) = parent.extends.as_deref() else {
+        return ron::de::from_str(&content).map_err(|err| anyhow!("{}:{err}", path.display()));
+    };
+    let theme: ExtendingThemeFile = ron::de::from_str(&content).map_err(|err| anyhow!("{}:{err}", path.display()))?;
+
+    stack.push(canonical);
+    let mut result = read_extending(&parent_path(path, parent), stack)
+        .with_context(|| format!("Failed to read theme '{parent}' extended by '{}'", path.display()))?;
+    stack.pop();
+    theme.apply_to(&mut result);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{config::theme::UiConfigFile, tests::fixtures::temp_dir::TempDir};
+
+    use super::ExtendingThemeFile;
+
+    #[test]
+    fn child_overrides_present_fields() {
+        let base = include_str!("../../../assets/example_theme.ron");
+        let dir = TempDir::new("themes");
+        dir.write("base.ron", base);
+        let path = dir.write(
+            "child.ron",
+            r#"#![enable(implicit_some)]
+            (
+                extends: "base",
+                background_color: "white",
+                borders_style: None,
+                symbols: (song: "s", dir: "d", marker: "m"),
+            )"#,
+        );
+
+        let theme = UiConfigFile::read(&path).unwrap();
+
+        let base: UiConfigFile = ron::de::from_str(base).unwrap();
+        assert_eq!(
+            theme,
+            UiConfigFile {
+                background_color: Some("white".to_owned()),
+                borders_style: None,
+                symbols: ron::de::from_str(r#"(song: "s", dir: "d", marker: "m")"#).unwrap(),
+                ..base
+            }
+        );
+        assert_eq!(
+            UiConfigFile::files(&path),
+            [path.clone(), path.with_file_name("base.ron")]
+        );
+    }
+
+    #[test]
+    fn extend_cycle_is_an_error() {
+        let dir = TempDir::new("themes");
+        let path = dir.write("child.ron", r#"(extends: "base")"#);
+        dir.write("base.ron", r#"(extends: "child")"#);
+
+        let err = UiConfigFile::read(&path).unwrap_err();
+
+        assert!(format!("{err:#}").contains("Themes extend each other"), "{err:#}");
+        assert_eq!(UiConfigFile::files(&path).len(), 2);
+    }
+
+    #[test]
+    fn missing_parent_is_an_error() {
+        let dir = TempDir::new("themes");
+        let path = dir.write("child.ron", r#"(extends: "nope")"#);
+
+        let err = UiConfigFile::read(&path).unwrap_err();
+
+        assert!(
+            format!("{err:#}").starts_with("Failed to read theme 'nope' extended by"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn extending_theme_has_every_theme_field() {
+        let default = UiConfigFile::default();
+        let content = ron::ser::to_string(&default).unwrap();
+
+        let theme: ExtendingThemeFile = ron::de::from_str(&content).unwrap();
+        let mut result = UiConfigFile {
+            draw_borders: false,
+            ..ron::de::from_str(include_str!("../../../assets/example_theme.ron")).unwrap()
+        };
+        theme.apply_to(&mut result);
+
+        assert_eq!(result, default);
+    }
+
+    #[test]
+    fn unknown_field_in_extending_theme_is_an_error() {
+        let dir = TempDir::new("themes");
+        dir.write("base.ron", "()");
+        let path = dir.write("child.ron", r#"(extends: "base", no_such_field: true)"#);
+
+        let err = UiConfigFile::read(&path).unwrap_err();
+
+        assert!(format!("{err:#}").contains("no_such_field"), "{err:#}");
+    }
+}
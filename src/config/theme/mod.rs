@@ -1,6 +1,6 @@
 use ::serde::{Deserialize, Serialize};
 use anyhow::Result;
-use properties::{SongFormat, SongFormatFile};
+use properties::{PropertyKind, SongFormat, SongFormatFile, SongProperty};
 use ratatui::style::{Color, Style};
 
 use self::{
@@ -11,6 +11,7 @@ use self::{
     style::{Modifiers, StringColor, ToConfigOr},
 };
 
+mod extends;
 mod header;
 mod progress_bar;
 pub mod properties;
@@ -48,6 +49,41 @@ pub struct UiConfig {
     pub default_album_art: &'static [u8],
 }
 
+impl UiConfig {
+    /// Calls `f` with every song property displayed by the browsers, the queue or the header
+    pub fn for_each_song_property(&self, mut f: impl FnMut(&SongProperty)) {
+        for prop in self.browser_song_format.0 {
+            prop.for_each_kind(&mut f);
+        }
+        for column in self.song_table_format {
+            column.prop.for_each_kind(&mut f);
+        }
+        for row in self.header.rows {
+            for prop in row.left.iter().chain(row.center).chain(row.right) {
+                prop.for_each_kind(&mut |kind| {
+                    if let PropertyKind::Song(prop) = kind {
+                        f(prop);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Whether the queue table or the header display ratings, both take them from the cached queue
+    pub fn queue_uses_rating(&self) -> bool {
+        let mut uses_rating = self.song_table_format.iter().any(|column| column.prop.uses_rating());
+        for row in self.header.rows {
+            for prop in row.left.iter().chain(row.center).chain(row.right) {
+                prop.for_each_kind(&mut |kind| {
+                    uses_rating |= matches!(kind, PropertyKind::Song(SongProperty::Rating));
+                });
+            }
+        }
+
+        uses_rating
+    }
+}
+
 impl std::fmt::Debug for UiConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "UiConfig {{ draw_borders: {}, background_color: {:?}, header_background_color: {:?}, background_color_modal: {:?}, borders_style: {:?}, highlighted_item_style: {:?}, current_item_style: {:?}, highlight_border_style: {:?}, tab_bar: {:?}, column_widths: {:?}, symbols: {:?}, progress_bar: {:?}, scrollbar: {:?}, show_song_table_header: {}, song_table_format: {:?}, header: {:?}, default_album_art: [u8; {}] }}", self.draw_borders, self.background_color, self.header_background_color, self.modal_background_color, self.borders_style, self.highlighted_item_style, self.current_item_style, self.highlight_border_style, self.tab_bar, self.column_widths, self.symbols, self.progress_bar, self.scrollbar, self.show_song_table_header, self.song_table_format, self.header, self.default_album_art.len())
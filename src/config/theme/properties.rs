@@ -18,6 +18,8 @@ pub enum SongPropertyFile {
     Album,
     Duration,
     Track,
+    Rating,
+    Priority,
     Other(String),
 }
 
@@ -30,6 +32,8 @@ pub enum SongProperty {
     Album,
     Duration,
     Track,
+    Rating,
+    Priority,
     Other(&'static str),
 }
 
@@ -45,6 +49,8 @@ pub enum StatusPropertyFile {
     Duration,
     Crossfade,
     Bitrate,
+    Partition,
+    Server,
 }
 
 #[derive(Debug, Clone, Display)]
@@ -59,6 +65,8 @@ pub enum StatusProperty {
     Duration,
     Crossfade,
     Bitrate,
+    Partition,
+    Server,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -141,6 +149,8 @@ impl TryFrom<SongPropertyFile> for SongProperty {
             SongPropertyFile::Album => SongProperty::Album,
             SongPropertyFile::Duration => SongProperty::Duration,
             SongPropertyFile::Track => SongProperty::Track,
+            SongPropertyFile::Rating => SongProperty::Rating,
+            SongPropertyFile::Priority => SongProperty::Priority,
             SongPropertyFile::Other(name) => SongProperty::Other(name.leak()),
         })
     }
@@ -171,6 +181,8 @@ impl TryFrom<StatusPropertyFile> for StatusProperty {
             StatusPropertyFile::Single => StatusProperty::Single,
             StatusPropertyFile::Bitrate => StatusProperty::Bitrate,
             StatusPropertyFile::Crossfade => StatusProperty::Crossfade,
+            StatusPropertyFile::Partition => StatusProperty::Partition,
+            StatusPropertyFile::Server => StatusProperty::Server,
         })
     }
 }
@@ -227,6 +239,56 @@ pub struct SongFormatFile(pub Vec<PropertyFile<SongPropertyFile>>);
 #[derive(Default, Clone, Copy)]
 pub struct SongFormat(pub &'static [&'static Property<'static, SongProperty>]);
 
+impl SongFormat {
+    pub fn uses_rating(&self) -> bool {
+        self.0.iter().any(|prop| prop.uses_rating())
+    }
+}
+
+impl SongProperty {
+    /// MPD tag this property is read from, None for properties which are not tags
+    pub fn tag(&self) -> Option<&'static str> {
+        match self {
+            SongProperty::Title => Some("Title"),
+            SongProperty::Artist => Some("Artist"),
+            SongProperty::Album => Some("Album"),
+            SongProperty::Track => Some("Track"),
+            SongProperty::Other(name) => Some(name),
+            SongProperty::Filename
+            | SongProperty::File
+            | SongProperty::Duration
+            | SongProperty::Rating
+            | SongProperty::Priority => None,
+        }
+    }
+}
+
+impl<T> Property<'_, T> {
+    /// Calls `f` with every property kind used by this property, its group members and its default
+    pub fn for_each_kind(&self, f: &mut impl FnMut(&T)) {
+        match &self.kind {
+            PropertyKindOrText::Property(kind) => f(kind),
+            PropertyKindOrText::Group(group) => group.iter().for_each(|prop| prop.for_each_kind(f)),
+            PropertyKindOrText::Text(_) => {}
+        }
+        if let Some(default) = self.default {
+            default.for_each_kind(f);
+        }
+    }
+}
+
+impl Property<'_, SongProperty> {
+    /// Whether this property, any of its group members or its default displays the song rating
+    pub fn uses_rating(&self) -> bool {
+        let uses_rating = match self.kind {
+            PropertyKindOrText::Property(SongProperty::Rating) => true,
+            PropertyKindOrText::Group(group) => group.iter().any(|prop| prop.uses_rating()),
+            PropertyKindOrText::Property(_) | PropertyKindOrText::Text(_) => false,
+        };
+        uses_rating || self.default.is_some_and(Property::uses_rating)
+    }
+}
+
 impl TryFrom<SongFormatFile> for SongFormat {
     type Error = anyhow::Error;
 
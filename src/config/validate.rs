@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::mpd::capabilities::Capabilities;
+
+use super::{
+    keys::{GlobalAction, KeybindEntriesFile},
+    tabs::Tabs,
+    theme::{properties::SongProperty, UiConfig, UiConfigFile},
+    ConfigFile,
+};
+
+/// Fields of songs which can be displayed with `Other` but are not tags and so are not listed by
+/// `tagtypes`
+const NON_TAG_SONG_FIELDS: [&str; 3] = ["Last-Modified", "Added", "Pos"];
+
+#[derive(Debug, Default, Deserialize)]
+struct KeybindsOnlyFile {
+    #[serde(default)]
+    keybinds: KeybindEntriesFile,
+}
+
+/// Checks the config file at the given path and the theme it references. Returns a description of
+/// every problem found, an empty list means the config is valid. Song properties are checked against
+/// the tags supported by the server when its `capabilities` are available.
+pub fn validate(config_path: &Path, capabilities: Option<&Capabilities>) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let config_str = match std::fs::read_to_string(config_path) {
+        Ok(config_str) => config_str,
+        Err(err) => return vec![format!("Failed to read config file '{}': {err}", config_path.display())],
+    };
+    let config_file: ConfigFile = match ron::de::from_str(&config_str) {
+        Ok(config_file) => config_file,
+        Err(err) => return vec![format!("{}:{err}", config_path.display())],
+    };
+    if let Ok(keybinds) = ron::de::from_str::<KeybindsOnlyFile>(&config_str) {
+        problems.extend(keybinds.keybinds.conflicts());
+    }
+    let config_file = match ConfigFile::read(config_path) {
+        Ok(merged) => merged,
+        Err(err) => {
+            problems.push(format!("{err:#}"));
+            config_file
+        }
+    };
+
+    match Tabs::try_from(config_file.tabs.clone()) {
+        Ok(tabs) => {
+            for action in config_file.keybinds.global.values() {
+                if let GlobalAction::SwitchToTab(name) = action.clone().into() {
+                    if !tabs.names.contains(&name) {
+                        problems.push(format!("Keybind switches to tab '{name}' which does not exist"));
+                    }
+                }
+            }
+        }
+        Err(err) => problems.push(format!("Invalid tabs: {err:#}")),
+    }
+
+    let Some(theme_path) = config_path
+        .parent()
+        .and_then(|config_dir| config_file.theme_path(config_dir))
+    else {
+        return problems;
+    };
+    let theme_file = match UiConfigFile::read(&theme_path) {
+        Ok(theme_file) => theme_file,
+        Err(err) => {
+            problems.push(format!("{err:#}"));
+            return problems;
+        }
+    };
+    problems.extend(validate_theme(theme_file, capabilities));
+
+    problems
+}
+
+fn validate_theme(mut theme_file: UiConfigFile, capabilities: Option<&Capabilities>) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Some(path) = theme_file.default_album_art_path.take() {
+        match std::fs::File::open(&path) {
+            Ok(_) => theme_file.default_album_art_path = Some(path),
+            Err(err) => problems.push(format!("Default album art '{path}' is not readable: {err}")),
+        }
+    }
+
+    match UiConfig::try_from(theme_file) {
+        Ok(theme) => theme.for_each_song_property(|prop| {
+            if let (SongProperty::Other(name), Some(capabilities)) = (prop, capabilities) {
+                let is_known = capabilities.supports_tag(name)
+                    || NON_TAG_SONG_FIELDS.iter().any(|field| field.eq_ignore_ascii_case(name));
+                let message = format!("Unknown song property Other(\"{name}\"), it is not a tag known to MPD");
+                if !is_known && !problems.contains(&message) {
+                    problems.push(message);
+                }
+            }
+        }),
+        Err(err) => problems.push(format!("Invalid theme: {err:#}")),
+    }
+
+    problems
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        mpd::{capabilities::Capabilities, client::Client},
+        tests::fixtures::{mpd_server::FakeMpdServer, temp_dir::TempDir},
+    };
+
+    use super::validate;
+
+    #[test]
+    fn example_config_and_theme_are_valid() {
+        let dir = TempDir::new("validate");
+        let path = dir.write(
+            "config.ron",
+            &include_str!("../../assets/example_config.ron").replace("theme: None", "theme: Some(\"theme\")"),
+        );
+        dir.write("themes/theme.ron", include_str!("../../assets/example_theme.ron"));
+
+        assert_eq!(validate(&path, None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_location_of_syntax_errors() {
+        let dir = TempDir::new("validate");
+        let path = dir.write("config.ron", "(\n    volume_step: \"five\",\n)");
+
+        let problems = validate(&path, None);
+
+        assert_eq!(problems.len(), 1);
+        assert!(
+            problems[0].starts_with(&format!("{}:2:", path.display())),
+            "{problems:?}"
+        );
+    }
+
+    #[test]
+    fn reports_keybind_and_tab_problems() {
+        let config = r#"(
+            keybinds: (
+                global: {
+                    "q": Quit,
+                    "1": SwitchToTab("Nope"),
+                    "j": NextTab,
+                    "q": Stop,
+                },
+                navigation: {
+                    "j": Down,
+                },
+            ),
+        )"#;
+        let dir = TempDir::new("validate");
+        let path = dir.write("config.ron", config);
+
+        let problems = validate(&path, None);
+
+        assert_eq!(
+            problems,
+            [
+                "Key 'q' is bound more than once in keybinds.global, Quit is overridden",
+                "Key 'j' bound to NextTab in keybinds.global is shadowed by Down in keybinds.navigation",
+                "Keybind switches to tab 'Nope' which does not exist",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_theme_problems() {
+        let theme = include_str!("../../assets/example_theme.ron")
+            .replace(
+                "default_album_art_path: None",
+                "default_album_art_path: \"/does/not/exist.jpg\"",
+            )
+            .replace("(kind: Property(Track))", "(kind: Property(Other(\"NotATag\")))")
+            .replace("(kind: Property(Title))", "(kind: Property(Other(\"genre\")))");
+        let dir = TempDir::new("validate");
+        let path = dir.write("config.ron", "#![enable(implicit_some)]\n(theme: \"theme\")");
+        dir.write("themes/theme.ron", &theme);
+        let server = FakeMpdServer::start();
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let capabilities = Capabilities::fetch(&mut client).unwrap();
+
+        let problems = validate(&path, Some(&capabilities));
+        let offline_problems = validate(&path, None);
+
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].starts_with("Default album art '/does/not/exist.jpg' is not readable"));
+        assert_eq!(
+            problems[1],
+            "Unknown song property Other(\"NotATag\"), it is not a tag known to MPD"
+        );
+        assert_eq!(offline_problems, problems[..1]);
+    }
+}
@@ -1,56 +1,266 @@
-use std::{cell::Cell, collections::HashSet, sync::mpsc::Sender};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    config::{Config, ImageMethod, Leak},
+    config::{AlbumArtConfig, Config, ImageMethod, Leak},
     mpd::{
+        capabilities::{Capabilities, Feature},
         client::Client,
-        commands::{Song, Status},
-        mpd_client::MpdClient,
+        commands::{playlist_changes::PositionChange, Song, Status},
+        mpd_client::{Filter, MpdClient, SingleOrRange, Tag},
     },
-    shared::macros::status_warn,
+    shared::{album_art::AlbumArtLoader, ext::mpd_client::MpdClientExt, macros::status_warn},
     AppEvent, WorkRequest,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
 
 pub struct AppContext {
     pub config: &'static Config,
+    /// Album art method from the config. The one in `config` is set to None while the connected
+    /// server does not support album art.
+    pub album_art_method: ImageMethod,
     pub status: Status,
     pub queue: Vec<Song>,
-    pub supported_commands: HashSet<String>,
+    /// Version of the MPD queue the cached `queue` corresponds to. None forces a full reload on
+    /// the next sync.
+    pub queue_version: Option<u32>,
+    pub capabilities: Capabilities,
     pub app_event_sender: Sender<AppEvent>,
     pub work_sender: Sender<WorkRequest>,
+    pub album_art: AlbumArtLoader,
     pub needs_render: Cell<bool>,
+    pub connection: ConnectionState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// Connection to MPD is down, next attempt to reconnect happens at `retry_at`
+    Disconnected {
+        attempt: u32,
+        retry_at: Instant,
+    },
+}
+
+impl ConnectionState {
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, ConnectionState::Disconnected { .. })
+    }
+
+    /// Schedules the given attempt with exponential backoff, starting at one second
+    pub fn disconnected(attempt: u32) -> Self {
+        let delay = Duration::from_secs(1u64 << attempt.min(5)).min(Self::MAX_RETRY_DELAY);
+        ConnectionState::Disconnected {
+            attempt,
+            retry_at: Instant::now() + delay,
+        }
+    }
 }
 
 impl AppContext {
     pub fn try_new(
         client: &mut Client<'_>,
-        mut config: Config,
+        config: Config,
         app_event_sender: Sender<AppEvent>,
         work_sender: Sender<WorkRequest>,
+        album_art: AlbumArtLoader,
     ) -> Result<Self> {
-        let status = client.get_status()?;
-        let queue = client.playlist_info()?.unwrap_or_default();
-        let supported_commands: HashSet<String> = client.commands()?.0.into_iter().collect();
-
-        log::info!(supported_commands:? = supported_commands; "Supported commands by server");
-
-        if !supported_commands.contains("albumart") || !supported_commands.contains("readpicture") {
-            config.album_art.method = ImageMethod::None;
-            status_warn!("Album art is disabled because it is not supported by MPD");
-        }
+        // rmpc can start while MPD is down, everything gets synced once the connection is up
+        let (status, queue, capabilities, connection) = if client.is_connected() {
+            let capabilities = negotiate_capabilities(&config, client)?;
+            let status = client.get_status()?;
+            let queue = client.playlist_info()?.unwrap_or_default();
+            (status, queue, capabilities, ConnectionState::Connected)
+        } else {
+            (
+                Status::default(),
+                Vec::new(),
+                Capabilities::default(),
+                ConnectionState::Disconnected {
+                    attempt: 0,
+                    retry_at: Instant::now(),
+                },
+            )
+        };
 
         log::info!(config:? = config; "Resolved config");
 
-        Ok(Self {
+        let mut context = Self {
+            album_art_method: config.album_art.method,
             config: config.leak(),
+            queue_version: status.playlist,
             status,
             queue,
-            supported_commands,
+            capabilities,
             app_event_sender,
             work_sender,
+            album_art,
             needs_render: Cell::new(false),
-        })
+            connection,
+        };
+        if !context.connection.is_disconnected() {
+            context.apply_album_art_support();
+        }
+        context.fetch_queue_ratings(client);
+
+        Ok(context)
+    }
+
+    /// Config as it was read, with album art enabled even when the connected server does not
+    /// support it
+    pub fn requested_config(&self) -> Config {
+        Config {
+            album_art: AlbumArtConfig {
+                method: self.album_art_method,
+                ..self.config.album_art.clone()
+            },
+            ..self.config.clone()
+        }
+    }
+
+    /// Disables album art while the connected server cannot provide it and enables it again after
+    /// connecting to one which can
+    fn apply_album_art_support(&mut self) {
+        let method = if self.capabilities.supports(Feature::AlbumArt) {
+            self.album_art_method
+        } else {
+            ImageMethod::None
+        };
+        if method == self.config.album_art.method {
+            return;
+        }
+
+        if method == ImageMethod::None {
+            status_warn!("Album art is disabled because it is not supported by MPD");
+        }
+        self.config = Config {
+            album_art: AlbumArtConfig {
+                method,
+                ..self.config.album_art.clone()
+            },
+            ..self.config.clone()
+        }
+        .leak();
+    }
+
+    /// Refetches everything cached from MPD, used after reconnecting as anything might have
+    /// changed in the meantime
+    pub fn resync(&mut self, client: &mut Client<'_>) -> Result<()> {
+        // The client restores its tag limit on reconnect, it has to be lifted to see all tags
+        if self.capabilities.limited_tag_types().is_some() {
+            client.tag_types_all()?;
+        }
+        self.capabilities = negotiate_capabilities(self.config, client)?;
+        self.apply_album_art_support();
+        self.status = client.get_status()?;
+        self.queue_version = None;
+        self.sync_queue(client)
+    }
+
+    /// Applies a config reloaded by the config watcher. The current config is kept when the new
+    /// one could not be read, eg. because of a syntax error while it is being edited.
+    pub fn apply_reloaded_config(&mut self, config: Result<Config>, client: &mut Client<'_>) -> Result<()> {
+        let config = config.context("Failed to reload config, keeping the current one")?;
+        self.apply_config(config, client)
+            .context("Failed to apply reloaded config")
+    }
+
+    /// Applies a config reloaded from disk. Settings used by other threads or only applied on
+    /// startup, eg. the address or the image protocol, are kept from the current config.
+    ///
+    /// The new config is leaked like the one read on startup because the UI keeps `'static`
+    /// references into it. The previous one cannot be freed for the same reason so every reload
+    /// leaks a config. The config watcher only reloads when the content of a file changed so this
+    /// is bounded by the number of edits.
+    fn apply_config(&mut self, config: Config, client: &mut Client<'_>) -> Result<()> {
+        let current = self.config;
+        self.config = Config {
+            address: current.address,
+            password: current.password,
+            cache_dir: current.cache_dir,
+            enable_mouse: current.enable_mouse,
+            status_update_interval_ms: current.status_update_interval_ms,
+            album_art: current.album_art.clone(),
+            channels: current.channels,
+            ..config
+        }
+        .leak();
+
+        // Formats might display tags which are not fetched yet
+        if !self.connection.is_disconnected() {
+            self.resync(client)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ratings are stored in stickers which are not part of the queue response. Failing to fetch
+    /// them is not fatal because MPD might not have the sticker database configured.
+    pub fn fetch_queue_ratings(&mut self, client: &mut impl MpdClient) {
+        fetch_ratings(self.config, &self.capabilities, client, self.queue.iter_mut());
+    }
+
+    /// Brings the cached queue up to date after it changed in MPD. Only songs which changed since
+    /// the last known queue version are fetched. The whole queue is reloaded when the version is
+    /// not known or the changes cannot be applied.
+    pub fn sync_queue(&mut self, client: &mut impl MpdClient) -> Result<()> {
+        let status = client.get_status()?;
+        match (self.queue_version, status.playlist) {
+            (Some(old), Some(new)) if old == new => {}
+            (Some(old), Some(new)) if old < new => {
+                if let Err(err) = self.apply_queue_changes(old, status.playlistlength as usize, client) {
+                    log::warn!(error:? = err; "Failed to apply queue changes, reloading the whole queue");
+                    self.reload_queue(client)?;
+                }
+            }
+            _ => self.reload_queue(client)?,
+        }
+        self.queue_version = status.playlist;
+
+        Ok(())
+    }
+
+    fn reload_queue(&mut self, client: &mut impl MpdClient) -> Result<()> {
+        self.queue = client.playlist_info()?.unwrap_or_default();
+        self.fetch_queue_ratings(client);
+        Ok(())
+    }
+
+    fn apply_queue_changes(&mut self, version: u32, new_len: usize, client: &mut impl MpdClient) -> Result<()> {
+        let changes = client.playlist_changes_pos_id(version)?;
+        let mut queue = apply_position_changes(std::mem::take(&mut self.queue), new_len, &changes.0);
+
+        let missing = queue.iter().positions(Option::is_none).collect_vec();
+        if let (Some(&first), Some(&last)) = (missing.first(), missing.last()) {
+            let mut songs = client.playlist_changes(version, Some(SingleOrRange::range(first, last + 1)))?;
+            fetch_ratings(self.config, &self.capabilities, client, songs.iter_mut());
+            for song in songs {
+                let Some(slot) = song
+                    .metadata
+                    .get("pos")
+                    .and_then(|pos| pos.parse::<usize>().ok())
+                    .and_then(|pos| queue.get_mut(pos))
+                else {
+                    continue;
+                };
+                if slot.is_none() {
+                    *slot = Some(song);
+                }
+            }
+        }
+
+        self.queue = queue
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| anyhow!("Queue changes did not contain all changed songs"))?;
+        Ok(())
     }
 
     pub fn render(&self) -> Result<(), std::sync::mpsc::SendError<AppEvent>> {
@@ -72,6 +282,27 @@ impl AppContext {
             .and_then(|id| self.queue.iter().enumerate().find(|(_, song)| song.id == id))
     }
 
+    /// Songs fetched by rmpc only contain tags used by the configured formats, this adds all of
+    /// the remaining ones. Used when all tags of a song are shown.
+    pub fn with_all_tags(&self, mut song: Song, client: &mut impl MpdClient) -> Song {
+        if self.capabilities.limited_tag_types().is_none() {
+            return song;
+        }
+
+        match self.capabilities.with_all_tag_types(client, |client| {
+            client.find_one(&[Filter::new(Tag::File, song.file.as_str())])
+        }) {
+            Ok(Some(full)) => {
+                for (tag, value) in full.metadata {
+                    song.metadata.entry(tag).or_insert(value);
+                }
+            }
+            Ok(None) => {}
+            Err(err) => log::warn!(error:? = err; "Failed to fetch all tags of song"),
+        }
+        song
+    }
+
     /// Gets the owned version of current song by either cloning it from queue
     /// or by querying MPD if not found
     pub fn get_current_song(&self, client: &mut impl MpdClient) -> Result<Option<Song>> {
@@ -82,3 +313,344 @@ impl AppContext {
         }
     }
 }
+
+/// Fetches what the server supports and limits tags in song responses to the ones the config uses
+fn negotiate_capabilities(config: &Config, client: &mut impl MpdClient) -> Result<Capabilities> {
+    let mut capabilities = Capabilities::fetch(client)?;
+    capabilities.limit_tag_types(client, config.song_tags())?;
+
+    Ok(capabilities)
+}
+
+/// Ratings are only fetched when the queue or the header displays them
+fn fetch_ratings<'a>(
+    config: &Config,
+    capabilities: &Capabilities,
+    client: &mut impl MpdClient,
+    songs: impl IntoIterator<Item = &'a mut Song>,
+) {
+    if !config.theme.queue_uses_rating() || !capabilities.supports(Feature::Stickers) {
+        return;
+    }
+    if let Err(err) = client.fetch_song_ratings(songs) {
+        log::warn!(error:? = err; "Failed to fetch song ratings");
+    }
+}
+
+/// Builds the new queue from the old one and changes reported by `plchangesposid`. Songs which
+/// were only moved are reused from the old queue. Positions which still have to be fetched from
+/// MPD, either because the song is new or because its tags changed, are left empty.
+fn apply_position_changes(old: Vec<Song>, new_len: usize, changes: &[PositionChange]) -> Vec<Option<Song>> {
+    let index_by_id: HashMap<u32, usize> = old.iter().enumerate().map(|(idx, song)| (song.id, idx)).collect();
+    let mut old = old.into_iter().map(Some).collect_vec();
+    let mut queue: Vec<Option<Song>> = Vec::with_capacity(new_len);
+    queue.resize_with(new_len, || None);
+    let mut changed_positions = vec![false; new_len];
+
+    for change in changes {
+        let Some(is_changed) = changed_positions.get_mut(change.pos) else {
+            continue;
+        };
+        *is_changed = true;
+        // Same song at the same position means its tags changed, refetch it in that case
+        queue[change.pos] = index_by_id
+            .get(&change.id)
+            .filter(|idx| **idx != change.pos)
+            .and_then(|idx| old[*idx].take())
+            .map(|mut song| {
+                song.metadata.insert("pos".to_owned(), change.pos.to_string());
+                song
+            });
+    }
+
+    for (pos, slot) in queue.iter_mut().enumerate() {
+        if !changed_positions[pos] {
+            *slot = old.get_mut(pos).and_then(Option::take);
+        }
+    }
+
+    queue
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::HashMap;
+
+    use std::time::{Duration, Instant};
+
+    use ron::extensions::Extensions;
+
+    use crate::{
+        config::{
+            keys::{GlobalAction, Key},
+            theme::{
+                properties::{Alignment, Property, PropertyKindOrText, SongProperty},
+                PercentOrLength, SongTableColumn,
+            },
+            ConfigFile, ImageMethod, Leak,
+        },
+        mpd::{
+            capabilities::Capabilities,
+            client::Client,
+            commands::{playlist_changes::PositionChange, Song},
+            mpd_client::MpdClient,
+        },
+        tests::fixtures::{
+            app_context,
+            mpd_server::{FakeMpdServer, FakeSong},
+            temp_dir::TempDir,
+        },
+    };
+
+    use super::{apply_position_changes, AppContext, ConnectionState};
+
+    fn song(id: u32) -> Song {
+        Song {
+            id,
+            file: format!("song{id}"),
+            duration: None,
+            metadata: HashMap::from([("pos".to_owned(), id.to_string())]),
+            stickers: None,
+            priority: None,
+        }
+    }
+
+    fn ids(queue: &[Option<Song>]) -> Vec<Option<u32>> {
+        queue.iter().map(|song| song.as_ref().map(|s| s.id)).collect()
+    }
+
+    #[test]
+    fn reuses_moved_songs() {
+        let old = vec![song(0), song(1), song(2)];
+        let changes = [PositionChange { pos: 0, id: 2 }, PositionChange { pos: 2, id: 0 }];
+
+        let result = apply_position_changes(old, 3, &changes);
+
+        assert_eq!(ids(&result), vec![Some(2), Some(1), Some(0)]);
+        assert_eq!(
+            result[0].as_ref().and_then(|s| s.metadata.get("pos")),
+            Some(&"0".to_owned())
+        );
+    }
+
+    #[test]
+    fn leaves_new_songs_empty() {
+        let old = vec![song(0), song(1)];
+        let changes = [PositionChange { pos: 1, id: 5 }, PositionChange { pos: 2, id: 6 }];
+
+        let result = apply_position_changes(old, 3, &changes);
+
+        assert_eq!(ids(&result), vec![Some(0), None, None]);
+    }
+
+    #[test]
+    fn refetches_song_changed_in_place() {
+        let old = vec![song(0), song(1)];
+        let changes = [PositionChange { pos: 1, id: 1 }];
+
+        let result = apply_position_changes(old, 2, &changes);
+
+        assert_eq!(ids(&result), vec![Some(0), None]);
+    }
+
+    #[test]
+    fn truncates_removed_songs() {
+        let old = vec![song(0), song(1), song(2)];
+        let changes = [PositionChange { pos: 0, id: 1 }];
+
+        let result = apply_position_changes(old, 1, &changes);
+
+        assert_eq!(ids(&result), vec![Some(1)]);
+    }
+
+    #[test]
+    fn reconnect_backoff_is_capped() {
+        for (attempt, expected_secs) in [(0, 1), (1, 2), (3, 8), (5, 30), (20, 30)] {
+            let before = Instant::now();
+            let ConnectionState::Disconnected { retry_at, .. } = ConnectionState::disconnected(attempt) else {
+                panic!("Expected disconnected state");
+            };
+            let delay = retry_at - before;
+
+            assert!(
+                delay >= Duration::from_secs(expected_secs),
+                "attempt {attempt}: {delay:?}"
+            );
+            assert!(delay < Duration::from_secs(expected_secs) + Duration::from_millis(100));
+        }
+    }
+
+    fn sticker_commands(server: &FakeMpdServer) -> Vec<String> {
+        let state = server.state();
+        state
+            .commands
+            .iter()
+            .filter(|command| command.starts_with("sticker"))
+            .cloned()
+            .collect()
+    }
+
+    fn context_with_ratings(client: &mut Client<'_>) -> AppContext {
+        let mut context = app_context();
+        let mut config = context.config.clone();
+        let rating: &'static Property<'static, SongProperty> = Box::leak(Box::new(Property {
+            kind: PropertyKindOrText::Property(SongProperty::Rating),
+            style: None,
+            default: None,
+        }));
+        config.theme.song_table_format = vec![SongTableColumn {
+            prop: rating,
+            label: "Rating",
+            width: PercentOrLength::Percent(100),
+            alignment: Alignment::Left,
+        }]
+        .leak();
+        context.config = config.leak();
+        context.capabilities = Capabilities::fetch(client).unwrap();
+        context
+    }
+
+    #[test]
+    fn ratings_are_not_fetched_when_not_displayed() {
+        let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac")]);
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut context = app_context();
+        context.capabilities = Capabilities::fetch(&mut client).unwrap();
+        client.add("01.flac").unwrap();
+
+        context.sync_queue(&mut client).unwrap();
+
+        assert_eq!(context.queue.len(), 1);
+        assert!(sticker_commands(&server).is_empty());
+    }
+
+    #[test]
+    fn ratings_are_fetched_only_for_changed_songs() {
+        let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac"), FakeSong::new("02.flac")]);
+        server.state().stickers.insert(
+            "02.flac".to_owned(),
+            [("rating".to_owned(), "4".to_owned())].into_iter().collect(),
+        );
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut context = context_with_ratings(&mut client);
+        client.add("01.flac").unwrap();
+        context.sync_queue(&mut client).unwrap();
+        server.state().commands.clear();
+
+        client.add("02.flac").unwrap();
+        context.sync_queue(&mut client).unwrap();
+
+        assert_eq!(sticker_commands(&server), [r#"sticker get song "02.flac" "rating""#]);
+        assert_eq!(context.queue[0].rating(), None);
+        assert_eq!(context.queue[1].rating(), Some(4));
+    }
+
+    fn reloaded_config(content: &str) -> crate::config::Config {
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME | Extensions::UNWRAP_NEWTYPES)
+            .from_str::<ConfigFile>(content)
+            .unwrap()
+            .into_config(None, None, None, None, true)
+            .unwrap()
+    }
+
+    fn context_with_album_art(client: &mut Client<'_>) -> AppContext {
+        let base = app_context();
+        let mut config = base.config.clone();
+        config.album_art.method = ImageMethod::Kitty;
+        AppContext::try_new(
+            client,
+            config,
+            base.app_event_sender.clone(),
+            base.work_sender.clone(),
+            base.album_art.clone(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn album_art_support_is_checked_when_connection_comes_up() {
+        let server = FakeMpdServer::start();
+        server.state().disabled_commands = vec!["albumart", "readpicture"];
+        let mut client = Client::disconnected(server.address(), None, "test", true);
+        let mut context = context_with_album_art(&mut client);
+        assert_eq!(context.config.album_art.method, ImageMethod::Kitty);
+
+        client.reconnect().unwrap();
+        context.resync(&mut client).unwrap();
+
+        assert_eq!(context.config.album_art.method, ImageMethod::None);
+        assert_eq!(context.album_art_method, ImageMethod::Kitty);
+    }
+
+    #[test]
+    fn album_art_is_enabled_again_for_server_supporting_it() {
+        let old_server = FakeMpdServer::start();
+        old_server.state().disabled_commands = vec!["albumart", "readpicture"];
+        let mut client = Client::init(old_server.address(), None, "test", true).unwrap();
+        let context = context_with_album_art(&mut client);
+        assert_eq!(context.config.album_art.method, ImageMethod::None);
+
+        let new_server = FakeMpdServer::start();
+        client.switch_server(new_server.address(), None);
+        client.reconnect().unwrap();
+        let context = AppContext::try_new(
+            &mut client,
+            context.requested_config(),
+            context.app_event_sender.clone(),
+            context.work_sender.clone(),
+            context.album_art.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(context.config.album_art.method, ImageMethod::Kitty);
+    }
+
+    #[test]
+    fn reloaded_config_keeps_connection_and_album_art() {
+        let server = FakeMpdServer::start();
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut context = app_context();
+        let current = context.config;
+        let reloaded = reloaded_config(
+            r#"(
+                address: "127.0.0.1:1",
+                password: "secret",
+                album_art: (max_size_px: (width: 1, height: 1)),
+                keybinds: (global: {"x": Quit}),
+                tabs: [(name: "Only", border_type: None, pane: Pane(Queue))],
+            )"#,
+        );
+
+        context.apply_reloaded_config(Ok(reloaded), &mut client).unwrap();
+
+        assert_eq!(context.config.address, current.address);
+        assert_eq!(context.config.password, current.password);
+        assert_eq!(context.config.album_art.max_size_px, current.album_art.max_size_px);
+        assert_eq!(
+            context.config.tabs.names.iter().map(|name| name.0).collect::<Vec<_>>(),
+            ["Only"]
+        );
+        assert_eq!(
+            context.config.keybinds.global.get(&"x".parse::<Key>().unwrap()),
+            Some(&GlobalAction::Quit)
+        );
+    }
+
+    #[test]
+    fn config_which_cannot_be_read_is_not_applied() {
+        let server = FakeMpdServer::start();
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut context = app_context();
+        let current = context.config;
+        let dir = TempDir::new("reload");
+        let path = dir.write("config.ron", "(address: ");
+
+        let config = ConfigFile::read(&path).and_then(|file| file.into_config(None, None, None, None, true));
+        let result = context.apply_reloaded_config(config, &mut client);
+
+        assert!(result.is_err());
+        assert!(std::ptr::eq(context.config, current));
+    }
+}
@@ -16,6 +16,7 @@
 use std::{
     io::{Read, Write},
     ops::Sub,
+    path::PathBuf,
     sync::mpsc::TryRecvError,
     time::Duration,
 };
@@ -24,19 +25,31 @@ use anyhow::{bail, Context, Result};
 use clap::Parser;
 use cli::run_external;
 use config::{
-    cli::{Args, Command},
-    ConfigFile,
+    cli::{Args, Command, ConfigCommand},
+    theme::UiConfigFile,
+    ConfigFile, ImageMethod,
 };
 use crossterm::event::{Event, KeyEvent};
 use itertools::Itertools;
 use log::{error, info, trace, warn};
-use mpd::{client::Client, commands::idle::IdleEvent};
+use mpd::{
+    capabilities::{Capabilities, Feature},
+    client::{Client, IdleInterrupter},
+    commands::{
+        idle::IdleEvent,
+        messages::{Message, Messages},
+    },
+    errors::MpdError,
+    transcript::Transcript,
+};
 use ratatui::{prelude::Backend, Terminal};
 use rustix::path::Arg;
 use shared::dependencies::{DEPENDENCIES, FFMPEG, FFPROBE, PYTHON3, PYTHON3MUTAGEN, UEBERZUGPP, YTDLP};
 use shared::{
+    album_art::{shrink_to_max_size, AlbumArtLoader, AlbumArtRequest},
     env::ENV,
-    ext::{duration::DurationExt, error::ErrorExt},
+    ext::{duration::DurationExt, error::ErrorExt, mpsc::RecvLast},
+    ipc::{self, ControlMessage, ControlSocket},
     logging,
     macros::{status_error, status_info, try_cont, try_skip},
     mouse_event::{MouseEvent, MouseEventTracker},
@@ -46,7 +59,8 @@ use shared::{
 use ui::{Level, UiAppEvent, UiEvent};
 
 use crate::{
-    config::Config,
+    config::{keys::GlobalAction, Channel, Config, Size},
+    context::ConnectionState,
     mpd::mpd_client::MpdClient,
     shared::macros::{status_warn, try_ret},
     ui::Ui,
@@ -55,6 +69,8 @@ use crate::{
 #[cfg(test)]
 mod tests {
     pub mod fixtures;
+    #[allow(clippy::unwrap_used)]
+    mod reconnect;
 }
 
 mod cli;
@@ -86,18 +102,77 @@ pub enum AppEvent {
     Resized { columns: u16, rows: u16 },
     WorkDone(Result<WorkDone>),
     UiAppEvent(UiAppEvent),
+    SwitchPartition(String),
+    SwitchServer(String),
+    AlbumArt { generation: u64, data: Option<Vec<u8>> },
+    ConnectionLost,
+    IdleReconnected(IdleInterrupter),
+    Remote(ControlMessage),
+    ConfigChanged(Result<Box<Config>>),
 }
 
 fn main() -> Result<()> {
     let mut args = Args::parse();
     match args.command {
-        Some(Command::Config { current: false }) => {
+        Some(Command::Config {
+            command: Some(ConfigCommand::Validate),
+            ..
+        }) => {
+            let capabilities = match server_capabilities(&mut args) {
+                Ok(capabilities) => Some(capabilities),
+                Err(err) => {
+                    eprintln!("Song properties are not checked against MPD tags, failed to query MPD: {err:#}");
+                    None
+                }
+            };
+            let problems = config::validate::validate(&args.config, capabilities.as_ref());
+            if !problems.is_empty() {
+                for problem in &problems {
+                    eprintln!("{problem}");
+                }
+                std::process::exit(1);
+            }
+            println!("Config at '{}' is valid", args.config.to_string_lossy());
+        }
+        Some(Command::Config {
+            command: Some(ConfigCommand::Migrate { dry_run }),
+            ..
+        }) => {
+            let (changes, migrated) = ConfigFile::migrate_file(&args.config)
+                .with_context(|| format!("Failed to read config at '{}'", args.config.to_string_lossy()))?;
+            if changes.is_empty() {
+                println!("Config at '{}' is up to date", args.config.to_string_lossy());
+                return Ok(());
+            }
+            for change in &changes {
+                println!("{change}");
+            }
+
+            if dry_run {
+                println!("\n{migrated}");
+            } else {
+                let backup = args.config.with_extension("ron.bak");
+                std::fs::copy(&args.config, &backup)?;
+                std::fs::write(&args.config, migrated)?;
+                println!(
+                    "Config migrated, the original was saved to '{}'. Comments and formatting of the original are not preserved.",
+                    backup.to_string_lossy()
+                );
+            }
+        }
+        Some(Command::Config {
+            current: false,
+            command: None,
+        }) => {
             std::io::stdout().write_all(include_bytes!("../assets/example_config.ron"))?;
         }
         Some(Command::Theme { current: false }) => {
             std::io::stdout().write_all(include_bytes!("../assets/example_theme.ron"))?;
         }
-        Some(Command::Config { current: true }) => {
+        Some(Command::Config {
+            current: true,
+            command: None,
+        }) => {
             let mut file = std::fs::File::open(&args.config)
                 .with_context(|| format!("Config file was not found at '{}'", args.config.to_string_lossy()))?;
             let mut config = String::new();
@@ -126,6 +201,7 @@ fn main() -> Result<()> {
                 Some(&args.config),
                 std::mem::take(&mut args.address),
                 std::mem::take(&mut args.password),
+                std::mem::take(&mut args.profile),
                 false,
             )?;
             let mut mpd_host = ENV.var("MPD_HOST").unwrap_or_else(|_| "unset".to_string());
@@ -164,6 +240,7 @@ fn main() -> Result<()> {
             println!("{:<20} {}", "TMUX", tmux::is_inside_tmux());
             println!("{}", UEBERZUGPP.display());
         }
+        Some(Command::Remote { pid, command }) => ipc::send(pid, &command.try_into()?)?,
         Some(Command::Version) => {
             println!(
                 "rmpc {}{}",
@@ -180,17 +257,25 @@ fn main() -> Result<()> {
                     Some(&args.config),
                     std::mem::take(&mut args.address),
                     std::mem::take(&mut args.password),
+                    std::mem::take(&mut args.profile),
                     true,
                 )?,
                 Err(_err) => ConfigFile::default().into_config(
                     None,
                     std::mem::take(&mut args.address),
                     std::mem::take(&mut args.password),
+                    std::mem::take(&mut args.profile),
                     true,
                 )?,
             }));
             let mut client = Client::init(config.address, config.password, "", true)?;
-            cmd.execute(&mut client, config, |work_request, c| {
+            client.set_transcript(args.transcript.as_deref().map(Transcript::create).transpose()?);
+            let cmd = cmd.with_stdin_paths()?;
+            if cmd.runs_until_interrupted() {
+                // Changes might not come for a long time
+                client.set_read_timeout(None)?;
+            }
+            let result = cmd.clone().execute(&mut client, config, |work_request, c| {
                 match handle_work_request(work_request, config) {
                     Ok(WorkDone::YoutubeDowloaded { file_path }) => match c.add(&file_path) {
                         Ok(()) => {}
@@ -202,7 +287,14 @@ fn main() -> Result<()> {
                         log::error!(err = err.to_string().as_str(); "Failed to handle work request");
                     }
                 }
-            })?;
+            });
+            if let Err(err) = result {
+                if let Some(code) = cmd.exit_code(&err) {
+                    eprintln!("Error: {err}");
+                    std::process::exit(code);
+                }
+                return Err(err);
+            }
         }
         None => {
             let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
@@ -211,20 +303,24 @@ fn main() -> Result<()> {
             std::thread::spawn(|| DEPENDENCIES.iter().for_each(|d| d.log()));
 
             let (worker_tx, worker_rx) = std::sync::mpsc::channel::<WorkRequest>();
+            let (album_art_tx, album_art_rx) = std::sync::mpsc::channel::<AlbumArtRequest>();
+            let album_art_loader = AlbumArtLoader::new(album_art_tx);
 
             let config = match ConfigFile::read(&args.config) {
                 Ok(val) => val.into_config(
                     Some(&args.config),
                     std::mem::take(&mut args.address),
                     std::mem::take(&mut args.password),
+                    std::mem::take(&mut args.profile),
                     false,
                 )?,
                 Err(err) => {
-                    status_warn!(err:?; "Failed to read config. Using default values. Check logs for more information");
+                    status_warn!(err:?; "Failed to read config. Using default values. Run 'rmpc config validate' for more information");
                     ConfigFile::default().into_config(
                         None,
                         std::mem::take(&mut args.address),
                         std::mem::take(&mut args.password),
+                        std::mem::take(&mut args.profile),
                         false,
                     )?
                 }
@@ -232,16 +328,28 @@ fn main() -> Result<()> {
 
             try_ret!(tx.send(AppEvent::RequestRender(false)), "Failed to render first frame");
 
-            let mut client = try_ret!(
-                Client::init(config.address, config.password, "command", true),
-                "Failed to connect to MPD"
-            );
+            let (mut client, mut idle_client) = match Client::init(config.address, config.password, "command", true)
+                .and_then(|client| Ok((client, Client::init(config.address, config.password, "idle", true)?)))
+            {
+                Ok(clients) => clients,
+                Err(err) => {
+                    status_warn!(err:?; "Failed to connect to MPD, will keep retrying in the background");
+                    (
+                        Client::disconnected(config.address, config.password, "command", true),
+                        Client::disconnected(config.address, config.password, "idle", true),
+                    )
+                }
+            };
+
+            let transcript = args.transcript.as_deref().map(Transcript::create).transpose()?;
+            client.set_transcript(transcript.clone());
+            idle_client.set_transcript(transcript.clone());
 
             let terminal = try_ret!(ui::setup_terminal(config.enable_mouse), "Failed to setup terminal");
             let tx_clone = tx.clone();
 
             let context = try_ret!(
-                context::AppContext::try_new(&mut client, config, tx_clone, worker_tx),
+                context::AppContext::try_new(&mut client, config, tx_clone, worker_tx, album_art_loader.clone()),
                 "Failed to create app context"
             );
 
@@ -255,25 +363,57 @@ fn main() -> Result<()> {
                 .name("worker task".to_owned())
                 .spawn(|| worker_task(worker_rx, tx_clone, context.config))?;
 
+            // Started even when the current server has no album art, another one might have it
+            if !matches!(context.album_art_method, ImageMethod::None | ImageMethod::Unsupported) {
+                // Connection is established lazily by the task itself
+                let mut album_art_client =
+                    Client::disconnected(context.config.address, context.config.password, "albumart", true);
+                album_art_client.set_transcript(transcript);
+                let tx_clone = tx.clone();
+                let max_size = context.config.album_art.max_size_px;
+                std::thread::Builder::new()
+                    .name("album art task".to_owned())
+                    .spawn(move || {
+                        album_art_task(album_art_client, album_art_rx, album_art_loader, max_size, tx_clone);
+                    })?;
+            }
+
             let tx_clone = tx.clone();
 
             std::thread::Builder::new()
                 .name("input poll".to_owned())
                 .spawn(|| input_poll_task(tx_clone))?;
 
-            let mut idle_client = try_ret!(
-                Client::init(context.config.address, context.config.password, "idle", true),
-                "Failed to connect to MPD with idle client"
-            );
+            let config = context.config;
+
+            let (idle_command_tx, idle_command_rx) = std::sync::mpsc::channel::<IdleCommand>();
+            let idle_handle = IdleHandle {
+                command_sender: idle_command_tx,
+                interrupter: try_ret!(idle_client.idle_interrupter(), "Failed to create idle interrupter"),
+            };
+
+            let tx_clone = tx.clone();
+            let config_path = args.config.clone();
+            std::thread::Builder::new()
+                .name("config watcher".to_owned())
+                .spawn(|| config_watch_task(config_path, tx_clone))?;
+
+            let control_socket = match ControlSocket::bind(tx.clone()) {
+                Ok(socket) => Some(socket),
+                Err(err) => {
+                    status_warn!(err:?; "Failed to create control socket, rmpc remote will not work");
+                    None
+                }
+            };
 
             let main_task = std::thread::Builder::new().name("main task".to_owned()).spawn(|| {
-                main_task(context, rx, client, render_loop, terminal);
+                main_task(context, rx, client, render_loop, terminal, idle_handle);
             })?;
 
             idle_client.set_read_timeout(None)?;
             std::thread::Builder::new()
                 .name("idle task".to_owned())
-                .spawn(|| idle_task(idle_client, tx))?;
+                .spawn(move || idle_task(idle_client, tx, idle_command_rx, config))?;
 
             let original_hook = std::panic::take_hook();
             std::panic::set_hook(Box::new(move |panic| {
@@ -286,12 +426,27 @@ fn main() -> Result<()> {
             info!("Application initialized successfully");
 
             main_task.join().expect("Main task to not fail");
+            drop(control_socket);
         }
     }
 
     Ok(())
 }
 
+/// Capabilities of the server the config at `args.config` connects to
+fn server_capabilities(args: &mut Args) -> Result<Capabilities> {
+    let config = ConfigFile::read(&args.config)?.into_config(
+        Some(&args.config),
+        std::mem::take(&mut args.address),
+        std::mem::take(&mut args.password),
+        std::mem::take(&mut args.profile),
+        true,
+    )?;
+    let mut client = Client::init(config.address, config.password, "", true)?;
+
+    Ok(Capabilities::fetch(&mut client)?)
+}
+
 fn handle_work_request(request: WorkRequest, config: &Config) -> Result<WorkDone> {
     match request {
         WorkRequest::DownloadYoutube { url } => {
@@ -316,6 +471,129 @@ fn handle_work_request(request: WorkRequest, config: &Config) -> Result<WorkDone
     }
 }
 
+/// Album art is fetched on its own connection so slow transfers do not block the UI. Requests
+/// superseded by a newer one are dropped or aborted mid transfer. Fetched images are shrunk to
+/// `max_size` here as well because decoding large images is slow too.
+#[allow(clippy::needless_pass_by_value)]
+fn album_art_task(
+    mut client: Client<'_>,
+    request_receiver: std::sync::mpsc::Receiver<AlbumArtRequest>,
+    loader: AlbumArtLoader,
+    max_size: Size,
+    sender: std::sync::mpsc::Sender<AppEvent>,
+) {
+    while let Ok(AlbumArtRequest {
+        generation,
+        file,
+        address,
+        password,
+    }) = request_receiver.recv_last()
+    {
+        if !loader.is_current(generation) {
+            continue;
+        }
+        if client.server() != (address, password) {
+            client.switch_server(address, password);
+        }
+        // The request is answered without album art when the client cannot connect so that the UI
+        // does not keep showing the album art of the previous song
+        let connected = client.is_connected()
+            || match client.reconnect() {
+                Ok(_) => true,
+                Err(err) => {
+                    log::warn!(error:? = err; "Failed to connect to MPD with album art client");
+                    false
+                }
+            };
+        client.set_cancel_token(Some(loader.cancel_token(generation)));
+
+        let start = std::time::Instant::now();
+        log::debug!(file = file.as_str(); "Searching for album art");
+        let data = match connected.then(|| client.find_album_art(&file)) {
+            Some(Ok(data)) => data,
+            Some(Err(MpdError::Cancelled)) => {
+                log::debug!(file = file.as_str(), elapsed:? = start.elapsed(); "Album art request cancelled");
+                continue;
+            }
+            Some(Err(err)) => {
+                status_error!(err:?; "Failed to fetch album art. {}", err.to_status());
+                None
+            }
+            None => None,
+        };
+        log::debug!(elapsed:? = start.elapsed(), size = data.as_ref().map(|v|v.len()); "Found album art");
+
+        if !loader.is_current(generation) {
+            continue;
+        }
+        let data = data.and_then(|data| match shrink_to_max_size(data, max_size) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                status_error!(err:?; "Failed to decode album art. {}", err.to_status());
+                None
+            }
+        });
+
+        if loader.is_current(generation) {
+            try_skip!(
+                sender.send(AppEvent::AlbumArt { generation, data }),
+                "Failed to send album art"
+            );
+        }
+    }
+}
+
+/// Checks the config, the files it includes, its theme and the themes it extends for changes every
+/// second and sends the newly read config to the main task. The watched files are resolved again
+/// whenever the config changes.
+#[allow(clippy::needless_pass_by_value)]
+fn config_watch_task(config_path: PathBuf, sender: std::sync::mpsc::Sender<AppEvent>) {
+    let modified_at = |paths: &[PathBuf]| {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect_vec()
+    };
+    let watched_files = |config_file: Option<&ConfigFile>| {
+        let mut files = config_file.map_or_else(|| vec![config_path.clone()], |file| file.files.clone());
+        if let Some(theme_path) = config_file.and_then(|file| config_path.parent().and_then(|dir| file.theme_path(dir)))
+        {
+            files.extend(UiConfigFile::files(&theme_path));
+        }
+        files
+    };
+
+    let contents = |paths: &[PathBuf]| paths.iter().map(|path| std::fs::read(path).ok()).collect_vec();
+
+    let mut watched = watched_files(ConfigFile::read(&config_path).ok().as_ref());
+    let mut last_modified = modified_at(&watched);
+    let mut last_contents = contents(&watched);
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+        if modified_at(&watched) == last_modified {
+            continue;
+        }
+        // Every reload leaks the config so files which were only touched or saved without changes
+        // do not trigger one
+        last_modified = modified_at(&watched);
+        if contents(&watched) == last_contents {
+            continue;
+        }
+
+        let config_file = ConfigFile::read(&config_path);
+        watched = watched_files(config_file.as_ref().ok());
+        last_modified = modified_at(&watched);
+        last_contents = contents(&watched);
+        let config = config_file.and_then(|file| file.into_config(Some(&config_path), None, None, None, true));
+        log::debug!(path:? = config_path, watched:?, success = config.is_ok(); "Config changed");
+
+        if sender.send(AppEvent::ConfigChanged(config.map(Box::new))).is_err() {
+            log::error!("Main task is not running, stopping config watcher");
+            return;
+        }
+    }
+}
+
 #[allow(clippy::needless_pass_by_value)]
 fn worker_task(
     work_request_receiver: std::sync::mpsc::Receiver<WorkRequest>,
@@ -346,6 +624,7 @@ fn main_task<B: Backend + std::io::Write>(
     mut client: Client<'_>,
     mut render_loop: RenderLoop,
     mut terminal: Terminal<B>,
+    mut idle_handle: IdleHandle,
 ) {
     let mut ui = Ui::new(&context).expect("UI to be created correctly");
     let event_receiver = event_receiver;
@@ -354,8 +633,10 @@ fn main_task<B: Backend + std::io::Write>(
     let max_fps = 30f64;
     let min_frame_duration = Duration::from_secs_f64(1f64 / max_fps);
     let mut last_render = std::time::Instant::now().sub(Duration::from_secs(10));
-    ui.before_show(&mut context, &mut client)
-        .expect("Initial render init to succeed");
+    if !context.connection.is_disconnected() {
+        ui.before_show(&mut context, &mut client)
+            .expect("Initial render init to succeed");
+    }
 
     loop {
         let now = std::time::Instant::now();
@@ -370,12 +651,47 @@ fn main_task<B: Backend + std::io::Write>(
                 Err(std::sync::mpsc::RecvTimeoutError::Timeout) => None,
                 Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => None,
             }
+        } else if context.connection.is_disconnected() {
+            // Tick every second to keep the countdown of the disconnected overlay up to date
+            let event = event_receiver.recv_timeout(Duration::from_secs(1)).ok();
+            render_wanted |= event.is_none();
+            event
         } else {
             event_receiver.recv().ok()
         };
 
+        if let ConnectionState::Disconnected { attempt, retry_at } = context.connection {
+            if retry_at <= std::time::Instant::now() {
+                match reconnect(&mut context, &mut client, &mut ui, &mut render_loop, &idle_handle) {
+                    Ok(()) => status_info!("Connected to MPD"),
+                    Err(err) => {
+                        log::warn!(error:? = err, attempt; "Failed to reconnect to MPD");
+                        client.disconnect();
+                        context.connection = ConnectionState::disconnected(attempt.saturating_add(1));
+                    }
+                }
+                render_wanted = true;
+            }
+        }
+
         if let Some(event) = event {
             match event {
+                // Nothing but quitting works without a connection to MPD
+                AppEvent::UserKeyInput(key) if context.connection.is_disconnected() => {
+                    let mut key: shared::key_event::KeyEvent = key.into();
+                    if matches!(key.as_global_action(&context), Some(GlobalAction::Quit)) {
+                        if let Err(err) = ui.on_event(UiEvent::Exit, &mut context, &mut client) {
+                            error!(error:? = err; "UI failed to handle quit event");
+                        }
+                        break;
+                    }
+                }
+                AppEvent::UserMouseInput(_) if context.connection.is_disconnected() => {}
+                AppEvent::IdleEvent(_) if context.connection.is_disconnected() => {}
+                AppEvent::RequestStatusUpdate if context.connection.is_disconnected() => {}
+                AppEvent::Remote(_) if context.connection.is_disconnected() => {
+                    status_warn!("Remote command ignored because rmpc is not connected to MPD");
+                }
                 AppEvent::UserKeyInput(key) => match ui.handle_key(&mut key.into(), &mut context, &mut client) {
                     Ok(ui::KeyHandleResult::None) => continue,
                     Ok(ui::KeyHandleResult::Quit) => {
@@ -389,6 +705,19 @@ fn main_task<B: Backend + std::io::Write>(
                         render_wanted = true;
                     }
                 },
+                AppEvent::Remote(message) => match ui.handle_control_message(message, &mut context, &mut client) {
+                    Ok(ui::KeyHandleResult::None) => render_wanted = true,
+                    Ok(ui::KeyHandleResult::Quit) => {
+                        if let Err(err) = ui.on_event(UiEvent::Exit, &mut context, &mut client) {
+                            error!(error:? = err; "UI failed to handle quit event");
+                        }
+                        break;
+                    }
+                    Err(err) => {
+                        status_error!(err:?; "Error: {}", err.to_status());
+                        render_wanted = true;
+                    }
+                },
                 AppEvent::UserMouseInput(ev) => match ui.handle_mouse_event(ev, &mut client, &mut context) {
                     Ok(()) => {}
                     Err(err) => {
@@ -458,6 +787,59 @@ fn main_task<B: Backend + std::io::Write>(
                         render_wanted = true;
                     }
                 },
+                AppEvent::AlbumArt { generation, data } => {
+                    if context.album_art.is_current(generation) {
+                        if let Err(err) = ui.on_event(UiEvent::AlbumArt(data), &mut context, &mut client) {
+                            error!(error:? = err; "UI failed to handle album art event");
+                        }
+                    }
+                }
+                AppEvent::ConnectionLost => {
+                    if !context.connection.is_disconnected() {
+                        status_warn!("Lost connection to MPD");
+                        client.disconnect();
+                        try_skip!(render_loop.stop(), "Failed to stop render loop");
+                        if let Err(err) = ui.on_event(UiEvent::Disconnected, &mut context, &mut client) {
+                            error!(error:? = err; "UI failed to handle disconnected event");
+                        }
+                        context.connection = ConnectionState::disconnected(0);
+                    }
+                    render_wanted = true;
+                }
+                AppEvent::IdleReconnected(interrupter) => idle_handle.interrupter = interrupter,
+                AppEvent::ConfigChanged(config) => {
+                    match context
+                        .apply_reloaded_config(config.map(|config| *config), &mut client)
+                        .and_then(|()| ui.on_config_changed(&mut context, &mut client))
+                    {
+                        Ok(()) => status_info!("Config reloaded"),
+                        Err(err) => status_error!(err:?; "{}", err.to_status()),
+                    }
+                    full_rerender_wanted = true;
+                    render_wanted = true;
+                }
+                AppEvent::SwitchServer(name) => {
+                    match switch_server(
+                        &name,
+                        &mut context,
+                        &mut client,
+                        &mut ui,
+                        &mut render_loop,
+                        &mut idle_handle,
+                    ) {
+                        Ok(()) => status_info!("Switched to server '{name}'"),
+                        Err(err) => status_error!(err:?; "Failed to switch to server '{name}': {}", err.to_status()),
+                    }
+                    full_rerender_wanted = true;
+                    render_wanted = true;
+                }
+                AppEvent::SwitchPartition(name) => {
+                    match switch_partition(&name, &mut context, &mut client, &mut idle_handle) {
+                        Ok(()) => status_info!("Switched to partition '{name}'"),
+                        Err(err) => status_error!(err:?; "Failed to switch to partition '{name}': {}", err.to_status()),
+                    }
+                    render_wanted = true;
+                }
             }
         }
         if render_wanted {
@@ -499,7 +881,7 @@ fn handle_idle_event(
 ) -> Result<()> {
     match event {
         IdleEvent::Mixer => {
-            if context.supported_commands.contains("getvol") {
+            if context.capabilities.supports(Feature::GetVolume) {
                 context.status.volume = try_ret!(client.get_volume(), "Failed to get volume");
             } else {
                 context.status = try_ret!(client.get_status(), "Failed to get status");
@@ -520,19 +902,22 @@ fn handle_idle_event(
             if context.status.song.is_some_and(|id| Some(id) != current_song_id) {
                 if let Some(command) = context.config.on_song_change {
                     let env = match context.get_current_song(client) {
-                        Ok(Some(song)) => song
-                            .metadata
-                            .into_iter()
-                            .map(|(mut k, v)| {
-                                k.make_ascii_uppercase();
-                                (k, v)
-                            })
-                            .chain(std::iter::once(("FILE".to_owned(), song.file)))
-                            .chain(std::iter::once((
-                                "DURATION".to_owned(),
-                                song.duration.map_or_else(String::new, |d| d.to_string()),
-                            )))
-                            .collect_vec(),
+                        Ok(Some(song)) => {
+                            // Scripts get all tags of the song, not only those used by the formats
+                            let song = context.with_all_tags(song, client);
+                            song.metadata
+                                .into_iter()
+                                .map(|(mut k, v)| {
+                                    k.make_ascii_uppercase();
+                                    (k, v)
+                                })
+                                .chain(std::iter::once(("FILE".to_owned(), song.file)))
+                                .chain(std::iter::once((
+                                    "DURATION".to_owned(),
+                                    song.duration.map_or_else(String::new, |d| d.to_string()),
+                                )))
+                                .collect_vec()
+                        }
                         Ok(None) => {
                             status_error!("No song found when executing on_song_change");
                             Vec::new()
@@ -547,46 +932,258 @@ fn handle_idle_event(
                 };
             }
         }
-        IdleEvent::Playlist => {
-            let queue = client.playlist_info()?;
-            context.queue = queue.unwrap_or_default();
-        }
+        IdleEvent::Playlist => context.sync_queue(client)?,
+        IdleEvent::Sticker => context.fetch_queue_ratings(client),
+        IdleEvent::Partition => context.status = try_ret!(client.get_status(), "Failed to get status"),
         IdleEvent::StoredPlaylist => {}
         IdleEvent::Database => {}
         IdleEvent::Update => {}
-        IdleEvent::Output
-        | IdleEvent::Partition
-        | IdleEvent::Sticker
-        | IdleEvent::Subscription
-        | IdleEvent::Message
-        | IdleEvent::Neighbor
-        | IdleEvent::Mount => {
+        // Messages can only be read by the subscribed client so they are handled by the idle task
+        IdleEvent::Message => {}
+        IdleEvent::Subscription => {}
+        IdleEvent::Output | IdleEvent::Neighbor | IdleEvent::Mount => {
             warn!(event:?; "Received unhandled event");
         }
     };
     Ok(())
 }
 
-fn idle_task(mut idle_client: Client<'_>, sender: std::sync::mpsc::Sender<AppEvent>) {
-    let mut error_count = 0;
+fn handle_messages(messages: Messages, config: &'static Config) {
+    for Message { channel, message } in messages {
+        if let Some(Channel {
+            on_message: Some(command),
+            ..
+        }) = config.channels.iter().find(|c| c.name == channel)
+        {
+            run_external(command, vec![("CHANNEL", channel), ("MESSAGE", message)]);
+        } else {
+            status_info!("[{channel}] {message}");
+        }
+    }
+}
+
+/// Reconnects the main client and brings everything cached from MPD up to date. The idle client
+/// is told to reconnect only after the main one succeeded.
+fn reconnect(
+    context: &mut context::AppContext,
+    client: &mut Client<'_>,
+    ui: &mut Ui<'_>,
+    render_loop: &mut RenderLoop,
+    idle_handle: &IdleHandle,
+) -> Result<()> {
+    client.reconnect()?;
+    let album_art_method = context.config.album_art.method;
+    context.resync(client)?;
+    context.connection = ConnectionState::Connected;
+    idle_handle.command_sender.send(IdleCommand::Reconnect)?;
+
+    if context.status.state == mpd::commands::status::State::Play {
+        render_loop.start()?;
+    }
+    ui.on_event(UiEvent::Reconnected, context, client)?;
+    // Server might have been replaced by one with different album art support
+    if context.config.album_art.method != album_art_method {
+        return ui.on_config_changed(context, client);
+    }
+    ui.before_show(context, client)?;
+    Ok(())
+}
+
+/// Used by the main task to make the idle client follow it when switching partitions and
+/// reconnecting
+struct IdleHandle {
+    command_sender: std::sync::mpsc::Sender<IdleCommand>,
+    interrupter: IdleInterrupter,
+}
+
+#[derive(Debug)]
+enum IdleCommand {
+    SwitchPartition(String),
+    /// Sent once the main client connected to the new server
+    SwitchServer {
+        address: config::MpdAddress<'static>,
+        password: Option<config::address::MpdPassword<'static>>,
+    },
+    /// Sent once the main client reconnected, the idle client waits for it after losing connection
+    Reconnect,
+}
+
+fn switch_partition(
+    name: &str,
+    context: &mut context::AppContext,
+    client: &mut Client<'_>,
+    idle_handle: &mut IdleHandle,
+) -> Result<()> {
+    client.switch_to_partition(name)?;
+    // Queue versions of different partitions are unrelated
+    context.queue_version = None;
+    idle_handle
+        .command_sender
+        .send(IdleCommand::SwitchPartition(name.to_owned()))?;
+    idle_handle.interrupter.interrupt()?;
+
+    // Everything except the database is partition specific so refresh it as if it changed
+    for event in [
+        IdleEvent::Player,
+        IdleEvent::Playlist,
+        IdleEvent::Mixer,
+        IdleEvent::Options,
+    ] {
+        context.app_event_sender.send(AppEvent::IdleEvent(event))?;
+    }
+    Ok(())
+}
+
+/// Connects the main client to the server profile with the given name and rebuilds the context and
+/// UI for it. The current server is kept when the new one cannot be reached.
+fn switch_server(
+    name: &str,
+    context: &mut context::AppContext,
+    client: &mut Client<'_>,
+    ui: &mut Ui<'_>,
+    render_loop: &mut RenderLoop,
+    idle_handle: &mut IdleHandle,
+) -> Result<()> {
+    let server = *context
+        .config
+        .servers
+        .iter()
+        .find(|server| server.name == name)
+        .with_context(|| format!("Server profile '{name}' does not exist"))?;
+
+    let (previous_address, previous_password) = client.server();
+    client.switch_server(server.address, server.password);
+    if let Err(err) = client.reconnect() {
+        client.switch_server(previous_address, previous_password);
+        if !context.connection.is_disconnected() && client.reconnect().is_err() {
+            context.app_event_sender.send(AppEvent::ConnectionLost)?;
+        }
+        return Err(err.into());
+    }
+
+    idle_handle.command_sender.send(IdleCommand::SwitchServer {
+        address: server.address,
+        password: server.password,
+    })?;
+    // Idle client is not idling when the connection was lost, it waits for the command instead
+    if let Err(err) = idle_handle.interrupter.interrupt() {
+        log::debug!(err:?; "Failed to interrupt idle client");
+    }
+
+    try_skip!(render_loop.stop(), "Failed to stop render loop");
+    *context = context::AppContext::try_new(
+        client,
+        Config {
+            address: server.address,
+            password: server.password,
+            ..context.requested_config()
+        },
+        context.app_event_sender.clone(),
+        context.work_sender.clone(),
+        context.album_art.clone(),
+    )?;
+    if context.status.state == mpd::commands::status::State::Play {
+        render_loop.start()?;
+    }
+
+    ui.on_event(UiEvent::Reconnected, context, client)?;
+    ui.on_config_changed(context, client)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn idle_task(
+    mut idle_client: Client<'_>,
+    sender: std::sync::mpsc::Sender<AppEvent>,
+    command_receiver: std::sync::mpsc::Receiver<IdleCommand>,
+    config: &'static Config,
+) {
     let sender = sender;
+    // Messages are delivered only to the subscribed client and the idle client is the one
+    // which gets notified about them. Subscriptions are restored by the client itself on reconnect.
+    let mut subscribed = false;
+    // Command which stopped the client from idling, handled before any newer one
+    let mut pending = None;
+    // Latest partition the main client switched to while this one was disconnected
+    let mut partition = None;
     loop {
-        let events = match idle_client.idle(None) {
-            Ok(val) => val,
-            Err(err) => {
-                if error_count > 5 {
-                    error!(err:?; "Unexpected error when receiving idle events");
-                    break;
+        if !idle_client.is_connected() {
+            // Main task drives the reconnection so both clients follow the same backoff
+            match pending.take().map_or_else(|| command_receiver.recv(), Ok) {
+                Ok(IdleCommand::Reconnect) => {}
+                Ok(IdleCommand::SwitchServer { address, password }) => {
+                    partition = None;
+                    idle_client.switch_server(address, password);
                 }
+                Ok(IdleCommand::SwitchPartition(name)) => {
+                    partition = Some(name);
+                    continue;
+                }
+                Err(_) => break,
+            }
+            reconnect_idle_client(&mut idle_client, &sender);
+            // Reconnecting restores the previous partition, the switch is applied right after
+            pending = partition.take().map(IdleCommand::SwitchPartition);
+            continue;
+        }
+
+        if !subscribed {
+            for channel in config.channels {
+                try_skip!(
+                    idle_client.subscribe(channel.name),
+                    "Failed to subscribe to MPD channel"
+                );
+            }
+            subscribed = true;
+        }
+
+        for command in pending.take().into_iter().chain(command_receiver.try_iter()) {
+            match command {
+                IdleCommand::SwitchPartition(partition) => try_skip!(
+                    idle_client.switch_to_partition(&partition),
+                    "Failed to switch idle client to partition"
+                ),
+                IdleCommand::SwitchServer { address, password } => {
+                    idle_client.switch_server(address, password);
+                    reconnect_idle_client(&mut idle_client, &sender);
+                }
+                IdleCommand::Reconnect => {}
+            }
+        }
+        if !idle_client.is_connected() {
+            continue;
+        }
+
+        // Commands are checked again right before idling, an interrupt sent before that would be lost
+        let events = match idle_client.idle_unless(&[], || {
+            pending = command_receiver.try_recv().ok();
+            pending.is_some()
+        }) {
+            Ok(Some(val)) => val,
+            Ok(None) => continue,
+            Err(err @ MpdError::Mpd(_)) => {
                 warn!(err:?; "Unexpected error when receiving idle events");
-                error_count += 1;
-                std::thread::sleep(Duration::from_secs(error_count));
+                std::thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+            Err(err) => {
+                warn!(err:?; "Lost connection to MPD while waiting for idle events");
+                idle_client.disconnect();
+                try_skip!(
+                    sender.send(AppEvent::ConnectionLost),
+                    "Failed to send connection lost event"
+                );
                 continue;
             }
         };
 
         for event in events {
             trace!(idle_event:? = event; "Received idle event");
+            if matches!(event, IdleEvent::Message) {
+                match idle_client.read_messages() {
+                    Ok(messages) => handle_messages(messages, config),
+                    Err(err) => error!(error:? = err; "Failed to read messages"),
+                }
+            }
             if let Err(err) = sender.send(AppEvent::IdleEvent(event)) {
                 error!(error:? = err; "Failed to send app event");
             }
@@ -594,6 +1191,27 @@ fn idle_task(mut idle_client: Client<'_>, sender: std::sync::mpsc::Sender<AppEve
     }
 }
 
+/// Main task is notified about the new interrupter on success and about the lost connection
+/// otherwise
+fn reconnect_idle_client(idle_client: &mut Client<'_>, sender: &std::sync::mpsc::Sender<AppEvent>) {
+    if let Err(err) = idle_client.reconnect() {
+        warn!(err:?; "Failed to reconnect idle client");
+        idle_client.disconnect();
+        try_skip!(
+            sender.send(AppEvent::ConnectionLost),
+            "Failed to send connection lost event"
+        );
+        return;
+    }
+    match idle_client.idle_interrupter() {
+        Ok(interrupter) => try_skip!(
+            sender.send(AppEvent::IdleReconnected(interrupter)),
+            "Failed to send idle reconnected event"
+        ),
+        Err(err) => error!(err:?; "Failed to create idle interrupter"),
+    }
+}
+
 fn input_poll_task(user_input_tx: std::sync::mpsc::Sender<AppEvent>) {
     let user_input_tx = user_input_tx;
     let mut mouse_event_tracker = MouseEventTracker::default();
@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use strum::Display;
+
+use super::{errors::MpdError, mpd_client::MpdClient, version::Version};
+
+type MpdResult<T> = Result<T, MpdError>;
+
+/// Features of rmpc which are only available with some MPD servers, either because they depend on
+/// the server version or on commands which can be disabled or unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Feature {
+    #[strum(to_string = "Album art")]
+    AlbumArt,
+    #[strum(to_string = "Song ratings")]
+    Stickers,
+    #[strum(to_string = "Partitions")]
+    Partitions,
+    #[strum(to_string = "Decoder list")]
+    Decoders,
+    #[strum(to_string = "Querying volume")]
+    GetVolume,
+    #[strum(to_string = "Oneshot single mode")]
+    SingleOneshot,
+    #[strum(to_string = "Oneshot consume mode")]
+    ConsumeOneshot,
+    #[strum(to_string = "Limiting tag types")]
+    TagTypes,
+    #[strum(to_string = "Positions relative to the current song")]
+    RelativePositions,
+}
+
+/// Registry of what the connected MPD server supports, built from `commands`, `tagtypes`,
+/// `urlhandlers` and the protocol version announced in the handshake
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub version: Version,
+    commands: HashSet<String>,
+    tag_types: HashSet<String>,
+    url_handlers: HashSet<String>,
+    /// Tags song responses were limited to, None when all tags are sent
+    limited_tag_types: Option<Vec<&'static str>>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            version: Version::new(0, 0, 0),
+            commands: HashSet::new(),
+            tag_types: HashSet::new(),
+            url_handlers: HashSet::new(),
+            limited_tag_types: None,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Queries the server. `tagtypes` only lists tags enabled for the connection so any tag limit
+    /// has to be lifted beforehand.
+    pub fn fetch(client: &mut impl MpdClient) -> MpdResult<Self> {
+        let commands: HashSet<String> = client.commands()?.0.into_iter().collect();
+        let tag_types = if commands.contains("tagtypes") {
+            client
+                .tag_types()?
+                .0
+                .into_iter()
+                .map(|tag| tag.to_lowercase())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let url_handlers = if commands.contains("urlhandlers") {
+            client.url_handlers()?.0.into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+
+        let result = Self {
+            version: client.version(),
+            commands,
+            tag_types,
+            url_handlers,
+            limited_tag_types: None,
+        };
+        log::info!(capabilities:? = result; "Server capabilities");
+
+        Ok(result)
+    }
+
+    pub fn supports_command(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+
+    pub fn supports_tag(&self, tag: &str) -> bool {
+        self.tag_types.contains(&tag.to_lowercase())
+    }
+
+    /// Whether MPD can play urls with the given scheme, eg. `http://`
+    pub fn supports_url_handler(&self, scheme: &str) -> bool {
+        self.url_handlers.contains(scheme)
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::AlbumArt => self.supports_command("albumart") && self.supports_command("readpicture"),
+            Feature::Stickers => self.supports_command("sticker"),
+            Feature::Partitions => self.supports_command("partition") && self.supports_command("listpartitions"),
+            Feature::Decoders => self.supports_command("decoders"),
+            Feature::GetVolume => self.supports_command("getvol"),
+            Feature::SingleOneshot => self.version >= Version::new(0, 21, 0),
+            Feature::ConsumeOneshot => self.version >= Version::new(0, 24, 0),
+            Feature::TagTypes => self.supports_command("tagtypes") && self.version >= Version::new(0, 21, 0),
+            Feature::RelativePositions => self.version >= Version::new(0, 23, 0),
+        }
+    }
+
+    /// Tags song responses are limited to, None when all tags are sent
+    pub fn limited_tag_types(&self) -> Option<&[&'static str]> {
+        self.limited_tag_types.as_deref()
+    }
+
+    /// Limits tags in song responses of the client to the given ones. Tags unknown to the server
+    /// are skipped because MPD rejects the whole command otherwise.
+    pub fn limit_tag_types(
+        &mut self,
+        client: &mut impl MpdClient,
+        tags: impl IntoIterator<Item = &'static str>,
+    ) -> MpdResult<()> {
+        if !self.supports(Feature::TagTypes) {
+            return Ok(());
+        }
+
+        let mut tags: Vec<&'static str> = tags.into_iter().filter(|tag| self.supports_tag(tag)).collect();
+        tags.sort_unstable_by_key(|tag| tag.to_lowercase());
+        tags.dedup_by_key(|tag| tag.to_lowercase());
+
+        log::debug!(tags:? = tags; "Limiting tag types");
+        client.tag_types_clear()?;
+        client.tag_types_enable(&tags)?;
+        self.limited_tag_types = Some(tags);
+
+        Ok(())
+    }
+
+    /// Runs `f` with all tags enabled, used when all tags of a song have to be shown
+    pub fn with_all_tag_types<C: MpdClient, T>(
+        &self,
+        client: &mut C,
+        f: impl FnOnce(&mut C) -> MpdResult<T>,
+    ) -> MpdResult<T> {
+        let Some(tags) = self.limited_tag_types() else {
+            return f(client);
+        };
+
+        client.tag_types_all()?;
+        let result = f(client);
+        client.tag_types_clear()?;
+        client.tag_types_enable(tags)?;
+
+        result
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::HashSet;
+
+    use itertools::Itertools;
+
+    use crate::{
+        mpd::{
+            client::Client,
+            commands::{lsinfo::FileOrDir, LsInfo},
+            mpd_client::MpdClient,
+            version::Version,
+        },
+        tests::fixtures::mpd_server::{FakeMpdServer, FakeSong},
+    };
+
+    use super::{Capabilities, Feature};
+
+    fn capabilities(version: Version, commands: &[&str]) -> Capabilities {
+        Capabilities {
+            version,
+            commands: commands.iter().map(|c| (*c).to_owned()).collect(),
+            tag_types: HashSet::from(["artist".to_owned(), "album".to_owned()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn features_depend_on_commands() {
+        let capabilities = capabilities(Version::new(0, 24, 0), &["albumart", "sticker"]);
+
+        assert!(capabilities.supports(Feature::Stickers));
+        assert!(!capabilities.supports(Feature::AlbumArt));
+        assert!(!capabilities.supports(Feature::Partitions));
+    }
+
+    #[test]
+    fn features_depend_on_version() {
+        let capabilities = capabilities(Version::new(0, 23, 5), &["tagtypes"]);
+
+        assert!(capabilities.supports(Feature::SingleOneshot));
+        assert!(capabilities.supports(Feature::TagTypes));
+        assert!(!capabilities.supports(Feature::ConsumeOneshot));
+    }
+
+    #[test]
+    fn limits_song_tags_and_restores_them_after_reconnect() {
+        let server = FakeMpdServer::start().with_library([FakeSong::new("song.flac")
+            .with_tag("Title", "title")
+            .with_tag("Artist", "artist")
+            .with_tag("Genre", "genre")]);
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut capabilities = Capabilities::fetch(&mut client).unwrap();
+
+        capabilities
+            .limit_tag_types(&mut client, ["title", "Artist", "NotATag"])
+            .unwrap();
+        server.drop_connections();
+        let limited = client.lsinfo(Some("song.flac")).unwrap();
+        let all = capabilities
+            .with_all_tag_types(&mut client, |client| client.lsinfo(Some("song.flac")))
+            .unwrap();
+        let limited_again = client.lsinfo(Some("song.flac")).unwrap();
+
+        let tags = |songs: LsInfo| -> Vec<String> {
+            songs
+                .0
+                .into_iter()
+                .flat_map(|item| match item {
+                    FileOrDir::File(song) => song.metadata.into_keys().sorted().collect_vec(),
+                    FileOrDir::Dir(_) => Vec::new(),
+                })
+                .collect()
+        };
+        assert_eq!(capabilities.limited_tag_types(), Some(["Artist", "title"].as_slice()));
+        assert_eq!(tags(limited), ["artist", "title"]);
+        assert_eq!(tags(all), ["artist", "genre", "title"]);
+        assert_eq!(tags(limited_again), ["artist", "title"]);
+    }
+
+    #[test]
+    fn tags_are_case_insensitive() {
+        let capabilities = capabilities(Version::new(0, 24, 0), &[]);
+
+        assert!(capabilities.supports_tag("Artist"));
+        assert!(!capabilities.supports_tag("AlbumArtist"));
+    }
+}
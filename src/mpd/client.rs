@@ -1,7 +1,11 @@
 use std::{
     io::{BufRead, BufReader, Write},
-    net::TcpStream,
+    net::{TcpStream, ToSocketAddrs},
     os::unix::net::UnixStream,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
 };
 
 use crate::{
@@ -11,11 +15,14 @@ use crate::{
 };
 
 use super::{
+    commands::IdleEvent,
     errors::MpdError,
     proto_client::{ProtoClient, SocketClient},
+    transcript::{Transcript, TranscriptEntry},
     version::Version,
 };
 use anyhow::Result;
+use itertools::Itertools;
 use log::debug;
 
 type MpdResult<T> = Result<T, MpdError>;
@@ -33,6 +40,18 @@ pub struct Client<'name> {
     reconnect: bool,
     addr: MpdAddress<'name>,
     password: Option<MpdPassword<'name>>,
+    /// Partition the client switched to, restored after reconnect
+    pub(super) partition: Option<String>,
+    /// Channels the client is subscribed to, restored after reconnect
+    pub(super) subscriptions: Vec<String>,
+    /// Tags enabled in song responses, None when all tags are enabled. Restored after reconnect
+    pub(super) tag_types: Option<Vec<String>>,
+    cancel_token: Option<CancelToken>,
+    /// Read timeout of the connection, kept so it can be restored after reconnect
+    read_timeout: Option<std::time::Duration>,
+    transcript: Option<Transcript>,
+    /// Whether the client waits for an `idle` response, shared with its [`IdleInterrupter`]s
+    idling: Arc<Mutex<bool>>,
     pub version: Version,
 }
 
@@ -46,9 +65,15 @@ impl std::fmt::Debug for Client<'_> {
     }
 }
 
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 enum TcpOrUnixStream {
     Unix(UnixStream),
     Tcp(TcpStream),
+    /// Used while the client is not connected. All IO fails immediately with
+    /// [`std::io::ErrorKind::NotConnected`] instead of trying to reconnect.
+    Disconnected,
 }
 
 impl TcpOrUnixStream {
@@ -60,6 +85,7 @@ impl TcpOrUnixStream {
             TcpOrUnixStream::Tcp(s) => {
                 s.set_write_timeout(duration)?;
             }
+            TcpOrUnixStream::Disconnected => {}
         }
         Ok(())
     }
@@ -72,6 +98,7 @@ impl TcpOrUnixStream {
             TcpOrUnixStream::Tcp(s) => {
                 s.set_read_timeout(duration)?;
             }
+            TcpOrUnixStream::Disconnected => {}
         }
         Ok(())
     }
@@ -80,15 +107,39 @@ impl TcpOrUnixStream {
         Ok(match self {
             TcpOrUnixStream::Unix(s) => TcpOrUnixStream::Unix(s.try_clone()?),
             TcpOrUnixStream::Tcp(s) => TcpOrUnixStream::Tcp(s.try_clone()?),
+            TcpOrUnixStream::Disconnected => TcpOrUnixStream::Disconnected,
         })
     }
 }
 
+impl TcpOrUnixStream {
+    fn connect(addr: MpdAddress<'_>) -> MpdResult<Self> {
+        match addr {
+            MpdAddress::IpAndPort(addr) => {
+                // Plain connect can block for minutes when the host is unreachable
+                let mut last_err = None;
+                for socket_addr in addr.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT) {
+                        Ok(stream) => return Ok(TcpOrUnixStream::Tcp(stream)),
+                        Err(err) => last_err = Some(err),
+                    }
+                }
+                Err(last_err.map_or_else(
+                    || MpdError::Generic(format!("Failed to resolve address '{addr}'")),
+                    MpdError::from,
+                ))
+            }
+            MpdAddress::SocketPath(addr) => Ok(TcpOrUnixStream::Unix(UnixStream::connect(addr)?)),
+        }
+    }
+}
+
 impl std::io::Read for TcpOrUnixStream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match self {
             TcpOrUnixStream::Unix(s) => s.read(buf),
             TcpOrUnixStream::Tcp(s) => s.read(buf),
+            TcpOrUnixStream::Disconnected => Err(std::io::ErrorKind::NotConnected.into()),
         }
     }
 }
@@ -98,6 +149,7 @@ impl std::io::Write for TcpOrUnixStream {
         match self {
             TcpOrUnixStream::Unix(s) => s.write(buf),
             TcpOrUnixStream::Tcp(s) => s.write(buf),
+            TcpOrUnixStream::Disconnected => Err(std::io::ErrorKind::NotConnected.into()),
         }
     }
 
@@ -105,6 +157,7 @@ impl std::io::Write for TcpOrUnixStream {
         match self {
             TcpOrUnixStream::Unix(s) => s.flush(),
             TcpOrUnixStream::Tcp(s) => s.flush(),
+            TcpOrUnixStream::Disconnected => Err(std::io::ErrorKind::NotConnected.into()),
         }
     }
 }
@@ -117,12 +170,9 @@ impl<'name> Client<'name> {
         name: &'name str,
         reconnect: bool,
     ) -> MpdResult<Client<'name>> {
-        let mut stream = match addr {
-            MpdAddress::IpAndPort(addr) => TcpOrUnixStream::Tcp(TcpStream::connect(addr)?),
-            MpdAddress::SocketPath(addr) => TcpOrUnixStream::Unix(UnixStream::connect(addr)?),
-        };
+        let mut stream = TcpOrUnixStream::connect(addr)?;
         stream.set_write_timeout(Some(std::time::Duration::from_secs(1)))?;
-        stream.set_read_timeout(Some(std::time::Duration::from_secs(10)))?;
+        stream.set_read_timeout(Some(DEFAULT_READ_TIMEOUT))?;
         let mut rx = BufReader::new(stream.try_clone()?);
 
         let mut buf = String::new();
@@ -151,6 +201,13 @@ impl<'name> Client<'name> {
             reconnect,
             addr,
             password,
+            partition: None,
+            subscriptions: Vec::new(),
+            tag_types: None,
+            cancel_token: None,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            transcript: None,
+            idling: Arc::default(),
             version,
         };
 
@@ -164,13 +221,62 @@ impl<'name> Client<'name> {
         Ok(client)
     }
 
-    fn reconnect(&mut self) -> MpdResult<&Client> {
-        let mut stream = match self.addr {
-            MpdAddress::IpAndPort(addr) => TcpOrUnixStream::Tcp(TcpStream::connect(addr)?),
-            MpdAddress::SocketPath(addr) => TcpOrUnixStream::Unix(UnixStream::connect(addr)?),
-        };
+    /// Creates a client which is not connected to MPD yet. Every command fails immediately until
+    /// [`Self::reconnect`] succeeds.
+    pub fn disconnected(
+        addr: MpdAddress<'name>,
+        password: Option<MpdPassword<'name>>,
+        name: &'name str,
+        reconnect: bool,
+    ) -> Client<'name> {
+        Self {
+            name,
+            rx: BufReader::new(TcpOrUnixStream::Disconnected),
+            stream: TcpOrUnixStream::Disconnected,
+            reconnect,
+            addr,
+            password,
+            partition: None,
+            subscriptions: Vec::new(),
+            tag_types: None,
+            cancel_token: None,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            transcript: None,
+            idling: Arc::default(),
+            version: Version::new(0, 0, 0),
+        }
+    }
+
+    /// Drops the connection. Partition and subscriptions are kept and restored on
+    /// [`Self::reconnect`].
+    pub fn disconnect(&mut self) {
+        self.rx = BufReader::new(TcpOrUnixStream::Disconnected);
+        self.stream = TcpOrUnixStream::Disconnected;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !matches!(self.stream, TcpOrUnixStream::Disconnected)
+    }
+
+    pub fn server(&self) -> (MpdAddress<'name>, Option<MpdPassword<'name>>) {
+        (self.addr, self.password)
+    }
+
+    /// Drops the connection and points the client to another server. State restored on reconnect
+    /// which belongs to the previous server, ie. partition and tag types, is cleared. Subscriptions
+    /// are kept. Call [`Self::reconnect`] to connect.
+    pub fn switch_server(&mut self, addr: MpdAddress<'name>, password: Option<MpdPassword<'name>>) {
+        self.disconnect();
+        self.addr = addr;
+        self.password = password;
+        self.partition = None;
+        self.tag_types = None;
+    }
+
+    pub fn reconnect(&mut self) -> MpdResult<&Client> {
+        let mut stream = TcpOrUnixStream::connect(self.addr)?;
         stream.set_write_timeout(Some(std::time::Duration::from_secs(1)))?;
-        stream.set_read_timeout(Some(std::time::Duration::from_secs(10)))?;
+        stream.set_read_timeout(self.read_timeout)?;
         let mut rx = BufReader::new(stream.try_clone()?);
 
         let mut buf = String::new();
@@ -198,10 +304,33 @@ impl<'name> Client<'name> {
 
         self.binary_limit(1024 * 1024 * 5)?;
 
+        if let Some(partition) = self.partition.clone() {
+            debug!(partition = partition.as_str(); "Restoring partition after reconnect");
+            self.switch_to_partition(&partition)?;
+        }
+
+        if let Some(tags) = self.tag_types.clone() {
+            debug!(tags:? = tags; "Restoring tag types after reconnect");
+            self.tag_types_clear()?;
+            self.tag_types_enable(&tags.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+
+        // Channels stay in the list when restoring them fails so the next reconnect tries again
+        for channel in self.subscriptions.clone() {
+            debug!(channel = channel.as_str(); "Restoring subscription after reconnect");
+            if let Err(err) = self
+                .send(&format!("subscribe \"{channel}\""))
+                .and_then(ProtoClient::read_ok)
+            {
+                log::warn!(channel = channel.as_str(), err:?; "Failed to restore subscription after reconnect");
+            }
+        }
+
         Ok(self)
     }
 
     pub fn set_read_timeout(&mut self, timeout: Option<std::time::Duration>) -> std::io::Result<()> {
+        self.read_timeout = timeout;
         self.stream.set_read_timeout(timeout)
     }
 
@@ -213,11 +342,115 @@ impl<'name> Client<'name> {
         ProtoClient::new(command, self)
     }
 
+    /// Sends all commands at once wrapped in `command_list_ok_begin` and `command_list_end`.
+    /// MPD stops executing the list at the first failed command.
+    pub fn send_ok_list(&mut self, commands: &[String]) -> MpdResult<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        let command = format!("command_list_ok_begin\n{}\ncommand_list_end", commands.join("\n"));
+        ProtoClient::new(&command, self)?.read_ok_list(commands.len())
+    }
+
     fn clear_read_buf(&mut self) -> Result<()> {
         log::trace!("Reinitialized read buffer");
         self.rx = BufReader::new(self.stream.try_clone()?);
         Ok(())
     }
+
+    /// Binary transfers of this client are aborted with [`MpdError::Cancelled`] once the token
+    /// is cancelled
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel_token = token;
+    }
+
+    /// Records all further protocol traffic of this client into the transcript
+    pub fn set_transcript(&mut self, transcript: Option<Transcript>) {
+        self.transcript = transcript;
+    }
+
+    /// Creates a handle which can interrupt the `idle` command this client is blocked on from
+    /// another thread. The handle is bound to the current connection and does nothing after
+    /// the client reconnects.
+    pub fn idle_interrupter(&self) -> MpdResult<IdleInterrupter> {
+        Ok(IdleInterrupter {
+            stream: self.stream.try_clone()?,
+            idling: Arc::clone(&self.idling),
+        })
+    }
+
+    /// Waits for changes in the given subsystems like `idle` unless `interrupted` returns true.
+    /// `interrupted` is checked under the same lock [`IdleInterrupter::interrupt`] takes. An
+    /// interrupt thus either reaches MPD after `idle` was sent or is seen by `interrupted`, in
+    /// which case None is returned without idling.
+    pub fn idle_unless(
+        &mut self,
+        subsystems: &[IdleEvent],
+        interrupted: impl FnOnce() -> bool,
+    ) -> MpdResult<Option<Vec<IdleEvent>>> {
+        let command = if subsystems.is_empty() {
+            "idle".to_owned()
+        } else {
+            format!("idle {}", subsystems.iter().join(" "))
+        };
+
+        let idling = Arc::clone(&self.idling);
+        let mut is_idling = idling.lock().unwrap_or_else(PoisonError::into_inner);
+        if interrupted() {
+            return Ok(None);
+        }
+        let response = self.send(&command);
+        *is_idling = response.is_ok();
+        drop(is_idling);
+
+        let result = response.and_then(ProtoClient::read_response);
+        *idling.lock().unwrap_or_else(PoisonError::into_inner) = false;
+        result.map(Some)
+    }
+}
+
+pub struct IdleInterrupter {
+    stream: TcpOrUnixStream,
+    idling: Arc<Mutex<bool>>,
+}
+
+impl std::fmt::Debug for IdleInterrupter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdleInterrupter")
+    }
+}
+
+impl IdleInterrupter {
+    /// Makes the client return from [`Client::idle_unless`]. `noidle` is only sent while the
+    /// client idles, MPD would ignore it otherwise. A client which does not idle yet checks its
+    /// `interrupted` condition before it does, so whatever the interrupt is for has to be done
+    /// before calling this, eg. sending a command to the thread owning the client.
+    pub fn interrupt(&mut self) -> std::io::Result<()> {
+        let is_idling = self.idling.lock().unwrap_or_else(PoisonError::into_inner);
+        if *is_idling {
+            self.stream.write_all(b"noidle\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Allows aborting binary transfers of a client from another thread. The transfer is aborted
+/// between chunks once the shared generation moves past the one the token was created for.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    generation: Arc<AtomicU64>,
+    expected: u64,
+}
+
+impl CancelToken {
+    pub fn new(generation: Arc<AtomicU64>, expected: u64) -> Self {
+        Self { generation, expected }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.generation.load(Ordering::Relaxed) != self.expected
+    }
 }
 
 impl<'name> SocketClient for Client<'name> {
@@ -236,4 +469,14 @@ impl<'name> SocketClient for Client<'name> {
     fn clear_read_buf(&mut self) -> Result<()> {
         self.clear_read_buf()
     }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    fn record(&self, entry: TranscriptEntry<'_>) {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(self.name, entry);
+        }
+    }
 }
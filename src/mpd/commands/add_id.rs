@@ -0,0 +1,18 @@
+use derive_more::{AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled, ParseErrorExt};
+
+/// Response of one or more `addid` commands, ids of the added songs in the order they were added
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, Into)]
+pub struct AddedIds(pub Vec<u32>);
+
+impl FromMpd for AddedIds {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "id" => self.0.push(value.parse().logerr(key, &value)?),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
@@ -4,12 +4,20 @@ use serde::Serialize;
 
 use crate::mpd::{errors::MpdError, FromMpd, LineHandled, ParseErrorExt};
 
+use super::stickers::RATING_STICKER;
+
 #[derive(Default, Serialize, PartialEq, Eq, Clone)]
 pub struct Song {
     pub id: u32,
     pub file: String,
     pub duration: Option<Duration>,
     pub metadata: HashMap<String, String>,
+    /// Priority of the song in the queue, only songs in the queue with non-zero priority have it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// Stickers are not part of the song response, they have to be fetched separately
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stickers: Option<HashMap<String, String>>,
 }
 
 impl std::fmt::Debug for Song {
@@ -38,6 +46,13 @@ impl Song {
     pub fn album(&self) -> Option<&String> {
         self.metadata.get("album")
     }
+
+    pub fn rating(&self) -> Option<u8> {
+        self.stickers
+            .as_ref()
+            .and_then(|stickers| stickers.get(RATING_STICKER))
+            .and_then(|rating| rating.parse().ok())
+    }
 }
 
 impl FromMpd for Song {
@@ -48,6 +63,7 @@ impl FromMpd for Song {
             "duration" => {
                 self.duration = Some(Duration::from_secs_f64(value.parse().logerr(key, &value)?));
             }
+            "prio" => self.priority = Some(value.parse().logerr(key, &value)?),
             "time" | "format" => {} // deprecated or ignored
             key => {
                 self.metadata.insert(key.to_owned(), value);
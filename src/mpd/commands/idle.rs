@@ -1,8 +1,11 @@
+use serde::Serialize;
+
 use crate::mpd::errors::MpdError;
 use crate::mpd::{FromMpd, LineHandled};
 
-#[derive(Debug, Clone, Copy, strum::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, strum::Display, Serialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum IdleEvent {
     Player, // the player has been started, stopped or seeked or tags of the currently playing song have changed (e.g. received from stream)
     Mixer,  // the volume has been changed
@@ -1,9 +1,10 @@
 use anyhow::anyhow;
 use anyhow::Context;
+use serde::Serialize;
 
 use crate::mpd::{errors::MpdError, FromMpd, LineHandled};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize)]
 pub struct Playlist {
     pub name: String,
     pub last_modified: String,
@@ -0,0 +1,56 @@
+use anyhow::anyhow;
+use anyhow::Context;
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled};
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct Channels(pub Vec<String>);
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct Messages(pub Vec<Message>);
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, Clone)]
+pub struct Message {
+    pub channel: String,
+    pub message: String,
+}
+
+impl FromMpd for Channels {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "channel" => self.0.push(value),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+impl FromMpd for Messages {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        if key == "channel" {
+            self.0.push(Message::default());
+        }
+
+        self.0
+            .last_mut()
+            .context(anyhow!(
+                "No element in accumulator while parsing Messages. Key '{}' Value :'{}'",
+                key,
+                value
+            ))?
+            .next_internal(key, value)
+    }
+}
+
+impl FromMpd for Message {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "channel" => self.channel = value,
+            "message" => self.message = value,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
@@ -1,3 +1,4 @@
+pub mod add_id;
 pub mod current_song;
 pub mod decoders;
 pub mod idle;
@@ -7,12 +8,18 @@ pub mod list_mounts;
 pub mod list_playlist;
 pub mod list_playlists;
 pub mod lsinfo;
+pub mod messages;
 pub mod outputs;
+pub mod partitions;
+pub mod playlist_changes;
 pub mod playlist_info;
+pub mod replay_gain;
 pub mod status;
+pub mod stickers;
 pub mod update;
 pub mod volume;
 
+pub use self::add_id::AddedIds;
 pub use self::current_song::Song;
 pub use self::decoders::Decoder;
 pub use self::idle::IdleEvent;
@@ -20,8 +27,13 @@ pub use self::list_files::ListFiles;
 pub use self::list_mounts::Mounts;
 pub use self::list_playlists::Playlist;
 pub use self::lsinfo::LsInfo;
+pub use self::messages::{Channels, Messages};
 pub use self::outputs::Output;
+pub use self::partitions::Partitions;
+pub use self::playlist_changes::PositionChanges;
+pub use self::replay_gain::{ReplayGainMode, ReplayGainStatus};
 pub use self::status::State;
 pub use self::status::Status;
+pub use self::stickers::{Sticker, Stickers, StickersWithFile};
 pub use self::update::Update;
 pub use self::volume::Volume;
@@ -0,0 +1,17 @@
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled};
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct Partitions(pub Vec<String>);
+
+impl FromMpd for Partitions {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "partition" => self.0.push(value),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::anyhow;
+use anyhow::Context;
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled, ParseErrorExt};
+
+/// Response of `plchangesposid`, positions and ids of all songs in the queue which changed
+/// since the given queue version
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct PositionChanges(pub Vec<PositionChange>);
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, Clone, Copy)]
+pub struct PositionChange {
+    pub pos: usize,
+    pub id: u32,
+}
+
+impl FromMpd for PositionChanges {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        if key == "cpos" {
+            self.0.push(PositionChange::default());
+        }
+
+        self.0
+            .last_mut()
+            .context(anyhow!(
+                "No element in accumulator while parsing PositionChanges. Key '{}' Value :'{}'",
+                key,
+                value
+            ))?
+            .next_internal(key, value)
+    }
+}
+
+impl FromMpd for PositionChange {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "cpos" => self.pos = value.parse().logerr(key, &value)?,
+            "id" => self.id = value.parse().logerr(key, &value)?,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled, ParseErrorExt};
+
+#[derive(Debug, Serialize, Default, Clone, Copy)]
+pub struct ReplayGainStatus {
+    pub mode: ReplayGainMode,
+}
+
+#[derive(Debug, Serialize, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+    Auto,
+}
+
+impl ReplayGainMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            ReplayGainMode::Off => ReplayGainMode::Track,
+            ReplayGainMode::Track => ReplayGainMode::Album,
+            ReplayGainMode::Album => ReplayGainMode::Auto,
+            ReplayGainMode::Auto => ReplayGainMode::Off,
+        }
+    }
+
+    pub fn cycle_back(self) -> Self {
+        match self {
+            ReplayGainMode::Off => ReplayGainMode::Auto,
+            ReplayGainMode::Track => ReplayGainMode::Off,
+            ReplayGainMode::Album => ReplayGainMode::Track,
+            ReplayGainMode::Auto => ReplayGainMode::Album,
+        }
+    }
+
+    pub fn to_mpd_value(self) -> &'static str {
+        match self {
+            ReplayGainMode::Off => "off",
+            ReplayGainMode::Track => "track",
+            ReplayGainMode::Album => "album",
+            ReplayGainMode::Auto => "auto",
+        }
+    }
+}
+
+impl std::fmt::Display for ReplayGainMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ReplayGainMode::Off => "Off",
+                ReplayGainMode::Track => "Track",
+                ReplayGainMode::Album => "Album",
+                ReplayGainMode::Auto => "Auto",
+            }
+        )
+    }
+}
+
+impl std::str::FromStr for ReplayGainMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ReplayGainMode::Off),
+            "track" => Ok(ReplayGainMode::Track),
+            "album" => Ok(ReplayGainMode::Album),
+            "auto" => Ok(ReplayGainMode::Auto),
+            val => Err(anyhow!("Received unknown value for ReplayGainMode '{}'", val)),
+        }
+    }
+}
+
+impl FromMpd for ReplayGainStatus {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "replay_gain_mode" => self.mode = value.parse().logerr(key, &value)?,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use anyhow::Context;
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::mpd::{errors::MpdError, FromMpd, LineHandled};
+
+/// Name of the sticker used to store song ratings. Value is a number between 0 and 10
+/// which is the same convention as other MPD clients use.
+pub const RATING_STICKER: &str = "rating";
+pub const MAX_RATING: u8 = 10;
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, Clone)]
+pub struct Sticker {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, IntoIterator, AsRef, AsMut, Into)]
+pub struct Stickers(pub HashMap<String, String>);
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, Clone)]
+pub struct StickerWithFile {
+    pub file: String,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq, IntoIterator, AsRef, AsMut, Into)]
+pub struct StickersWithFile(pub Vec<StickerWithFile>);
+
+/// Stickers are returned as `sticker: name=value`
+fn split_sticker(value: &str) -> Result<(String, String), MpdError> {
+    let Some((key, value)) = value.split_once('=') else {
+        return Err(MpdError::Generic(format!("Invalid sticker value: '{value}'")));
+    };
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+impl FromMpd for Sticker {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "sticker" => {
+                let (key, value) = split_sticker(&value)?;
+                self.key = key;
+                self.value = value;
+            }
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+impl FromMpd for Stickers {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "sticker" => {
+                let (key, value) = split_sticker(&value)?;
+                self.0.insert(key, value);
+            }
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+impl FromMpd for StickersWithFile {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "file" => self.0.push(StickerWithFile {
+                file: value,
+                ..Default::default()
+            }),
+            "sticker" => {
+                let (key, value) = split_sticker(&value)?;
+                let last = self.0.last_mut().context(anyhow!(
+                    "No element in accumulator while parsing StickersWithFile. Key '{}' Value :'{}'",
+                    key,
+                    value
+                ))?;
+                last.key = key;
+                last.value = value;
+            }
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
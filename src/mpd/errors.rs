@@ -11,6 +11,8 @@ pub enum MpdError {
     Mpd(MpdFailureResponse),
     ValueExpected(String),
     UnsupportedMpdVersion(&'static str),
+    /// Request was aborted by its [`CancelToken`](super::client::CancelToken)
+    Cancelled,
 }
 
 impl std::error::Error for MpdError {}
@@ -30,6 +32,7 @@ impl Display for MpdError {
             MpdError::Mpd(err) => write!(f, "MpdError: '{err}'"),
             MpdError::ValueExpected(val) => write!(f, "Expected value from MPD but got '{val}'"),
             MpdError::UnsupportedMpdVersion(val) => write!(f, "Unsupported MPD version: '{val}'"),
+            MpdError::Cancelled => write!(f, "Request has been cancelled"),
         }
     }
 }
@@ -1,10 +1,12 @@
 use self::errors::MpdError;
 
+pub mod capabilities;
 pub mod client;
 pub mod commands;
 pub mod errors;
 pub mod mpd_client;
 pub mod proto_client;
+pub mod transcript;
 pub mod version;
 
 trait FromMpd
@@ -2,6 +2,7 @@ use std::{collections::BTreeSet, str::FromStr};
 
 use anyhow::Result;
 use derive_more::Deref;
+use itertools::Itertools;
 use strum::AsRefStr;
 
 use crate::shared::{ext::error::ErrorExt, macros::status_error};
@@ -10,7 +11,9 @@ use super::{
     client::Client,
     commands::{
         decoders::Decoders, list::MpdList, list_playlist::FileList, outputs::Outputs, status::OnOffOneshot,
-        volume::Bound, IdleEvent, ListFiles, LsInfo, Mounts, Playlist, Song, Status, Update, Volume,
+        volume::Bound, AddedIds, Channels, IdleEvent, ListFiles, LsInfo, Messages, Mounts, Partitions, Playlist,
+        PositionChanges, ReplayGainMode, ReplayGainStatus, Song, Status, Sticker, Stickers, StickersWithFile, Update,
+        Volume,
     },
     errors::{ErrorCode, MpdError, MpdFailureResponse},
     proto_client::ProtoClient,
@@ -64,9 +67,16 @@ pub trait MpdClient {
     fn binary_limit(&mut self, limit: u64) -> MpdResult<()>;
     fn password(&mut self, password: &str) -> MpdResult<()>;
     fn commands(&mut self) -> MpdResult<MpdList>;
+    fn tag_types(&mut self) -> MpdResult<MpdList>;
+    /// Disables all tags in song responses of this connection
+    fn tag_types_clear(&mut self) -> MpdResult<()>;
+    fn tag_types_enable(&mut self, tags: &[&str]) -> MpdResult<()>;
+    fn tag_types_all(&mut self) -> MpdResult<()>;
+    fn url_handlers(&mut self) -> MpdResult<MpdList>;
     fn update(&mut self, path: Option<&str>) -> MpdResult<Update>;
     fn rescan(&mut self, path: Option<&str>) -> MpdResult<Update>;
-    fn idle(&mut self, subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>>;
+    /// Waits for changes in the given subsystems, or in any subsystem if none are given
+    fn idle(&mut self, subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>>;
     fn noidle(&mut self) -> MpdResult<()>;
     fn get_volume(&mut self) -> MpdResult<Volume>;
     fn set_volume(&mut self, volume: Volume) -> MpdResult<()>;
@@ -89,21 +99,65 @@ pub trait MpdClient {
     fn random(&mut self, enabled: bool) -> MpdResult<()>;
     fn single(&mut self, single: OnOffOneshot) -> MpdResult<()>;
     fn consume(&mut self, consume: OnOffOneshot) -> MpdResult<()>;
+    // Playback options
+    fn crossfade(&mut self, seconds: u32) -> MpdResult<()>;
+    /// Sets the threshold at which songs will be overlapped, in decibels
+    fn mixrampdb(&mut self, db: f32) -> MpdResult<()>;
+    /// Sets additional time subtracted from the overlap calculated by mixrampdb, None disables
+    /// mixramp and falls back to crossfading
+    fn mixrampdelay(&mut self, seconds: Option<f32>) -> MpdResult<()>;
+    fn replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()>;
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus>;
     // Mounts
     fn mount(&mut self, name: &str, path: &str) -> MpdResult<()>;
     fn unmount(&mut self, name: &str) -> MpdResult<()>;
     fn list_mounts(&mut self) -> MpdResult<Mounts>;
     // Current queue
     fn add(&mut self, path: &str) -> MpdResult<()>;
+    /// Adds all paths to the queue in a single command list
+    fn add_multiple(&mut self, paths: &[&str]) -> MpdResult<()>;
+    /// Adds a song to the queue at the given position, or to its end, and returns its id
+    fn add_id(&mut self, path: &str, position: Option<QueueMoveTarget>) -> MpdResult<u32>;
+    /// Adds all songs in order starting at the given position in a single command list and returns
+    /// their ids. Relative positions are evaluated against the current song as it is before the
+    /// first song is added.
+    fn add_ids(&mut self, paths: &[&str], position: Option<QueueMoveTarget>) -> MpdResult<AddedIds>;
     fn clear(&mut self) -> MpdResult<()>;
     fn delete_id(&mut self, id: u32) -> MpdResult<()>;
+    /// Deletes all songs with the given ids from the queue in a single command list
+    fn delete_ids(&mut self, ids: &[u32]) -> MpdResult<()>;
+    /// Deletes songs at the given positions from the queue
+    fn delete_from_queue(&mut self, songs: &SingleOrRange) -> MpdResult<()>;
     fn playlist_info(&mut self) -> MpdResult<Option<Vec<Song>>>;
+    /// Songs in the queue which changed since the given queue version, optionally limited to
+    /// the given range of positions
+    fn playlist_changes(&mut self, version: u32, range: Option<SingleOrRange>) -> MpdResult<Vec<Song>>;
+    /// Positions and ids of songs in the queue which changed since the given queue version
+    fn playlist_changes_pos_id(&mut self, version: u32) -> MpdResult<PositionChanges>;
     fn find(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Vec<Song>>;
     fn search(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Vec<Song>>;
     fn move_id(&mut self, id: u32, to: QueueMoveTarget) -> MpdResult<()>;
+    /// Executes all moves in order in a single command list. Each target is
+    /// evaluated against the queue as it is after the previous moves.
+    fn move_ids(&mut self, moves: &[(u32, QueueMoveTarget)]) -> MpdResult<()>;
+    /// Moves songs at the given positions in the queue
+    fn move_in_queue(&mut self, songs: &SingleOrRange, to: QueueMoveTarget) -> MpdResult<()>;
+    /// Shuffles the whole queue or only the given range of positions
+    fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()>;
+    /// Swaps songs at the given positions
+    fn swap(&mut self, first: usize, second: usize) -> MpdResult<()>;
+    fn swap_ids(&mut self, first: u32, second: u32) -> MpdResult<()>;
+    /// Sets priority of songs in the given range of positions. Songs with higher priority are
+    /// played first in random mode.
+    fn prio(&mut self, priority: u8, range: &SingleOrRange) -> MpdResult<()>;
+    fn prio_ids(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()>;
     fn find_one(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Option<Song>>;
     fn find_add(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<()>;
     fn search_add(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<()>;
+    /// Same as [`Self::find_add`] but inserts the songs at the given position in the queue
+    fn find_add_at(&mut self, filter: &[Filter<'_, '_>], position: QueueMoveTarget) -> MpdResult<()>;
+    /// Same as [`Self::search_add`] but inserts the songs at the given position in the queue
+    fn search_add_at(&mut self, filter: &[Filter<'_, '_>], position: QueueMoveTarget) -> MpdResult<()>;
     fn list_tag(&mut self, tag: Tag, filter: Option<&[Filter<'_, '_>]>) -> MpdResult<MpdList>;
     // Database
     fn lsinfo(&mut self, path: Option<&str>) -> MpdResult<LsInfo>;
@@ -115,12 +169,19 @@ pub trait MpdClient {
     fn list_playlist(&mut self, name: &str) -> MpdResult<FileList>;
     fn list_playlist_info(&mut self, playlist: &str, range: Option<SingleOrRange>) -> MpdResult<Vec<Song>>;
     fn load_playlist(&mut self, name: &str) -> MpdResult<()>;
+    /// Loads all playlists into the queue in a single command list
+    fn load_playlists(&mut self, names: &[&str]) -> MpdResult<()>;
     fn rename_playlist(&mut self, name: &str, new_name: &str) -> MpdResult<()>;
     fn delete_playlist(&mut self, name: &str) -> MpdResult<()>;
     fn delete_from_playlist(&mut self, playlist_name: &str, songs: &SingleOrRange) -> MpdResult<()>;
     fn move_in_playlist(&mut self, playlist_name: &str, range: &SingleOrRange, target_position: usize)
         -> MpdResult<()>;
     fn add_to_playlist(&mut self, playlist_name: &str, uri: &str, target_position: Option<usize>) -> MpdResult<()>;
+    /// Appends all songs to the playlist in a single command list, the playlist is created if it
+    /// does not exist
+    fn add_multiple_to_playlist(&mut self, playlist_name: &str, uris: &[&str]) -> MpdResult<()>;
+    /// Removes all songs from the playlist. Creates an empty playlist if it does not exist.
+    fn clear_playlist(&mut self, name: &str) -> MpdResult<()>;
     fn save_queue_as_playlist(&mut self, name: &str, mode: Option<SaveMode>) -> MpdResult<()>;
     /// This function first invokes [`Self::albumart`].
     /// If no album art is fonud it invokes [`Self::read_picture`].
@@ -131,8 +192,38 @@ pub trait MpdClient {
     fn toggle_output(&mut self, id: u32) -> MpdResult<()>;
     fn enable_output(&mut self, id: u32) -> MpdResult<()>;
     fn disable_output(&mut self, id: u32) -> MpdResult<()>;
+    /// Moves output with the given name to the partition this client is using
+    fn move_output(&mut self, output_name: &str) -> MpdResult<()>;
+    // Partitions
+    /// Switches this client to the given partition. Other connections are not affected.
+    fn switch_to_partition(&mut self, name: &str) -> MpdResult<()>;
+    fn list_partitions(&mut self) -> MpdResult<Partitions>;
+    fn new_partition(&mut self, name: &str) -> MpdResult<()>;
+    fn delete_partition(&mut self, name: &str) -> MpdResult<()>;
     // Decoders
     fn decoders(&mut self) -> MpdResult<Decoders>;
+    // Stickers
+    /// Returns None if the sticker does not exist
+    fn sticker(&mut self, uri: &str, name: &str) -> MpdResult<Option<Sticker>>;
+    fn set_sticker(&mut self, uri: &str, name: &str, value: &str) -> MpdResult<()>;
+    fn delete_sticker(&mut self, uri: &str, name: &str) -> MpdResult<()>;
+    /// Sets the sticker on all songs in a single command list
+    fn set_stickers(&mut self, uris: &[&str], name: &str, value: &str) -> MpdResult<()>;
+    /// Deletes the sticker from all songs in a single command list. Fails when one of the songs
+    /// does not have it.
+    fn delete_stickers(&mut self, uris: &[&str], name: &str) -> MpdResult<()>;
+    fn delete_all_stickers(&mut self, uri: &str) -> MpdResult<()>;
+    fn list_stickers(&mut self, uri: &str) -> MpdResult<Stickers>;
+    /// Searches the sticker database for songs under `uri` which have a sticker with the given name
+    fn find_stickers(&mut self, uri: &str, name: &str) -> MpdResult<StickersWithFile>;
+    // Client to client
+    fn subscribe(&mut self, channel: &str) -> MpdResult<()>;
+    fn unsubscribe(&mut self, channel: &str) -> MpdResult<()>;
+    /// Lists all channels which have at least one subscriber
+    fn channels(&mut self) -> MpdResult<Channels>;
+    /// Reads and consumes messages received on channels this client is subscribed to
+    fn read_messages(&mut self) -> MpdResult<Messages>;
+    fn send_message(&mut self, channel: &str, message: &str) -> MpdResult<()>;
 }
 
 impl MpdClient for Client<'_> {
@@ -173,13 +264,46 @@ impl MpdClient for Client<'_> {
         self.send("commands").and_then(ProtoClient::read_response)
     }
 
+    // Lists tags enabled for this connection
+    fn tag_types(&mut self) -> MpdResult<MpdList> {
+        self.send("tagtypes").and_then(ProtoClient::read_response)
+    }
+
+    fn tag_types_clear(&mut self) -> MpdResult<()> {
+        self.send("tagtypes clear").and_then(ProtoClient::read_ok)?;
+        self.tag_types = Some(Vec::new());
+        Ok(())
+    }
+
+    fn tag_types_enable(&mut self, tags: &[&str]) -> MpdResult<()> {
+        if tags.is_empty() {
+            return Ok(());
+        }
+        self.send(&format!("tagtypes enable {}", tags.join(" ")))
+            .and_then(ProtoClient::read_ok)?;
+        if let Some(enabled) = self.tag_types.as_mut() {
+            enabled.extend(tags.iter().map(|tag| (*tag).to_owned()));
+        }
+        Ok(())
+    }
+
+    fn tag_types_all(&mut self) -> MpdResult<()> {
+        self.send("tagtypes all").and_then(ProtoClient::read_ok)?;
+        self.tag_types = None;
+        Ok(())
+    }
+
+    fn url_handlers(&mut self) -> MpdResult<MpdList> {
+        self.send("urlhandlers").and_then(ProtoClient::read_response)
+    }
+
     // Queries
-    fn idle(&mut self, subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>> {
-        if let Some(subsystem) = subsystem {
-            self.send(&format!("idle {subsystem}"))
-                .and_then(ProtoClient::read_response)
-        } else {
+    fn idle(&mut self, subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>> {
+        if subsystems.is_empty() {
             self.send("idle").and_then(ProtoClient::read_response)
+        } else {
+            self.send(&format!("idle {}", subsystems.iter().join(" ")))
+                .and_then(ProtoClient::read_response)
         }
     }
 
@@ -285,6 +409,31 @@ impl MpdClient for Client<'_> {
         }
     }
 
+    // Playback options
+    fn crossfade(&mut self, seconds: u32) -> MpdResult<()> {
+        self.send(&format!("crossfade {seconds}"))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn mixrampdb(&mut self, db: f32) -> MpdResult<()> {
+        self.send(&format!("mixrampdb {db}")).and_then(ProtoClient::read_ok)
+    }
+
+    fn mixrampdelay(&mut self, seconds: Option<f32>) -> MpdResult<()> {
+        let seconds = seconds.map_or_else(|| "nan".to_owned(), |seconds| seconds.to_string());
+        self.send(&format!("mixrampdelay {seconds}"))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()> {
+        self.send(&format!("replay_gain_mode {}", mode.to_mpd_value()))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus> {
+        self.send("replay_gain_status").and_then(ProtoClient::read_response)
+    }
+
     // Mounts
     fn mount(&mut self, name: &str, path: &str) -> MpdResult<()> {
         self.send(&format!("mount \"{name}\" \"{path}\""))
@@ -304,6 +453,35 @@ impl MpdClient for Client<'_> {
         self.send(&format!("add \"{path}\"")).and_then(ProtoClient::read_ok)
     }
 
+    fn add_multiple(&mut self, paths: &[&str]) -> MpdResult<()> {
+        self.send_ok_list(&paths.iter().map(|path| format!("add \"{path}\"")).collect_vec())
+    }
+
+    fn add_id(&mut self, path: &str, position: Option<QueueMoveTarget>) -> MpdResult<u32> {
+        self.add_ids(&[path], position)?
+            .0
+            .first()
+            .copied()
+            .ok_or_else(|| MpdError::Generic(format!("MPD did not return id of the added song '{path}'")))
+    }
+
+    fn add_ids(&mut self, paths: &[&str], position: Option<QueueMoveTarget>) -> MpdResult<AddedIds> {
+        if paths.is_empty() {
+            return Ok(AddedIds::default());
+        }
+
+        let commands = paths
+            .iter()
+            .enumerate()
+            .map(|(idx, path)| match &position {
+                Some(position) => format!("addid \"{path}\" \"{}\"", position.offset(idx).as_mpd_str()),
+                None => format!("addid \"{path}\""),
+            })
+            .join("\n");
+        self.send(&format!("command_list_begin\n{commands}\ncommand_list_end"))
+            .and_then(ProtoClient::read_response)
+    }
+
     fn clear(&mut self) -> MpdResult<()> {
         self.send("clear").and_then(ProtoClient::read_ok)
     }
@@ -312,15 +490,87 @@ impl MpdClient for Client<'_> {
         self.send(&format!("deleteid \"{id}\"")).and_then(ProtoClient::read_ok)
     }
 
+    fn delete_ids(&mut self, ids: &[u32]) -> MpdResult<()> {
+        self.send_ok_list(&ids.iter().map(|id| format!("deleteid \"{id}\"")).collect_vec())
+    }
+
+    fn delete_from_queue(&mut self, songs: &SingleOrRange) -> MpdResult<()> {
+        self.send(&format!("delete {}", songs.as_mpd_range()))
+            .and_then(ProtoClient::read_ok)
+    }
+
     fn move_id(&mut self, id: u32, to: QueueMoveTarget) -> MpdResult<()> {
         self.send(&format!("moveid \"{id}\" \"{}\"", to.as_mpd_str()))
             .and_then(ProtoClient::read_ok)
     }
 
+    fn move_ids(&mut self, moves: &[(u32, QueueMoveTarget)]) -> MpdResult<()> {
+        self.send_ok_list(
+            &moves
+                .iter()
+                .map(|(id, to)| format!("moveid \"{id}\" \"{}\"", to.as_mpd_str()))
+                .collect_vec(),
+        )
+    }
+
+    fn move_in_queue(&mut self, songs: &SingleOrRange, to: QueueMoveTarget) -> MpdResult<()> {
+        self.send(&format!("move {} \"{}\"", songs.as_mpd_range(), to.as_mpd_str()))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()> {
+        match range {
+            Some(range) => self
+                .send(&format!("shuffle {}", range.as_mpd_range()))
+                .and_then(ProtoClient::read_ok),
+            None => self.send("shuffle").and_then(ProtoClient::read_ok),
+        }
+    }
+
+    fn swap(&mut self, first: usize, second: usize) -> MpdResult<()> {
+        self.send(&format!("swap \"{first}\" \"{second}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn swap_ids(&mut self, first: u32, second: u32) -> MpdResult<()> {
+        self.send(&format!("swapid \"{first}\" \"{second}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn prio(&mut self, priority: u8, range: &SingleOrRange) -> MpdResult<()> {
+        self.send(&format!("prio {priority} {}", range.as_mpd_range()))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn prio_ids(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.send(&format!("prioid {priority} {}", ids.iter().join(" ")))
+            .and_then(ProtoClient::read_ok)
+    }
+
     fn playlist_info(&mut self) -> MpdResult<Option<Vec<Song>>> {
         self.send("playlistinfo").and_then(ProtoClient::read_opt_response)
     }
 
+    fn playlist_changes(&mut self, version: u32, range: Option<SingleOrRange>) -> MpdResult<Vec<Song>> {
+        match range {
+            // Older versions do not know the range argument so they simply get all changed songs
+            Some(range) if self.version >= Version::new(0, 21, 0) => self
+                .send(&format!("plchanges \"{version}\" {}", range.as_mpd_range()))
+                .and_then(ProtoClient::read_response),
+            _ => self
+                .send(&format!("plchanges \"{version}\""))
+                .and_then(ProtoClient::read_response),
+        }
+    }
+
+    fn playlist_changes_pos_id(&mut self, version: u32) -> MpdResult<PositionChanges> {
+        self.send(&format!("plchangesposid \"{version}\""))
+            .and_then(ProtoClient::read_response)
+    }
+
     /// Search the database for songs matching FILTER
     fn find(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Vec<Song>> {
         self.send(&format!("find \"({})\"", filter.to_query_str()))
@@ -359,6 +609,34 @@ impl MpdClient for Client<'_> {
             .and_then(ProtoClient::read_ok)
     }
 
+    fn find_add_at(&mut self, filter: &[Filter<'_, '_>], position: QueueMoveTarget) -> MpdResult<()> {
+        if self.version < Version::new(0, 23, 0) {
+            return Err(MpdError::UnsupportedMpdVersion(
+                "findadd with position can only be used since MPD 0.23.0",
+            ));
+        }
+        self.send(&format!(
+            "findadd \"({})\" position \"{}\"",
+            filter.to_query_str(),
+            position.as_mpd_str()
+        ))
+        .and_then(ProtoClient::read_ok)
+    }
+
+    fn search_add_at(&mut self, filter: &[Filter<'_, '_>], position: QueueMoveTarget) -> MpdResult<()> {
+        if self.version < Version::new(0, 23, 0) {
+            return Err(MpdError::UnsupportedMpdVersion(
+                "searchadd with position can only be used since MPD 0.23.0",
+            ));
+        }
+        self.send(&format!(
+            "searchadd \"({})\" position \"{}\"",
+            filter.to_query_str(),
+            position.as_mpd_str()
+        ))
+        .and_then(ProtoClient::read_ok)
+    }
+
     fn list_tag(&mut self, tag: Tag, filter: Option<&[Filter<'_, '_>]>) -> MpdResult<MpdList> {
         self.send(&if let Some(filter) = filter {
             format!("list {} \"({})\"", tag.as_str(), filter.to_query_str())
@@ -419,6 +697,9 @@ impl MpdClient for Client<'_> {
     fn load_playlist(&mut self, name: &str) -> MpdResult<()> {
         self.send(&format!("load \"{name}\"")).and_then(ProtoClient::read_ok)
     }
+    fn load_playlists(&mut self, names: &[&str]) -> MpdResult<()> {
+        self.send_ok_list(&names.iter().map(|name| format!("load \"{name}\"")).collect_vec())
+    }
     fn delete_playlist(&mut self, name: &str) -> MpdResult<()> {
         self.send(&format!("rm \"{name}\"")).and_then(ProtoClient::read_ok)
     }
@@ -455,6 +736,20 @@ impl MpdClient for Client<'_> {
             .and_then(ProtoClient::read_ok)
     }
 
+    fn add_multiple_to_playlist(&mut self, playlist_name: &str, uris: &[&str]) -> MpdResult<()> {
+        self.send_ok_list(
+            &uris
+                .iter()
+                .map(|uri| format!(r#"playlistadd "{playlist_name}" "{uri}""#))
+                .collect_vec(),
+        )
+    }
+
+    fn clear_playlist(&mut self, name: &str) -> MpdResult<()> {
+        self.send(&format!("playlistclear \"{name}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
     fn save_queue_as_playlist(&mut self, name: &str, mode: Option<SaveMode>) -> MpdResult<()> {
         if let Some(mode) = mode {
             if self.version < Version::new(0, 24, 0) {
@@ -499,11 +794,13 @@ impl MpdClient for Client<'_> {
                     log::debug!("No album art found, falling back to placeholder image");
                     Ok(None)
                 }
+                Err(MpdError::Cancelled) => Err(MpdError::Cancelled),
                 Err(e) => {
                     status_error!(error:? = e; "Failed to read picture. {}", e.to_status());
                     Ok(None)
                 }
             },
+            Err(MpdError::Cancelled) => Err(MpdError::Cancelled),
             Err(e) => {
                 status_error!(error:? = e; "Failed to read picture. {}", e.to_status());
                 Ok(None)
@@ -528,10 +825,124 @@ impl MpdClient for Client<'_> {
         self.send(&format!("disableoutput {id}")).and_then(ProtoClient::read_ok)
     }
 
+    fn move_output(&mut self, output_name: &str) -> MpdResult<()> {
+        self.send(&format!("moveoutput \"{output_name}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    // Partitions
+    fn switch_to_partition(&mut self, name: &str) -> MpdResult<()> {
+        self.send(&format!("partition \"{name}\""))
+            .and_then(ProtoClient::read_ok)?;
+        self.partition = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn list_partitions(&mut self) -> MpdResult<Partitions> {
+        self.send("listpartitions").and_then(ProtoClient::read_response)
+    }
+
+    fn new_partition(&mut self, name: &str) -> MpdResult<()> {
+        self.send(&format!("newpartition \"{name}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn delete_partition(&mut self, name: &str) -> MpdResult<()> {
+        self.send(&format!("delpartition \"{name}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
     // Decoders
     fn decoders(&mut self) -> MpdResult<Decoders> {
         self.send("decoders").and_then(ProtoClient::read_response)
     }
+
+    // Stickers
+    fn sticker(&mut self, uri: &str, name: &str) -> MpdResult<Option<Sticker>> {
+        match self
+            .send(&format!("sticker get song \"{uri}\" \"{name}\""))
+            .and_then(ProtoClient::read_opt_response)
+        {
+            Err(MpdError::Mpd(MpdFailureResponse {
+                code: ErrorCode::NoExist,
+                ..
+            })) => Ok(None),
+            result => result,
+        }
+    }
+
+    fn set_sticker(&mut self, uri: &str, name: &str, value: &str) -> MpdResult<()> {
+        self.send(&format!("sticker set song \"{uri}\" \"{name}\" \"{value}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn delete_sticker(&mut self, uri: &str, name: &str) -> MpdResult<()> {
+        self.send(&format!("sticker delete song \"{uri}\" \"{name}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn set_stickers(&mut self, uris: &[&str], name: &str, value: &str) -> MpdResult<()> {
+        self.send_ok_list(
+            &uris
+                .iter()
+                .map(|uri| format!("sticker set song \"{uri}\" \"{name}\" \"{value}\""))
+                .collect_vec(),
+        )
+    }
+
+    fn delete_stickers(&mut self, uris: &[&str], name: &str) -> MpdResult<()> {
+        self.send_ok_list(
+            &uris
+                .iter()
+                .map(|uri| format!("sticker delete song \"{uri}\" \"{name}\""))
+                .collect_vec(),
+        )
+    }
+
+    fn delete_all_stickers(&mut self, uri: &str) -> MpdResult<()> {
+        self.send(&format!("sticker delete song \"{uri}\""))
+            .and_then(ProtoClient::read_ok)
+    }
+
+    fn list_stickers(&mut self, uri: &str) -> MpdResult<Stickers> {
+        self.send(&format!("sticker list song \"{uri}\""))
+            .and_then(ProtoClient::read_response)
+    }
+
+    fn find_stickers(&mut self, uri: &str, name: &str) -> MpdResult<StickersWithFile> {
+        self.send(&format!("sticker find song \"{uri}\" \"{name}\""))
+            .and_then(ProtoClient::read_response)
+    }
+
+    // Client to client
+    fn subscribe(&mut self, channel: &str) -> MpdResult<()> {
+        self.send(&format!("subscribe \"{channel}\""))
+            .and_then(ProtoClient::read_ok)?;
+        self.subscriptions.push(channel.to_owned());
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, channel: &str) -> MpdResult<()> {
+        self.send(&format!("unsubscribe \"{channel}\""))
+            .and_then(ProtoClient::read_ok)?;
+        self.subscriptions.retain(|c| c != channel);
+        Ok(())
+    }
+
+    fn channels(&mut self) -> MpdResult<Channels> {
+        self.send("channels").and_then(ProtoClient::read_response)
+    }
+
+    fn read_messages(&mut self) -> MpdResult<Messages> {
+        self.send("readmessages").and_then(ProtoClient::read_response)
+    }
+
+    fn send_message(&mut self, channel: &str, message: &str) -> MpdResult<()> {
+        // Messages are free form text so they can contain characters which have to be escaped
+        let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+        self.send(&format!("sendmessage \"{channel}\" \"{message}\""))
+            .and_then(ProtoClient::read_ok)
+    }
 }
 
 #[derive(Debug)]
@@ -542,7 +953,44 @@ pub enum QueueMoveTarget {
     Absolute(usize),
 }
 
+impl FromStr for QueueMoveTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            v if v.starts_with('-') => Ok(QueueMoveTarget::RelativeSub(v.trim_start_matches('-').parse()?)),
+            v if v.starts_with('+') => Ok(QueueMoveTarget::RelativeAdd(v.trim_start_matches('+').parse()?)),
+            v => Ok(QueueMoveTarget::Absolute(v.parse()?)),
+        }
+    }
+}
+
 impl QueueMoveTarget {
+    /// Position of the song which is `by` songs after this one once the songs before it were
+    /// inserted. Songs inserted before the current song move it down so relative positions
+    /// before it stay the same.
+    fn offset(&self, by: usize) -> Self {
+        match self {
+            QueueMoveTarget::RelativeAdd(v) => QueueMoveTarget::RelativeAdd(v + by),
+            QueueMoveTarget::RelativeSub(v) => QueueMoveTarget::RelativeSub(*v),
+            QueueMoveTarget::Absolute(v) => QueueMoveTarget::Absolute(v + by),
+        }
+    }
+
+    /// The same position counted from the start of the queue, for MPD versions which do not support
+    /// positions relative to the current song. None when they are relative and there is no current song.
+    pub fn into_absolute(self, current: Option<u32>) -> Option<Self> {
+        match self {
+            QueueMoveTarget::RelativeAdd(v) => {
+                current.map(|current| QueueMoveTarget::Absolute(current as usize + 1 + v))
+            }
+            QueueMoveTarget::RelativeSub(v) => {
+                current.map(|current| QueueMoveTarget::Absolute((current as usize).saturating_sub(v)))
+            }
+            QueueMoveTarget::Absolute(_) => Some(self),
+        }
+    }
+
     fn as_mpd_str(&self) -> String {
         match self {
             QueueMoveTarget::RelativeAdd(v) => format!("+{v}"),
@@ -578,6 +1026,22 @@ impl SingleOrRange {
     }
 }
 
+impl FromStr for SingleOrRange {
+    type Err = anyhow::Error;
+
+    /// Parses a single position or a range in the MPD `START:END` format, end is exclusive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((start, end)) => {
+                let (start, end) = (start.parse()?, end.parse()?);
+                anyhow::ensure!(start < end, "Invalid range '{s}', start has to be lower than end");
+                Ok(Self::range(start, end))
+            }
+            None => Ok(Self::single(s.parse()?)),
+        }
+    }
+}
+
 impl std::fmt::Display for Ranges {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut iter = self.0.iter().peekable();
@@ -705,6 +1169,8 @@ pub enum Tag<'custom> {
     Title,
     File,
     Genre,
+    /// Songs inside the given directory, the filter kind is ignored
+    Base,
     Custom(&'custom str),
 }
 
@@ -718,6 +1184,7 @@ impl Tag<'_> {
             Tag::Title => "Title",
             Tag::File => "File",
             Tag::Genre => "Genre",
+            Tag::Base => "base",
             Tag::Custom(v) => v,
         }
     }
@@ -770,6 +1237,10 @@ impl<'tag, 'value> Filter<'tag, 'value> {
     }
 
     fn to_query_str(&self) -> String {
+        if self.tag == Tag::Base {
+            return format!("base '{}'", self.value.escape());
+        }
+
         match self.kind {
             FilterKind::Exact => format!("{} == '{}'", self.tag.as_str(), self.value.escape()),
             FilterKind::StartsWith => format!("{} =~ '^{}'", self.tag.as_str(), self.value.escape()),
@@ -859,6 +1330,13 @@ mod filter_tests {
         assert_eq!(input.to_query_str(), r"(Album =~ 'the greatest.*\\\\s+[A-Za-z]+$')");
     }
 
+    #[test]
+    fn base() {
+        let input: &[Filter<'_, '_>] = &[Filter::new(Tag::Base, "rock [live]")];
+
+        assert_eq!(input.to_query_str(), "(base 'rock [live]')");
+    }
+
     #[test]
     fn multiple_values() {
         let input: &[Filter<'_, '_>] = &[
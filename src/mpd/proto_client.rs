@@ -8,7 +8,9 @@ use log::trace;
 
 use super::{
     errors::{MpdError, MpdFailureResponse},
-    split_line, FromMpd,
+    split_line,
+    transcript::TranscriptEntry,
+    FromMpd,
 };
 type MpdResult<T> = Result<T, MpdError>;
 
@@ -41,6 +43,12 @@ pub trait SocketClient {
     fn write(&mut self, bytes: &[u8]) -> std::io::Result<()>;
     fn read(&mut self) -> &mut impl BufRead;
     fn clear_read_buf(&mut self) -> Result<()>;
+    /// Checked between chunks of binary responses, returning true aborts the transfer
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+    /// Called with every command sent and every piece of response received
+    fn record(&self, _entry: TranscriptEntry<'_>) {}
 }
 
 impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
@@ -56,15 +64,24 @@ impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
             if e.kind() == std::io::ErrorKind::BrokenPipe {
                 self.client.reconnect()?;
                 self.client.write([command, "\n"].concat().as_bytes())?;
+                self.record_command(command);
                 Ok(self)
             } else {
                 Err(e.into())
             }
         } else {
+            self.record_command(command);
             Ok(self)
         }
     }
 
+    /// Command lists are recorded line by line so every entry stays on a single line
+    fn record_command(&self, command: &str) {
+        for line in command.lines() {
+            self.client.record(TranscriptEntry::Command(line));
+        }
+    }
+
     pub(super) fn read_ok(mut self) -> Result<(), MpdError> {
         trace!(command = self.command; "Reading command");
         match self.read_line() {
@@ -82,6 +99,32 @@ impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
         }
     }
 
+    /// Reads response to a command list started with `command_list_ok_begin`.
+    /// MPD responds with `list_OK` for every command in the list followed by a final `OK`.
+    pub(super) fn read_ok_list(mut self, command_count: usize) -> Result<(), MpdError> {
+        trace!(command = self.command, command_count; "Reading command list");
+        for responses_read in 0..=command_count {
+            match self.read_line() {
+                Ok(MpdLine::Ok) => {}
+                Ok(MpdLine::Value(val)) => {
+                    self.client.clear_read_buf()?;
+                    return Err(MpdError::Generic(format!("Expected 'list_OK' or 'OK' but got '{val}'")));
+                }
+                // Sending the list again is only safe when none of its commands were executed yet
+                Err(MpdError::ClientClosed) if responses_read == 0 => {
+                    self.client.reconnect()?;
+                    self.execute(self.command)?;
+                    return self.read_ok_list(command_count);
+                }
+                Err(e) => {
+                    self.client.clear_read_buf()?;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn next<V: FromMpd>(&mut self, v: &mut V, val: String) -> Result<(), MpdError> {
         match v.next(val) {
             Ok(val) => Ok(val),
@@ -158,6 +201,10 @@ impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
             }
         };
         loop {
+            if self.client.is_cancelled() {
+                trace!(len = buf.len(); "Binary response cancelled");
+                return Err(MpdError::Cancelled);
+            }
             self.execute(&format!("{} {}", self.command, buf.len()))?;
             match self._read_bin(&mut buf) {
                 Ok(Some(response)) => {
@@ -207,8 +254,10 @@ impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
 
         let read = self.client.read();
         let mut handle = read.take(result.bytes_read);
+        let start = binary_buf.len();
         let _ = handle.read_to_end(binary_buf)?;
         let _ = read.read_line(&mut String::new()); // MPD prints an empty new line at the end of binary response
+        self.client.record(TranscriptEntry::Binary(&binary_buf[start..]));
         match self.read_line()? {
             MpdLine::Ok => Ok(Some(result)),
             MpdLine::Value(val) => Err(MpdError::Generic(format!("Expected 'OK' but got '{val}'"))),
@@ -228,6 +277,7 @@ impl<'cmd, 'client, C: SocketClient> ProtoClient<'cmd, 'client, C> {
         if bytes_read == 0 {
             return Err(MpdError::ClientClosed);
         }
+        self.client.record(TranscriptEntry::Line(line.trim_end_matches('\n')));
 
         if line.starts_with("OK") || line.starts_with("list_OK") {
             return Ok(MpdLine::Ok);
@@ -527,6 +577,62 @@ mod tests {
         }
     }
 
+    mod ok_list {
+        use crate::mpd::{
+            errors::{ErrorCode, MpdFailureResponse},
+            proto_client::ProtoClient,
+        };
+
+        use super::*;
+
+        #[test]
+        fn parses_correct_response() {
+            let buf: &[u8] = b"list_OK\nlist_OK\nOK\n";
+
+            let result = ProtoClient::new("", &mut TestClient::new(buf)).unwrap().read_ok_list(2);
+
+            assert_eq!(result, Ok(()));
+        }
+
+        #[test]
+        fn returns_mpd_error_from_failed_command() {
+            let buf: &[u8] = b"list_OK\nACK [50@1] {add} No such directory\n";
+            let err = MpdFailureResponse {
+                code: ErrorCode::NoExist,
+                command_list_index: 1,
+                command: "add".to_string(),
+                message: "No such directory".to_string(),
+            };
+
+            let result = ProtoClient::new("", &mut TestClient::new(buf)).unwrap().read_ok_list(2);
+
+            assert_eq!(result, Err(MpdError::Mpd(err)));
+        }
+
+        #[test]
+        fn returns_error_when_receiving_value() {
+            let buf: &[u8] = b"list_OK\nidc\nOK\n";
+
+            let result = ProtoClient::new("", &mut TestClient::new(buf)).unwrap().read_ok_list(2);
+
+            assert_eq!(
+                result,
+                Err(MpdError::Generic(String::from(
+                    "Expected 'list_OK' or 'OK' but got 'idc'"
+                )))
+            );
+        }
+
+        #[test]
+        fn does_not_retry_partially_executed_list() {
+            let buf: &[u8] = b"list_OK\n";
+
+            let result = ProtoClient::new("", &mut TestClient::new(buf)).unwrap().read_ok_list(2);
+
+            assert_eq!(result, Err(MpdError::ClientClosed));
+        }
+    }
+
     mod binary {
         use crate::mpd::{
             errors::{ErrorCode, MpdError, MpdFailureResponse},
@@ -0,0 +1,299 @@
+//! Recording of the raw protocol traffic between rmpc and MPD and replaying it back in tests.
+//!
+//! Every entry is written on its own line in the form `<client name>\t<kind>\t<data>` where kind is
+//! `>` for a command sent to MPD, `<` for a response line and `#` for base64 encoded binary data.
+//! The argument of the `password` command is never written to the transcript.
+#[cfg(test)]
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Cursor},
+};
+use std::{
+    fs::File,
+    io::{LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use base64::Engine;
+
+#[cfg(test)]
+use super::{errors::MpdError, proto_client::SocketClient};
+
+#[cfg(test)]
+type MpdResult<T> = Result<T, MpdError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptEntry<'a> {
+    Command(&'a str),
+    Line(&'a str),
+    Binary(&'a [u8]),
+}
+
+/// Shared handle to a transcript file, all clients can record into the same one
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    writer: Arc<Mutex<LineWriter<File>>>,
+}
+
+impl Transcript {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create transcript file '{}'", path.to_string_lossy()))?;
+        Ok(Self {
+            writer: Arc::new(Mutex::new(LineWriter::new(file))),
+        })
+    }
+
+    pub fn record(&self, client_name: &str, entry: TranscriptEntry<'_>) {
+        let line = format_entry(client_name, entry);
+        let mut writer = self.writer.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Err(err) = writer.write_all(line.as_bytes()) {
+            log::error!(err:?; "Failed to write to protocol transcript");
+        }
+    }
+}
+
+fn format_entry(client_name: &str, entry: TranscriptEntry<'_>) -> String {
+    match entry {
+        TranscriptEntry::Command(command) if command.starts_with("password ") => {
+            format!("{client_name}\t>\tpassword <redacted>\n")
+        }
+        TranscriptEntry::Command(command) => format!("{client_name}\t>\t{command}\n"),
+        TranscriptEntry::Line(line) => format!("{client_name}\t<\t{line}\n"),
+        TranscriptEntry::Binary(bytes) => format!(
+            "{client_name}\t#\t{}\n",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ),
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Eq)]
+enum ReplayEntry {
+    Command(String),
+    /// Raw bytes exactly as MPD sent them
+    Response(Vec<u8>),
+}
+
+/// [`SocketClient`] which serves responses from a recorded transcript instead of talking to MPD.
+/// Commands have to be sent in the same order as they were recorded, any difference fails the
+/// write with [`std::io::ErrorKind::InvalidData`].
+#[cfg(test)]
+#[derive(Debug)]
+pub struct ReplayClient {
+    entries: VecDeque<ReplayEntry>,
+    read: Cursor<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl ReplayClient {
+    /// Parses a transcript. When `client_name` is given only entries of that client are replayed,
+    /// otherwise the transcript is expected to contain a single client.
+    pub fn from_transcript(transcript: &str, client_name: Option<&str>) -> Result<Self> {
+        let mut entries = VecDeque::new();
+        for (idx, line) in transcript.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+            let mut parts = line.splitn(3, '\t');
+            let (Some(name), Some(kind), Some(data)) = (parts.next(), parts.next(), parts.next()) else {
+                anyhow::bail!("Invalid transcript entry on line {}: '{line}'", idx + 1);
+            };
+            if client_name.is_some_and(|client_name| client_name != name) {
+                continue;
+            }
+
+            entries.push_back(match kind {
+                ">" => ReplayEntry::Command(data.to_owned()),
+                "<" => ReplayEntry::Response([data.as_bytes(), b"\n"].concat()),
+                "#" => {
+                    let mut bytes = base64::engine::general_purpose::STANDARD
+                        .decode(data)
+                        .with_context(|| format!("Invalid binary data on line {}", idx + 1))?;
+                    // MPD terminates binary data with a new line
+                    bytes.push(b'\n');
+                    ReplayEntry::Response(bytes)
+                }
+                kind => anyhow::bail!("Unknown transcript entry kind '{kind}' on line {}", idx + 1),
+            });
+        }
+
+        Ok(Self {
+            entries,
+            read: Cursor::new(Vec::new()),
+        })
+    }
+
+    pub fn from_file(path: &Path, client_name: Option<&str>) -> Result<Self> {
+        let transcript = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transcript file '{}'", path.to_string_lossy()))?;
+        Self::from_transcript(&transcript, client_name)
+    }
+
+    /// Whether all recorded commands were sent and all responses read
+    pub fn is_finished(&mut self) -> bool {
+        self.entries.is_empty() && self.read.fill_buf().is_ok_and(<[u8]>::is_empty)
+    }
+
+    fn queue_responses(&mut self) {
+        let position = usize::try_from(self.read.position()).unwrap_or_default();
+        let mut buf = std::mem::take(self.read.get_mut()).split_off(position);
+        while let Some(ReplayEntry::Response(bytes)) = self.entries.front() {
+            buf.extend_from_slice(bytes);
+            self.entries.pop_front();
+        }
+        self.read = Cursor::new(buf);
+    }
+}
+
+#[cfg(test)]
+impl SocketClient for ReplayClient {
+    fn reconnect(&mut self) -> MpdResult<&impl SocketClient> {
+        Ok(self)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let written = String::from_utf8_lossy(bytes);
+        for command in written.lines() {
+            match self.entries.pop_front() {
+                Some(ReplayEntry::Command(expected)) if expected == command => {}
+                Some(ReplayEntry::Command(expected)) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Transcript expected command '{expected}' but got '{command}'"),
+                    ));
+                }
+                Some(ReplayEntry::Response(_)) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Transcript expected responses to be read before command '{command}'"),
+                    ));
+                }
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Transcript has no more commands but got '{command}'"),
+                    ));
+                }
+            }
+        }
+        self.queue_responses();
+        Ok(())
+    }
+
+    fn read(&mut self) -> &mut impl BufRead {
+        &mut self.read
+    }
+
+    fn clear_read_buf(&mut self) -> Result<()> {
+        self.read = Cursor::new(Vec::new());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        config::address::MpdPassword,
+        mpd::{
+            client::Client,
+            commands::{Status, Volume},
+            errors::MpdError,
+            mpd_client::MpdClient,
+            proto_client::ProtoClient,
+        },
+        tests::fixtures::{
+            mpd_server::{FakeMpdServer, FakeSong},
+            temp_dir::TempDir,
+        },
+    };
+
+    use super::{ReplayClient, Transcript};
+
+    const TRANSCRIPT: &str = "command\t>\tstatus\n\
+                              command\t<\tvolume: 55\n\
+                              command\t<\tOK\n\
+                              idle\t>\tidle\n\
+                              idle\t<\tchanged: player\n\
+                              idle\t<\tOK\n\
+                              command\t>\tping\n\
+                              command\t<\tOK\n";
+
+    #[test]
+    fn replays_responses_of_given_client() {
+        let mut client = ReplayClient::from_transcript(TRANSCRIPT, Some("command")).unwrap();
+
+        let status: Status = ProtoClient::new("status", &mut client)
+            .unwrap()
+            .read_response()
+            .unwrap();
+        ProtoClient::new("ping", &mut client).unwrap().read_ok().unwrap();
+
+        assert_eq!(status.volume, Volume::new(55));
+        assert!(client.is_finished());
+    }
+
+    #[test]
+    fn fails_when_commands_differ() {
+        let mut client = ReplayClient::from_transcript(TRANSCRIPT, Some("command")).unwrap();
+
+        let result = ProtoClient::new("ping", &mut client);
+
+        assert_eq!(
+            result.unwrap_err(),
+            MpdError::Generic("Transcript expected command 'status' but got 'ping'".to_owned())
+        );
+    }
+
+    #[test]
+    fn password_is_not_recorded() {
+        let server = FakeMpdServer::start();
+        server.state().password = Some("secret".to_owned());
+        let dir = TempDir::new("transcript");
+        let path = dir.join("transcript.txt");
+        let mut client = Client::init(server.address(), Some(MpdPassword("secret")), "test", true).unwrap();
+        client.set_transcript(Some(Transcript::create(&path).unwrap()));
+
+        client.reconnect().unwrap();
+
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        assert!(transcript.contains("test\t>\tpassword <redacted>\n"), "{transcript}");
+        assert!(!transcript.contains("secret"), "{transcript}");
+    }
+
+    #[test]
+    fn recorded_session_can_be_replayed() {
+        let server = FakeMpdServer::start()
+            .with_library([FakeSong::new("song.flac").with_picture((0..=255).cycle().take(10_000).collect())]);
+        let dir = TempDir::new("transcript");
+        let path = dir.join("transcript.txt");
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        client.set_transcript(Some(Transcript::create(&path).unwrap()));
+        client.binary_limit(4096).unwrap();
+        client.add("song.flac").unwrap();
+        let expected_status = client.get_status().unwrap();
+        let expected_picture = client.find_album_art("song.flac").unwrap();
+
+        let mut replay = ReplayClient::from_file(&path, Some("test")).unwrap();
+        ProtoClient::new("binarylimit 4096", &mut replay)
+            .unwrap()
+            .read_ok()
+            .unwrap();
+        ProtoClient::new("add \"song.flac\"", &mut replay)
+            .unwrap()
+            .read_ok()
+            .unwrap();
+        let status: Status = ProtoClient::new("status", &mut replay)
+            .unwrap()
+            .read_response()
+            .unwrap();
+        let picture = ProtoClient::new("albumart \"song.flac\"", &mut replay)
+            .unwrap()
+            .read_bin()
+            .unwrap();
+
+        assert_eq!(status.playlistlength, expected_status.playlistlength);
+        assert_eq!(picture, expected_picture);
+        assert!(replay.is_finished());
+    }
+}
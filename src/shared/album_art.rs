@@ -0,0 +1,123 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc::Sender,
+    Arc,
+};
+
+use anyhow::Result;
+use image::DynamicImage;
+
+use crate::{
+    config::{address::MpdPassword, MpdAddress, Size},
+    mpd::client::CancelToken,
+    shared::image::{decode_image, get_gif_frames, jpg_encode},
+};
+
+#[derive(Debug)]
+pub struct AlbumArtRequest {
+    pub generation: u64,
+    pub file: String,
+    /// Server the file belongs to, the album art client follows the main one when it changes
+    pub address: MpdAddress<'static>,
+    pub password: Option<MpdPassword<'static>>,
+}
+
+/// Handle used to request album art from the album art worker which fetches it on its own
+/// connection. Only the latest request is served, any newer request or [`Self::cancel`]
+/// aborts the one which is in progress.
+#[derive(Debug, Clone)]
+pub struct AlbumArtLoader {
+    sender: Sender<AlbumArtRequest>,
+    generation: Arc<AtomicU64>,
+}
+
+impl AlbumArtLoader {
+    pub fn new(sender: Sender<AlbumArtRequest>) -> Self {
+        Self {
+            sender,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn request(
+        &self,
+        file: String,
+        address: MpdAddress<'static>,
+        password: Option<MpdPassword<'static>>,
+    ) -> Result<()> {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sender.send(AlbumArtRequest {
+            generation,
+            file,
+            address,
+            password,
+        })?;
+        Ok(())
+    }
+
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether the request with the given generation was not superseded by a newer one
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::Relaxed) == generation
+    }
+
+    pub fn cancel_token(&self, generation: u64) -> CancelToken {
+        CancelToken::new(Arc::clone(&self.generation), generation)
+    }
+}
+
+/// Shrinks fetched album art to the largest size it can be displayed at. Runs in the album art
+/// worker so the image protocols never decode the full sized image which can take a while for
+/// large covers. Animated gifs and images which are small enough are returned as they are.
+pub fn shrink_to_max_size(data: Vec<u8>, max_size: Size) -> Result<Vec<u8>> {
+    if get_gif_frames(&data)?.is_some() {
+        return Ok(data);
+    }
+
+    let image = decode_image(&data)?;
+    let (max_width, max_height) = (u32::from(max_size.width), u32::from(max_size.height));
+    if image.width() <= max_width && image.height() <= max_height {
+        return Ok(data);
+    }
+
+    let image = image.resize(max_width, max_height, image::imageops::FilterType::Lanczos3);
+    // Jpeg has no alpha channel
+    jpg_encode(&DynamicImage::ImageRgb8(image.to_rgb8()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat};
+
+    use crate::{config::Size, shared::image::decode_image};
+
+    use super::shrink_to_max_size;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgb8(width, height)
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn large_images_are_shrunk_to_max_size() {
+        let max_size = Size {
+            width: 100,
+            height: 100,
+        };
+
+        let shrunk = decode_image(&shrink_to_max_size(png(400, 200), max_size).unwrap()).unwrap();
+        let small = png(50, 80);
+
+        assert_eq!((shrunk.width(), shrunk.height()), (100, 50));
+        assert_eq!(shrink_to_max_size(small.clone(), max_size).unwrap(), small);
+    }
+}
@@ -22,6 +22,7 @@ pub mod error {
                 MpdError::Mpd(e) => format!("MPD Error: {e}"),
                 MpdError::ValueExpected(e) => format!("Expected Value but got '{e}'"),
                 MpdError::UnsupportedMpdVersion(e) => format!("Unsuported MPD version: {e}"),
+                MpdError::Cancelled => "Request cancelled".to_string(),
             }
         }
     }
@@ -127,16 +128,56 @@ pub mod iter {
 }
 
 pub mod mpd_client {
+    use std::collections::{HashMap, HashSet};
+
+    use itertools::Itertools;
+
     use crate::{
         context::AppContext,
         mpd::{
+            capabilities::{Capabilities, Feature},
+            commands::{
+                stickers::{MAX_RATING, RATING_STICKER},
+                AddedIds, Song, Status,
+            },
             errors::{ErrorCode, MpdError, MpdFailureResponse},
-            mpd_client::MpdClient,
+            mpd_client::{MpdClient, QueueMoveTarget, SingleOrRange},
         },
     };
 
+    /// Highest priority MPD allows, songs with it are played first in random mode
+    pub const HIGHEST_PRIORITY: u8 = 255;
+
+    /// Up to this many songs have their rating queried one by one instead of searching the whole
+    /// sticker database
+    const SINGLE_RATING_LOOKUP_LIMIT: usize = 50;
+
     pub trait MpdClientExt {
         fn play_last(&mut self, context: &AppContext) -> Result<(), MpdError>;
+        /// Fills in the rating sticker of the given songs. Ratings of a few songs, eg. songs which
+        /// changed in the queue, are queried one by one. For more songs the whole sticker database
+        /// is searched in a single request instead of querying every song separately.
+        fn fetch_song_ratings<'a>(&mut self, songs: impl IntoIterator<Item = &'a mut Song>) -> Result<(), MpdError>;
+        /// Sets rating of the song, rating of zero removes it
+        fn rate_song(&mut self, file: &str, rating: u8) -> Result<(), MpdError>;
+        /// Sets rating of all songs at once, rating of zero removes it
+        fn rate_songs(&mut self, files: &[&str], rating: u8) -> Result<(), MpdError>;
+        /// Inserts songs in order right after the current song. They are appended to the end of
+        /// the queue when there is no current song.
+        fn insert_after_current(
+            &mut self,
+            files: &[&str],
+            status: &Status,
+            capabilities: &Capabilities,
+        ) -> Result<AddedIds, MpdError>;
+        /// Appends songs to the queue with the highest priority so they are played next even in
+        /// random mode
+        fn add_with_priority(&mut self, files: &[&str]) -> Result<AddedIds, MpdError>;
+        /// Appends songs to the queue and shuffles them without touching the rest of the queue
+        fn add_shuffled(&mut self, files: &[&str]) -> Result<AddedIds, MpdError>;
+        /// Creates a new playlist with the songs of an existing one. Fails when a playlist
+        /// named `new_name` already exists.
+        fn copy_playlist(&mut self, name: &str, new_name: &str) -> Result<(), MpdError>;
     }
 
     impl<T: MpdClient> MpdClientExt for T {
@@ -156,5 +197,200 @@ pub mod mpd_client {
             };
             Ok(())
         }
+
+        fn fetch_song_ratings<'a>(&mut self, songs: impl IntoIterator<Item = &'a mut Song>) -> Result<(), MpdError> {
+            let songs = songs.into_iter().collect_vec();
+            let mut ratings: HashMap<String, String> = if songs.len() <= SINGLE_RATING_LOOKUP_LIMIT {
+                let mut ratings = HashMap::new();
+                for song in &songs {
+                    if let Some(sticker) = self.sticker(&song.file, RATING_STICKER)? {
+                        ratings.insert(song.file.clone(), sticker.value);
+                    }
+                }
+                ratings
+            } else {
+                self.find_stickers("", RATING_STICKER)?
+                    .into_iter()
+                    .map(|sticker| (sticker.file, sticker.value))
+                    .collect()
+            };
+
+            for song in songs {
+                let stickers = song.stickers.get_or_insert_with(HashMap::new);
+                match ratings.remove(&song.file) {
+                    Some(rating) => stickers.insert(RATING_STICKER.to_owned(), rating),
+                    None => stickers.remove(RATING_STICKER),
+                };
+            }
+            Ok(())
+        }
+
+        fn rate_song(&mut self, file: &str, rating: u8) -> Result<(), MpdError> {
+            if rating == 0 {
+                self.delete_sticker(file, RATING_STICKER)
+            } else {
+                self.set_sticker(file, RATING_STICKER, &rating.min(MAX_RATING).to_string())
+            }
+        }
+
+        fn rate_songs(&mut self, files: &[&str], rating: u8) -> Result<(), MpdError> {
+            if rating > 0 {
+                return self.set_stickers(files, RATING_STICKER, &rating.min(MAX_RATING).to_string());
+            }
+
+            // Deleting a rating the song does not have would stop the whole command list
+            let rated: HashSet<String> = self
+                .find_stickers("", RATING_STICKER)?
+                .into_iter()
+                .map(|sticker| sticker.file)
+                .collect();
+            let files = files.iter().copied().filter(|file| rated.contains(*file)).collect_vec();
+            self.delete_stickers(&files, RATING_STICKER)
+        }
+
+        fn insert_after_current(
+            &mut self,
+            files: &[&str],
+            status: &Status,
+            capabilities: &Capabilities,
+        ) -> Result<AddedIds, MpdError> {
+            let position = QueueMoveTarget::RelativeAdd(0);
+            let position = if capabilities.supports(Feature::RelativePositions) {
+                status.song.map(|_| position)
+            } else {
+                position.into_absolute(status.song)
+            };
+            self.add_ids(files, position)
+        }
+
+        fn add_with_priority(&mut self, files: &[&str]) -> Result<AddedIds, MpdError> {
+            let ids = self.add_ids(files, None)?;
+            self.prio_ids(HIGHEST_PRIORITY, &ids.0)?;
+            Ok(ids)
+        }
+
+        fn add_shuffled(&mut self, files: &[&str]) -> Result<AddedIds, MpdError> {
+            let start = self.get_status()?.playlistlength as usize;
+            let ids = self.add_ids(files, None)?;
+            if ids.0.len() > 1 {
+                self.shuffle(Some(SingleOrRange::range(start, start + ids.0.len())))?;
+            }
+            Ok(ids)
+        }
+
+        fn copy_playlist(&mut self, name: &str, new_name: &str) -> Result<(), MpdError> {
+            if self.list_playlists()?.iter().any(|playlist| playlist.name == new_name) {
+                return Err(MpdError::Generic(format!("Playlist '{new_name}' already exists")));
+            }
+            let files = self.list_playlist(name)?;
+            // Makes sure the copy exists even when the original playlist is empty
+            self.clear_playlist(new_name)?;
+            self.add_multiple_to_playlist(new_name, &files.0.iter().map(String::as_str).collect::<Vec<_>>())
+        }
+    }
+
+    #[cfg(test)]
+    #[allow(clippy::unwrap_used)]
+    mod tests {
+        use crate::{
+            mpd::{capabilities::Capabilities, client::Client, mpd_client::MpdClient},
+            tests::fixtures::mpd_server::{FakeMpdServer, FakeSong},
+        };
+
+        use super::MpdClientExt;
+
+        #[test]
+        fn add_shuffled_only_shuffles_added_songs() {
+            let server =
+                FakeMpdServer::start().with_library(["01.flac", "02.flac", "03.flac", "04.flac"].map(FakeSong::new));
+            let mut client = Client::init(server.address(), None, "test", true).unwrap();
+            client.add("01.flac").unwrap();
+
+            client.add_shuffled(&["02.flac", "03.flac", "04.flac"]).unwrap();
+
+            let state = server.state();
+            assert!(state.commands.iter().any(|command| command == r#"shuffle "1:4""#));
+            let mut queue = state.queue.iter().map(|song| song.file.as_str()).collect::<Vec<_>>();
+            assert_eq!(queue[0], "01.flac");
+            queue[1..].sort_unstable();
+            assert_eq!(queue, ["01.flac", "02.flac", "03.flac", "04.flac"]);
+        }
+
+        #[test]
+        fn rate_songs_removes_only_existing_ratings() {
+            let server = FakeMpdServer::start().with_library(["01.flac", "02.flac", "03.flac"].map(FakeSong::new));
+            let mut client = Client::init(server.address(), None, "test", true).unwrap();
+
+            client.rate_songs(&["01.flac", "02.flac"], 4).unwrap();
+            assert_eq!(server.state().stickers["02.flac"]["rating"], "4");
+
+            client.rate_songs(&["01.flac", "02.flac", "03.flac"], 0).unwrap();
+            let state = server.state();
+            assert!(state.stickers.values().all(|stickers| stickers.is_empty()));
+            assert!(!state
+                .commands
+                .iter()
+                .any(|command| command.starts_with(r#"sticker delete song "03.flac""#)));
+        }
+
+        #[test]
+        fn insert_after_current_uses_absolute_position_on_old_mpd() {
+            let server = FakeMpdServer::start()
+                .with_library(["01.flac", "02.flac", "03.flac", "04.flac", "05.flac"].map(FakeSong::new));
+            let mut client = Client::init(server.address(), None, "test", true).unwrap();
+            client.add("01.flac").unwrap();
+            client.add("02.flac").unwrap();
+            client.play_pos(0).unwrap();
+            let status = client.get_status().unwrap();
+            let capabilities = Capabilities::fetch(&mut client).unwrap();
+
+            client
+                .insert_after_current(&["03.flac"], &status, &Capabilities::default())
+                .unwrap();
+            client
+                .insert_after_current(&["04.flac"], &status, &capabilities)
+                .unwrap();
+
+            let state = server.state();
+            assert!(state.commands.iter().any(|command| command == r#"addid "03.flac" "1""#));
+            assert!(state
+                .commands
+                .iter()
+                .any(|command| command == r#"addid "04.flac" "+0""#));
+            assert_eq!(
+                state.queue.iter().map(|song| song.file.as_str()).collect::<Vec<_>>(),
+                ["01.flac", "04.flac", "03.flac", "02.flac"]
+            );
+        }
+
+        #[test]
+        fn copy_playlist_copies_songs() {
+            let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac"), FakeSong::new("02.flac")]);
+            let mut client = Client::init(server.address(), None, "test", true).unwrap();
+            client
+                .add_multiple_to_playlist("list", &["02.flac", "01.flac"])
+                .unwrap();
+            client.clear_playlist("empty").unwrap();
+
+            client.copy_playlist("list", "list (copy)").unwrap();
+            client.copy_playlist("empty", "empty (copy)").unwrap();
+
+            let state = server.state();
+            assert_eq!(state.playlists["list (copy)"], ["02.flac", "01.flac"]);
+            assert_eq!(state.playlists["list"], ["02.flac", "01.flac"]);
+            assert!(state.playlists["empty (copy)"].is_empty());
+        }
+
+        #[test]
+        fn copy_playlist_does_not_overwrite_existing_playlist() {
+            let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac"), FakeSong::new("02.flac")]);
+            let mut client = Client::init(server.address(), None, "test", true).unwrap();
+            client.add_to_playlist("list", "01.flac", None).unwrap();
+            client.add_to_playlist("other", "02.flac", None).unwrap();
+
+            assert!(client.copy_playlist("list", "other").is_err());
+
+            assert_eq!(server.state().playlists["other"], ["02.flac"]);
+        }
     }
 }
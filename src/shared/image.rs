@@ -203,17 +203,20 @@ pub fn get_image_area_size_px(area_width_col: u16, area_height_col: u16, max_siz
     Ok((w, h))
 }
 
-pub fn resize_image(image_data: &[u8], width_px: u16, hegiht_px: u16) -> Result<DynamicImage> {
-    Ok(image::ImageReader::new(Cursor::new(image_data))
+pub fn decode_image(image_data: &[u8]) -> Result<DynamicImage> {
+    image::ImageReader::new(Cursor::new(image_data))
         .with_guessed_format()
         .context("Unable to guess image format")?
         .decode()
-        .context("Unable to decode image")?
-        .resize(
-            u32::from(width_px),
-            u32::from(hegiht_px),
-            image::imageops::FilterType::Lanczos3,
-        ))
+        .context("Unable to decode image")
+}
+
+pub fn resize_image(image_data: &[u8], width_px: u16, hegiht_px: u16) -> Result<DynamicImage> {
+    Ok(decode_image(image_data)?.resize(
+        u32::from(width_px),
+        u32::from(hegiht_px),
+        image::imageops::FilterType::Lanczos3,
+    ))
 }
 
 pub fn jpg_encode(img: &DynamicImage) -> Result<Vec<u8>> {
@@ -0,0 +1,293 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{
+        cli::RemoteCommand,
+        keys::{GlobalActionFile, Key},
+    },
+    ui::Level,
+    AppEvent,
+};
+
+/// Directory with the control sockets of the current user. Other users must not be able to
+/// connect to them because a control message can run an external command.
+fn socket_dir() -> Result<PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("rmpc"),
+        None => std::env::temp_dir().join(format!("rmpc-{}", rustix::process::geteuid().as_raw())),
+    };
+    prepare_private_dir(&dir)?;
+
+    Ok(dir)
+}
+
+/// Creates the directory accessible only by the current user if it does not exist and makes sure
+/// an existing one is owned by the current user and not accessible by anybody else
+fn prepare_private_dir(dir: &Path) -> Result<()> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+        Err(err) => {
+            return Err(anyhow::Error::from(err)
+                .context(format!("Failed to create control socket directory '{}'", dir.display())))
+        }
+    }
+
+    let metadata = std::fs::symlink_metadata(dir)
+        .with_context(|| format!("Failed to read control socket directory '{}'", dir.display()))?;
+    if !metadata.is_dir() {
+        bail!("Control socket directory '{}' is not a directory", dir.display());
+    }
+    if metadata.uid() != rustix::process::geteuid().as_raw() {
+        bail!("Control socket directory '{}' is owned by another user", dir.display());
+    }
+    if metadata.mode() & 0o077 != 0 {
+        bail!(
+            "Control socket directory '{}' is accessible by other users, its permissions must be 0700",
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn socket_path(pid: u32) -> Result<PathBuf> {
+    Ok(socket_dir()?.join(format!("rmpc-{pid}.sock")))
+}
+
+/// Sent to a running instance through its control socket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ControlMessage {
+    Action(GlobalActionFile),
+    Key(Key),
+    Status { message: String, level: Level },
+    JumpToCurrent,
+}
+
+impl TryFrom<RemoteCommand> for ControlMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RemoteCommand) -> Result<Self, Self::Error> {
+        Ok(match value {
+            RemoteCommand::Action { action } => {
+                ControlMessage::Action(ron::from_str(&action).with_context(|| format!("Invalid action '{action}'"))?)
+            }
+            RemoteCommand::Key { key } => {
+                ControlMessage::Key(key.parse().map_err(|err| anyhow!("Invalid key '{key}': {err}"))?)
+            }
+            RemoteCommand::SwitchTab { name } => ControlMessage::Action(GlobalActionFile::SwitchToTab(name)),
+            RemoteCommand::Status { message, level } => ControlMessage::Status {
+                message,
+                level: level.into(),
+            },
+            RemoteCommand::Modal { modal } => ControlMessage::Action(modal.into()),
+            RemoteCommand::JumpToCurrent => ControlMessage::JumpToCurrent,
+        })
+    }
+}
+
+/// Unix socket through which `rmpc remote` controls a running instance. Every line received is a
+/// JSON encoded [`ControlMessage`] which is passed on to the main task. The socket file is removed
+/// when this is dropped.
+#[derive(Debug)]
+pub struct ControlSocket {
+    path: PathBuf,
+}
+
+impl ControlSocket {
+    pub fn bind(sender: Sender<AppEvent>) -> Result<Self> {
+        Self::bind_at(socket_path(std::process::id())?, sender)
+    }
+
+    fn bind_at(path: PathBuf, sender: Sender<AppEvent>) -> Result<Self> {
+        // Left behind by a previous instance with the same pid which did not exit cleanly
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to create control socket at '{}'", path.display()))?;
+
+        std::thread::Builder::new()
+            .name("control socket".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let result = stream
+                        .map_err(anyhow::Error::from)
+                        .and_then(|s| handle_connection(s, &sender));
+                    if let Err(err) = result {
+                        log::error!(err:?; "Failed to handle control socket connection");
+                    }
+                }
+            })?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            log::error!(err:?, path:? = self.path; "Failed to remove control socket");
+        }
+    }
+}
+
+fn handle_connection(stream: UnixStream, sender: &Sender<AppEvent>) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        match serde_json::from_str::<ControlMessage>(&line?) {
+            Ok(message) => {
+                log::debug!(content:? = message; "Received control message");
+                sender
+                    .send(AppEvent::Remote(message))
+                    .map_err(|_| anyhow!("Main task is not running"))?;
+                writeln!(writer, "ok")?;
+            }
+            Err(err) => writeln!(writer, "Invalid control message: {err}")?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the command to the instance with the given pid or to all running instances if none is
+/// given
+pub fn send(pid: Option<u32>, message: &ControlMessage) -> Result<()> {
+    if let Some(pid) = pid {
+        return send_to(&socket_path(pid)?, message)
+            .with_context(|| format!("Failed to send command to rmpc instance with pid {pid}"));
+    }
+
+    let mut sent = 0;
+    for path in running_instances(&socket_dir()?) {
+        let io_error_kind = |err: &anyhow::Error| err.downcast_ref::<std::io::Error>().map(std::io::Error::kind);
+        match send_to(&path, message) {
+            Ok(()) => sent += 1,
+            Err(err) if io_error_kind(&err) == Some(ErrorKind::ConnectionRefused) => {
+                // Nobody listens anymore, the instance crashed or was killed
+                log::debug!(path:?; "Removing stale control socket");
+                if let Err(err) = std::fs::remove_file(&path) {
+                    log::warn!(err:?, path:?; "Failed to remove stale control socket");
+                }
+            }
+            Err(err) if io_error_kind(&err) == Some(ErrorKind::PermissionDenied) => {
+                log::warn!(path:?; "Skipping control socket which cannot be accessed");
+            }
+            Err(err) => return Err(err.context(format!("Failed to send command to '{}'", path.display()))),
+        }
+    }
+
+    if sent == 0 {
+        bail!("No running rmpc instance found");
+    }
+
+    Ok(())
+}
+
+fn running_instances(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "sock")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("rmpc-"))
+        })
+        .collect()
+}
+
+fn send_to(path: &Path, message: &ControlMessage) -> Result<()> {
+    let mut stream = UnixStream::connect(path)?;
+    writeln!(stream, "{}", serde_json::to_string(message)?)?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    match response.trim_end() {
+        "ok" => Ok(()),
+        "" => bail!("Instance closed the connection without a response"),
+        err => bail!("{err}"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        config::{
+            cli::{ModalKind, RemoteCommand},
+            keys::GlobalActionFile,
+        },
+        tests::fixtures::temp_dir::TempDir,
+        AppEvent,
+    };
+
+    use super::{prepare_private_dir, send_to, ControlMessage, ControlSocket};
+
+    #[test]
+    fn messages_are_passed_to_main_task() {
+        let dir = TempDir::new("ipc");
+        let path = dir.join("rmpc.sock");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let socket = ControlSocket::bind_at(path.clone(), tx).unwrap();
+        let message: ControlMessage = RemoteCommand::Action {
+            action: r#"SwitchToTab("Library")"#.to_owned(),
+        }
+        .try_into()
+        .unwrap();
+
+        send_to(&path, &message).unwrap();
+
+        assert!(matches!(rx.recv().unwrap(), AppEvent::Remote(received) if received == message));
+        drop(socket);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn socket_dir_must_not_be_accessible_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new("ipc");
+        let dir = temp_dir.join("sockets");
+
+        prepare_private_dir(&dir).unwrap();
+        let created_mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let result = prepare_private_dir(&dir);
+
+        assert_eq!(created_mode & 0o777, 0o700);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn remote_commands_are_converted_to_actions() {
+        let modal: ControlMessage = RemoteCommand::Modal {
+            modal: ModalKind::PlaybackOptions,
+        }
+        .try_into()
+        .unwrap();
+        let invalid = ControlMessage::try_from(RemoteCommand::Action {
+            action: "NotAnAction".to_owned(),
+        });
+
+        assert_eq!(modal, ControlMessage::Action(GlobalActionFile::ShowPlaybackOptions));
+        assert!(invalid.is_err());
+    }
+}
@@ -1,8 +1,12 @@
 use crossterm::event::{KeyCode, KeyEvent as CKeyEvent};
 
 use crate::{
-    config::keys::{CommonAction, GlobalAction, QueueActions},
+    config::keys::{
+        AlbumsActions, ArtistsActions, CommonAction, DirectoriesActions, GlobalAction, PlaylistsActions, QueueActions,
+        SearchActions,
+    },
     context::AppContext,
+    shared::macros::status_warn,
 };
 
 #[cfg(debug_assertions)]
@@ -52,7 +56,13 @@ impl KeyEvent {
             None
         } else if let Some(action) = context.config.keybinds.global.get(&self.inner.into()) {
             self.already_handled = true;
-            Some(*action)
+            match action.required_feature() {
+                Some(feature) if !context.capabilities.supports(feature) => {
+                    status_warn!("{feature} is not supported by your MPD server");
+                    None
+                }
+                _ => Some(*action),
+            }
         } else {
             None
         }
@@ -80,4 +90,59 @@ impl KeyEvent {
             None
         }
     }
+
+    pub fn as_albums_action(&mut self, context: &AppContext) -> Option<AlbumsActions> {
+        if self.already_handled {
+            None
+        } else if let Some(action) = context.config.keybinds.albums.get(&self.inner.into()) {
+            self.already_handled = true;
+            Some(*action)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_artists_action(&mut self, context: &AppContext) -> Option<ArtistsActions> {
+        if self.already_handled {
+            None
+        } else if let Some(action) = context.config.keybinds.artists.get(&self.inner.into()) {
+            self.already_handled = true;
+            Some(*action)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_directories_action(&mut self, context: &AppContext) -> Option<DirectoriesActions> {
+        if self.already_handled {
+            None
+        } else if let Some(action) = context.config.keybinds.directories.get(&self.inner.into()) {
+            self.already_handled = true;
+            Some(*action)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_playlists_action(&mut self, context: &AppContext) -> Option<PlaylistsActions> {
+        if self.already_handled {
+            None
+        } else if let Some(action) = context.config.keybinds.playlists.get(&self.inner.into()) {
+            self.already_handled = true;
+            Some(*action)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_search_action(&mut self, context: &AppContext) -> Option<SearchActions> {
+        if self.already_handled {
+            None
+        } else if let Some(action) = context.config.keybinds.search.get(&self.inner.into()) {
+            self.already_handled = true;
+            Some(*action)
+        } else {
+            None
+        }
+    }
 }
@@ -1,9 +1,11 @@
+pub mod album_art;
 pub mod dependencies;
 pub mod env;
 pub mod ext;
 pub mod geometry;
 pub mod id;
 pub mod image;
+pub mod ipc;
 pub mod key_event;
 pub mod logging;
 pub mod macros;
@@ -1,15 +1,18 @@
-use std::{cell::Cell, collections::HashSet, sync::mpsc::channel};
+use std::{cell::Cell, sync::mpsc::channel};
 
 use ratatui::{backend::TestBackend, Terminal};
 use rstest::fixture;
 
 use crate::{
     config::{Config, ConfigFile, Leak},
-    context::AppContext,
-    mpd::commands::Status,
+    context::{AppContext, ConnectionState},
+    mpd::{capabilities::Capabilities, commands::Status},
+    shared::album_art::AlbumArtLoader,
 };
 
 pub mod mpd_client;
+pub mod mpd_server;
+pub mod temp_dir;
 
 #[fixture]
 pub fn status() -> Status {
@@ -20,20 +23,26 @@ pub fn status() -> Status {
 pub fn app_context() -> AppContext {
     let chan1 = channel();
     let chan2 = channel();
+    let chan3 = channel();
     chan1.1.leak();
     chan2.1.leak();
+    chan3.1.leak();
     let config = ConfigFile::default()
-        .into_config(None, None, None, true)
+        .into_config(None, None, None, None, true)
         .expect("Test default config to convert correctly")
         .leak();
     AppContext {
         status: Status::default(),
+        album_art_method: config.album_art.method,
         config,
         queue: Vec::default(),
+        queue_version: None,
         app_event_sender: chan1.0,
         work_sender: chan2.0,
-        supported_commands: HashSet::new(),
+        album_art: AlbumArtLoader::new(chan3.0),
+        capabilities: Capabilities::default(),
         needs_render: Cell::new(false),
+        connection: ConnectionState::Connected,
     }
 }
 
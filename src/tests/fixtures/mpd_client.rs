@@ -10,8 +10,9 @@ use rstest::fixture;
 
 use crate::mpd::{
     commands::{
-        list::MpdList, list_playlist::FileList, status::OnOffOneshot, volume::Bound, IdleEvent, ListFiles, LsInfo,
-        Playlist, Song, Status, Update, Volume,
+        list::MpdList, list_playlist::FileList, status::OnOffOneshot, stickers::StickerWithFile, volume::Bound,
+        AddedIds, IdleEvent, ListFiles, LsInfo, Playlist, PositionChanges, ReplayGainMode, ReplayGainStatus, Song,
+        Status, Sticker, Stickers, StickersWithFile, Update, Volume,
     },
     errors::MpdError,
     mpd_client::{Filter, MpdClient, QueueMoveTarget, SaveMode, SingleOrRange, Tag, ValueChange},
@@ -40,6 +41,8 @@ pub fn client() -> TestMpdClient {
                     ("title".to_owned(), format!("{}_{}_file_{i}", *artist, *album)),
                 ]),
                 duration: Some(Duration::from_secs(i.into())),
+                stickers: None,
+                priority: None,
             })
         })
         .collect();
@@ -71,6 +74,7 @@ pub fn client() -> TestMpdClient {
         volume: Volume::new(100),
         status: Status::default(),
         calls: HashMap::default(),
+        stickers: HashMap::default(),
         rx: BufReader::new(Box::new(Cursor::new(String::new()))),
     }
 }
@@ -88,6 +92,7 @@ pub struct TestMpdClient {
     pub volume: Volume,
     pub status: Status,
     pub calls: HashMap<String, u32>,
+    pub stickers: HashMap<String, HashMap<String, String>>,
     pub rx: BufReader<Box<dyn BufRead>>,
 }
 
@@ -108,7 +113,27 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
-    fn idle(&mut self, _subsystem: Option<IdleEvent>) -> MpdResult<Vec<IdleEvent>> {
+    fn tag_types(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_clear(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_enable(&mut self, _tags: &[&str]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_all(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn url_handlers(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn idle(&mut self, _subsystems: &[IdleEvent]) -> MpdResult<Vec<IdleEvent>> {
         todo!("Not yet implemented")
     }
 
@@ -231,10 +256,42 @@ impl MpdClient for TestMpdClient {
         Ok(())
     }
 
+    fn crossfade(&mut self, seconds: u32) -> MpdResult<()> {
+        self.status.xfade = Some(seconds);
+        Ok(())
+    }
+
+    fn mixrampdb(&mut self, _db: f32) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn mixrampdelay(&mut self, _seconds: Option<f32>) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn replay_gain_mode(&mut self, _mode: ReplayGainMode) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus> {
+        todo!("Not yet implemented")
+    }
+
     fn add(&mut self, _path: &str) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
 
+    /// Only exact file paths are supported, directories are not
+    fn add_multiple(&mut self, paths: &[&str]) -> MpdResult<()> {
+        for path in paths {
+            let Some(idx) = self.songs.iter().position(|song| song.file == *path) else {
+                return Err(MpdError::Generic(format!("No such song: '{path}'")));
+            };
+            self.queue.push(idx);
+        }
+        Ok(())
+    }
+
     fn clear(&mut self) -> MpdResult<()> {
         self.songs.clear();
         self.current_song_idx = None;
@@ -246,12 +303,24 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn delete_ids(&mut self, _ids: &[u32]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn playlist_info(&mut self) -> MpdResult<Option<Vec<Song>>> {
         Ok(Some(
             self.queue.iter().map(|idx| self.songs[*idx].clone()).collect_vec(),
         ))
     }
 
+    fn playlist_changes(&mut self, _version: u32, _range: Option<SingleOrRange>) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
+    fn playlist_changes_pos_id(&mut self, _version: u32) -> MpdResult<PositionChanges> {
+        todo!("Not yet implemented")
+    }
+
     /// `FilterKind` not implemented, everything is treated as Contains
     fn find(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Vec<Song>> {
         Ok(self
@@ -277,6 +346,7 @@ impl MpdClient for TestMpdClient {
                         Tag::Title => values[3].is_some_and(|a| a.contains(filter.value)),
                         Tag::File => values[4].is_some_and(|a| a.contains(filter.value)),
                         Tag::Genre => values[5].is_some_and(|a| a.contains(filter.value)),
+                        Tag::Base => values[4].is_some_and(|a| a.starts_with(&format!("{}/", filter.value))),
                         Tag::Custom(_) => false,
                     };
                     if !value {
@@ -323,6 +393,7 @@ impl MpdClient for TestMpdClient {
                             values[3].is_some_and(|a| a.to_lowercase().contains(&filter.value.to_lowercase()))
                         }
                         Tag::File => values[4].is_some_and(|a| a.to_lowercase().contains(&filter.value.to_lowercase())),
+                        Tag::Base => values[4].is_some_and(|a| a.starts_with(&format!("{}/", filter.value))),
                         Tag::Genre => {
                             values[5].is_some_and(|a| a.to_lowercase().contains(&filter.value.to_lowercase()))
                         }
@@ -343,6 +414,46 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn move_ids(&mut self, _moves: &[(u32, QueueMoveTarget)]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn delete_from_queue(&mut self, _songs: &SingleOrRange) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn move_in_queue(&mut self, _songs: &SingleOrRange, _to: QueueMoveTarget) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn shuffle(&mut self, _range: Option<SingleOrRange>) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn swap(&mut self, _first: usize, _second: usize) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn swap_ids(&mut self, _first: u32, _second: u32) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn add_id(&mut self, _path: &str, _position: Option<QueueMoveTarget>) -> MpdResult<u32> {
+        todo!("Not yet implemented")
+    }
+
+    fn add_ids(&mut self, _paths: &[&str], _position: Option<QueueMoveTarget>) -> MpdResult<AddedIds> {
+        todo!("Not yet implemented")
+    }
+
+    fn prio(&mut self, _priority: u8, _range: &SingleOrRange) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn prio_ids(&mut self, _priority: u8, _ids: &[u32]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn find_one(&mut self, filter: &[Filter<'_, '_>]) -> MpdResult<Option<Song>> {
         let mut res = self.find(filter)?;
         if res.len() > 1 {
@@ -356,6 +467,14 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn find_add_at(&mut self, _filter: &[Filter<'_, '_>], _position: QueueMoveTarget) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn search_add_at(&mut self, _filter: &[Filter<'_, '_>], _position: QueueMoveTarget) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn list_tag(&mut self, _tag: Tag, _filter: Option<&[Filter<'_, '_>]>) -> MpdResult<MpdList> {
         todo!("Not yet implemented")
     }
@@ -413,6 +532,8 @@ impl MpdClient for TestMpdClient {
                         id: *idx as u32,
                         duration: None,
                         metadata: HashMap::default(),
+                        stickers: None,
+                        priority: None,
                     })
                     .collect())
             },
@@ -423,6 +544,16 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn load_playlists(&mut self, names: &[&str]) -> MpdResult<()> {
+        for name in names {
+            let Some(playlist) = self.playlists.iter().find(|p| p.name == *name) else {
+                return Err(MpdError::Generic("Playlist not found".to_string()));
+            };
+            self.queue.extend(playlist.songs_indices.iter().copied());
+        }
+        Ok(())
+    }
+
     fn rename_playlist(&mut self, _name: &str, _new_name: &str) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -448,6 +579,14 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn add_multiple_to_playlist(&mut self, _playlist_name: &str, _uris: &[&str]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn clear_playlist(&mut self, _name: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn save_queue_as_playlist(&mut self, _name: &str, _mode: Option<SaveMode>) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -512,9 +651,114 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn move_output(&mut self, _output_name: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn switch_to_partition(&mut self, _name: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn list_partitions(&mut self) -> MpdResult<crate::mpd::commands::Partitions> {
+        todo!("Not yet implemented")
+    }
+
+    fn new_partition(&mut self, _name: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn delete_partition(&mut self, _name: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn decoders(&mut self) -> MpdResult<crate::mpd::commands::decoders::Decoders> {
         todo!("Not yet implemented")
     }
+
+    fn sticker(&mut self, uri: &str, name: &str) -> MpdResult<Option<Sticker>> {
+        Ok(self
+            .stickers
+            .get(uri)
+            .and_then(|stickers| stickers.get(name))
+            .map(|value| Sticker {
+                key: name.to_owned(),
+                value: value.clone(),
+            }))
+    }
+
+    fn set_sticker(&mut self, uri: &str, name: &str, value: &str) -> MpdResult<()> {
+        self.stickers
+            .entry(uri.to_owned())
+            .or_default()
+            .insert(name.to_owned(), value.to_owned());
+        Ok(())
+    }
+
+    fn delete_sticker(&mut self, uri: &str, name: &str) -> MpdResult<()> {
+        if let Some(stickers) = self.stickers.get_mut(uri) {
+            stickers.remove(name);
+        }
+        Ok(())
+    }
+
+    fn set_stickers(&mut self, uris: &[&str], name: &str, value: &str) -> MpdResult<()> {
+        for uri in uris {
+            self.set_sticker(uri, name, value)?;
+        }
+        Ok(())
+    }
+
+    fn delete_stickers(&mut self, uris: &[&str], name: &str) -> MpdResult<()> {
+        for uri in uris {
+            self.delete_sticker(uri, name)?;
+        }
+        Ok(())
+    }
+
+    fn delete_all_stickers(&mut self, uri: &str) -> MpdResult<()> {
+        self.stickers.remove(uri);
+        Ok(())
+    }
+
+    fn list_stickers(&mut self, uri: &str) -> MpdResult<Stickers> {
+        Ok(Stickers(self.stickers.get(uri).cloned().unwrap_or_default()))
+    }
+
+    fn find_stickers(&mut self, uri: &str, name: &str) -> MpdResult<StickersWithFile> {
+        Ok(StickersWithFile(
+            self.stickers
+                .iter()
+                .filter(|(file, _)| file.starts_with(uri))
+                .filter_map(|(file, stickers)| {
+                    stickers.get(name).map(|value| StickerWithFile {
+                        file: file.clone(),
+                        key: name.to_owned(),
+                        value: value.clone(),
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    fn subscribe(&mut self, _channel: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn unsubscribe(&mut self, _channel: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn channels(&mut self) -> MpdResult<crate::mpd::commands::Channels> {
+        todo!("Not yet implemented")
+    }
+
+    fn read_messages(&mut self) -> MpdResult<crate::mpd::commands::Messages> {
+        todo!("Not yet implemented")
+    }
+
+    fn send_message(&mut self, _channel: &str, _message: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
 }
 
 impl SocketClient for TestMpdClient {
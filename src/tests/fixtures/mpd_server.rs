@@ -0,0 +1,2081 @@
+//! In-process fake MPD server speaking the real text protocol. It holds an in-memory library,
+//! queue and stored playlists and notifies idling clients about changes, which makes it possible
+//! to exercise [`Client`](crate::mpd::client::Client) end to end including reconnection, idle and
+//! binary transfers.
+//!
+//! The server can also be run standalone so that rmpc itself can be pointed at it:
+//! `RMPC_FAKE_MPD_ADDRESS=127.0.0.1:6601 cargo test run_fake_mpd_server -- --ignored`
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError},
+        Arc, Mutex, MutexGuard, Weak,
+    },
+    time::Duration,
+};
+
+use itertools::Itertools;
+
+use crate::config::MpdAddress;
+
+const PROTOCOL_VERSION: &str = "0.24.0";
+const DEFAULT_BINARY_LIMIT: usize = 8192;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FakeSong {
+    pub file: String,
+    /// Tags in the order they are sent to the client, names are as MPD sends them, eg. `Artist`
+    pub tags: Vec<(String, String)>,
+    pub duration: Option<Duration>,
+    pub picture: Option<Vec<u8>>,
+}
+
+impl FakeSong {
+    pub fn new(file: &str) -> Self {
+        Self {
+            file: file.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_tag(mut self, name: &str, value: &str) -> Self {
+        self.tags.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn with_picture(mut self, picture: Vec<u8>) -> Self {
+        self.picture = Some(picture);
+        self
+    }
+
+    fn tag(&self, name: &str) -> Option<&str> {
+        if name.eq_ignore_ascii_case("file") {
+            return Some(&self.file);
+        }
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        push_line(out, "file", &self.file);
+        for (name, value) in &self.tags {
+            push_line(out, name, value);
+        }
+        if let Some(duration) = self.duration {
+            push_line(out, "Time", &duration.as_secs().to_string());
+            push_line(out, "duration", &format!("{:.3}", duration.as_secs_f64()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedSong {
+    pub id: u32,
+    pub file: String,
+    pub priority: u8,
+    /// Queue version in which the song was added, moved or its position changed
+    version: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlayState {
+    Play,
+    Pause,
+    #[default]
+    Stop,
+}
+
+impl PlayState {
+    fn as_str(self) -> &'static str {
+        match self {
+            PlayState::Play => "play",
+            PlayState::Pause => "pause",
+            PlayState::Stop => "stop",
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ServerState {
+    pub library: Vec<FakeSong>,
+    pub queue: Vec<QueuedSong>,
+    pub playlists: BTreeMap<String, Vec<String>>,
+    pub state: PlayState,
+    /// Position of the current song in the queue
+    pub current: Option<usize>,
+    pub elapsed: Duration,
+    pub volume: u8,
+    pub repeat: bool,
+    pub random: bool,
+    pub single: bool,
+    pub consume: bool,
+    pub crossfade: u32,
+    pub mixramp_db: f32,
+    /// None when mixramp is disabled
+    pub mixramp_delay: Option<f32>,
+    pub replay_gain_mode: String,
+    pub outputs: Vec<(String, bool)>,
+    /// Required password, None allows everything without authentication
+    pub password: Option<String>,
+    /// Song stickers by file and sticker name
+    pub stickers: BTreeMap<String, BTreeMap<String, String>>,
+    /// Every command received by the server, in order
+    pub commands: Vec<String>,
+    /// Protocol version announced to new connections
+    pub version: &'static str,
+    /// Commands left out of the `commands` response, eg. to simulate a server without album art
+    pub disabled_commands: Vec<&'static str>,
+    /// Exact command lines which fail instead of being executed
+    pub failing_commands: Vec<String>,
+    queue_version: u32,
+    next_id: u32,
+    idle_listeners: Vec<Weak<Mutex<BTreeSet<&'static str>>>>,
+    mailboxes: Vec<Weak<Mutex<Mailbox>>>,
+}
+
+impl Default for ServerState {
+    fn default() -> Self {
+        Self {
+            library: Vec::new(),
+            queue: Vec::new(),
+            playlists: BTreeMap::new(),
+            state: PlayState::Stop,
+            current: None,
+            elapsed: Duration::ZERO,
+            volume: 100,
+            repeat: false,
+            random: false,
+            single: false,
+            consume: false,
+            crossfade: 0,
+            mixramp_db: 0.0,
+            mixramp_delay: None,
+            replay_gain_mode: "off".to_owned(),
+            outputs: vec![("Fake output".to_owned(), true)],
+            password: None,
+            stickers: BTreeMap::new(),
+            commands: Vec::new(),
+            version: PROTOCOL_VERSION,
+            disabled_commands: Vec::new(),
+            failing_commands: Vec::new(),
+            queue_version: 1,
+            next_id: 1,
+            idle_listeners: Vec::new(),
+            mailboxes: Vec::new(),
+        }
+    }
+}
+
+impl ServerState {
+    /// Marks the subsystem as changed for every connected client
+    pub fn notify(&mut self, subsystem: &'static str) {
+        self.idle_listeners.retain(|listener| {
+            let Some(listener) = listener.upgrade() else {
+                return false;
+            };
+            lock(&listener).insert(subsystem);
+            true
+        });
+    }
+
+    /// Mailboxes of the connected clients
+    fn mailboxes(&mut self) -> Vec<Arc<Mutex<Mailbox>>> {
+        self.mailboxes.retain(|mailbox| mailbox.strong_count() > 0);
+        self.mailboxes.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    pub fn queue_version(&self) -> u32 {
+        self.queue_version
+    }
+
+    fn find_song(&self, file: &str) -> Option<&FakeSong> {
+        self.library.iter().find(|song| song.file == file)
+    }
+
+    fn bump_queue_version(&mut self) -> u32 {
+        self.queue_version += 1;
+        self.notify("playlist");
+        self.queue_version
+    }
+
+    /// Marks every song from `from` onwards as changed because their positions moved
+    fn touch_queue_from(&mut self, from: usize) {
+        let version = self.queue_version;
+        for song in self.queue.iter_mut().skip(from) {
+            song.version = version;
+        }
+    }
+
+    fn set_priority(&mut self, positions: impl IntoIterator<Item = usize>, priority: u8) -> Result<(), Ack> {
+        let version = self.bump_queue_version();
+        for pos in positions {
+            let song = self.queue.get_mut(pos).ok_or_else(|| Ack::argument("Bad song index"))?;
+            song.priority = priority;
+            song.version = version;
+        }
+        Ok(())
+    }
+
+    fn insert_into_queue(&mut self, file: &str, pos: Option<usize>) -> Result<u32, Ack> {
+        if self.find_song(file).is_none() {
+            return Err(Ack::no_exist("No such song"));
+        }
+        let pos = pos.unwrap_or(self.queue.len());
+        if pos > self.queue.len() {
+            return Err(Ack::argument("Bad song index"));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let version = self.bump_queue_version();
+        self.queue.insert(
+            pos,
+            QueuedSong {
+                id,
+                file: file.to_owned(),
+                priority: 0,
+                version,
+            },
+        );
+        if let Some(current) = self.current.as_mut() {
+            if pos <= *current {
+                *current += 1;
+            }
+        }
+        self.touch_queue_from(pos);
+        Ok(id)
+    }
+
+    fn delete_from_queue(&mut self, pos: usize) {
+        self.queue.remove(pos);
+        self.current = match self.current {
+            Some(current) if current == pos => {
+                self.state = PlayState::Stop;
+                None
+            }
+            Some(current) if current > pos => Some(current - 1),
+            current => current,
+        };
+        self.bump_queue_version();
+        self.touch_queue_from(pos);
+    }
+
+    fn move_in_queue(&mut self, from: usize, to: usize) -> Result<(), Ack> {
+        self.move_range_in_queue(from, from + 1, to)
+    }
+
+    /// Moves songs in `start..end` so that the first of them ends up at `to`
+    fn move_range_in_queue(&mut self, start: usize, end: usize, to: usize) -> Result<(), Ack> {
+        if start >= end || end > self.queue.len() || to + (end - start) > self.queue.len() {
+            return Err(Ack::argument("Bad song index"));
+        }
+        let current_id = self.current.map(|pos| self.queue[pos].id);
+        let songs: Vec<QueuedSong> = self.queue.drain(start..end).collect();
+        self.queue.splice(to..to, songs);
+        self.current = current_id.and_then(|id| self.queue.iter().position(|song| song.id == id));
+        self.bump_queue_version();
+        self.touch_queue_from(start.min(to));
+        Ok(())
+    }
+
+    fn swap_in_queue(&mut self, first: usize, second: usize) -> Result<(), Ack> {
+        if first >= self.queue.len() || second >= self.queue.len() {
+            return Err(Ack::argument("Bad song index"));
+        }
+        self.queue.swap(first, second);
+        self.current = self.current.map(|current| match current {
+            current if current == first => second,
+            current if current == second => first,
+            current => current,
+        });
+        self.bump_queue_version();
+        self.touch_queue_from(first.min(second));
+        Ok(())
+    }
+
+    fn position_of_id(&self, id: &str) -> Result<usize, Ack> {
+        let id: u32 = id.parse().map_err(|_| Ack::argument("Integer expected"))?;
+        self.queue
+            .iter()
+            .position(|song| song.id == id)
+            .ok_or_else(|| Ack::no_exist("No such song"))
+    }
+
+    fn play(&mut self, pos: usize) -> Result<(), Ack> {
+        if pos >= self.queue.len() {
+            return Err(Ack::argument("Bad song index"));
+        }
+        self.current = Some(pos);
+        self.state = PlayState::Play;
+        self.elapsed = Duration::ZERO;
+        self.notify("player");
+        Ok(())
+    }
+
+    fn write_queued_song(&self, pos: usize, out: &mut Vec<u8>) {
+        let queued = &self.queue[pos];
+        match self.find_song(&queued.file) {
+            Some(song) => song.write_to(out),
+            None => push_line(out, "file", &queued.file),
+        }
+        push_line(out, "Pos", &pos.to_string());
+        push_line(out, "Id", &queued.id.to_string());
+        if queued.priority > 0 {
+            push_line(out, "Prio", &queued.priority.to_string());
+        }
+    }
+
+    fn write_status(&self, out: &mut Vec<u8>) {
+        push_line(out, "volume", &self.volume.to_string());
+        push_line(out, "repeat", bool_str(self.repeat));
+        push_line(out, "random", bool_str(self.random));
+        push_line(out, "single", bool_str(self.single));
+        push_line(out, "consume", bool_str(self.consume));
+        if self.crossfade > 0 {
+            push_line(out, "xfade", &self.crossfade.to_string());
+        }
+        push_line(out, "mixrampdb", &format!("{:.6}", self.mixramp_db));
+        if let Some(delay) = self.mixramp_delay {
+            push_line(out, "mixrampdelay", &format!("{delay:.6}"));
+        }
+        push_line(out, "partition", "default");
+        push_line(out, "playlist", &self.queue_version.to_string());
+        push_line(out, "playlistlength", &self.queue.len().to_string());
+        push_line(out, "state", self.state.as_str());
+        if let Some(current) = self.current {
+            push_line(out, "song", &current.to_string());
+            push_line(out, "songid", &self.queue[current].id.to_string());
+            push_line(out, "elapsed", &format!("{:.3}", self.elapsed.as_secs_f64()));
+            if let Some(duration) = self.find_song(&self.queue[current].file).and_then(|s| s.duration) {
+                push_line(out, "duration", &format!("{:.3}", duration.as_secs_f64()));
+            }
+            if let Some(next) = self.queue.get(current + 1) {
+                push_line(out, "nextsong", &(current + 1).to_string());
+                push_line(out, "nextsongid", &next.id.to_string());
+            }
+        }
+    }
+
+    /// Files and directories directly inside `dir`, directories are deduplicated
+    fn list_dir(&self, dir: &str) -> Result<(BTreeSet<String>, Vec<&FakeSong>), Ack> {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        let mut dirs = BTreeSet::new();
+        let mut files = Vec::new();
+        for song in &self.library {
+            let Some(rest) = song.file.strip_prefix(&prefix) else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((subdir, _)) => {
+                    dirs.insert(format!("{prefix}{subdir}"));
+                }
+                None => files.push(song),
+            }
+        }
+        if !dir.is_empty() && dirs.is_empty() && files.is_empty() {
+            return Err(Ack::no_exist("No such directory"));
+        }
+        Ok((dirs, files))
+    }
+}
+
+#[derive(Debug)]
+struct Ack {
+    code: u8,
+    message: String,
+}
+
+impl Ack {
+    fn argument(message: &str) -> Self {
+        Self {
+            code: 2,
+            message: message.to_owned(),
+        }
+    }
+
+    fn password(message: &str) -> Self {
+        Self {
+            code: 3,
+            message: message.to_owned(),
+        }
+    }
+
+    fn permission(message: &str) -> Self {
+        Self {
+            code: 4,
+            message: message.to_owned(),
+        }
+    }
+
+    fn unknown(command: &str) -> Self {
+        Self {
+            code: 5,
+            message: format!("unknown command \"{command}\""),
+        }
+    }
+
+    fn no_exist(message: &str) -> Self {
+        Self {
+            code: 50,
+            message: message.to_owned(),
+        }
+    }
+
+    fn exist(message: &str) -> Self {
+        Self {
+            code: 56,
+            message: message.to_owned(),
+        }
+    }
+}
+
+const SUPPORTED_COMMANDS: &[&str] = &[
+    "add",
+    "addid",
+    "albumart",
+    "binarylimit",
+    "channels",
+    "clear",
+    "close",
+    "commands",
+    "consume",
+    "crossfade",
+    "currentsong",
+    "decoders",
+    "delete",
+    "deleteid",
+    "disableoutput",
+    "enableoutput",
+    "find",
+    "findadd",
+    "idle",
+    "list",
+    "listfiles",
+    "listplaylist",
+    "listplaylistinfo",
+    "listplaylists",
+    "load",
+    "lsinfo",
+    "move",
+    "mixrampdb",
+    "mixrampdelay",
+    "moveid",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "partition",
+    "password",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistadd",
+    "playlistclear",
+    "playlistdelete",
+    "playlistinfo",
+    "plchanges",
+    "plchangesposid",
+    "previous",
+    "prio",
+    "prioid",
+    "random",
+    "readmessages",
+    "readpicture",
+    "rename",
+    "replay_gain_mode",
+    "replay_gain_status",
+    "repeat",
+    "rm",
+    "save",
+    "search",
+    "searchadd",
+    "sendmessage",
+    "setvol",
+    "shuffle",
+    "single",
+    "status",
+    "sticker",
+    "subscribe",
+    "stop",
+    "swap",
+    "swapid",
+    "tagtypes",
+    "toggleoutput",
+    "unsubscribe",
+    "update",
+    "urlhandlers",
+    "volume",
+];
+
+#[derive(Debug)]
+enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener, PathBuf),
+}
+
+/// Fake MPD server running on background threads. It stops accepting connections and drops all
+/// existing ones when dropped.
+#[derive(Debug)]
+pub struct FakeMpdServer {
+    address: &'static str,
+    is_unix: bool,
+    state: Arc<Mutex<ServerState>>,
+    connections: Arc<Mutex<Vec<Connection>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Debug)]
+enum Connection {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn shutdown(&self) {
+        // Errors only mean the connection is already closed
+        let _ = match self {
+            Connection::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            Connection::Unix(stream) => stream.shutdown(Shutdown::Both),
+        };
+    }
+}
+
+impl FakeMpdServer {
+    /// Starts the server on a random local TCP port
+    pub fn start() -> Self {
+        Self::start_tcp("127.0.0.1:0")
+    }
+
+    pub fn start_tcp(address: &str) -> Self {
+        let listener = TcpListener::bind(address).expect("Fake MPD server to bind");
+        let address = listener
+            .local_addr()
+            .expect("Bound listener to have an address")
+            .to_string();
+        Self::start_with(Listener::Tcp(listener), address, false)
+    }
+
+    pub fn start_unix() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "rmpc-fake-mpd-{}-{}.sock",
+            std::process::id(),
+            UNIX_SOCKET_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).expect("Fake MPD server to bind");
+        let address = path.to_string_lossy().into_owned();
+        Self::start_with(Listener::Unix(listener, path), address, true)
+    }
+
+    fn start_with(listener: Listener, address: String, is_unix: bool) -> Self {
+        let server = Self {
+            address: Box::leak(address.into_boxed_str()),
+            is_unix,
+            state: Arc::new(Mutex::new(ServerState::default())),
+            connections: Arc::new(Mutex::new(Vec::new())),
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        let state = Arc::clone(&server.state);
+        let connections = Arc::clone(&server.connections);
+        let stopped = Arc::clone(&server.stopped);
+        std::thread::spawn(move || accept_loop(&listener, &state, &connections, &stopped));
+
+        server
+    }
+
+    pub fn address(&self) -> MpdAddress<'static> {
+        if self.is_unix {
+            MpdAddress::SocketPath(self.address)
+        } else {
+            MpdAddress::IpAndPort(self.address)
+        }
+    }
+
+    pub fn with_library(self, songs: impl IntoIterator<Item = FakeSong>) -> Self {
+        self.state().library.extend(songs);
+        self
+    }
+
+    /// Direct access to the server state. Changes made through it are not announced to idling
+    /// clients unless [`ServerState::notify`] is called.
+    pub fn state(&self) -> MutexGuard<'_, ServerState> {
+        lock(&self.state)
+    }
+
+    /// Closes all open connections as if the server restarted, new connections are still accepted
+    pub fn drop_connections(&self) {
+        for connection in lock(&self.connections).drain(..) {
+            connection.shutdown();
+        }
+    }
+}
+
+impl Drop for FakeMpdServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.drop_connections();
+    }
+}
+
+static UNIX_SOCKET_COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn accept_loop(
+    listener: &Listener,
+    state: &Arc<Mutex<ServerState>>,
+    connections: &Arc<Mutex<Vec<Connection>>>,
+    stopped: &AtomicBool,
+) {
+    let nonblocking = match listener {
+        Listener::Tcp(listener) => listener.set_nonblocking(true),
+        Listener::Unix(listener, _) => listener.set_nonblocking(true),
+    };
+    nonblocking.expect("Listener to be set to nonblocking");
+
+    while !stopped.load(Ordering::Relaxed) {
+        let accepted = match listener {
+            Listener::Tcp(listener) => listener.accept().map(|(stream, _)| {
+                stream.set_nonblocking(false).map(|()| {
+                    let reader = stream.try_clone().map(|s| Box::new(s) as Box<dyn Read + Send>);
+                    let conn = stream.try_clone().map(Connection::Tcp);
+                    (reader, Box::new(stream) as Box<dyn Write + Send>, conn)
+                })
+            }),
+            Listener::Unix(listener, _) => listener.accept().map(|(stream, _)| {
+                stream.set_nonblocking(false).map(|()| {
+                    let reader = stream.try_clone().map(|s| Box::new(s) as Box<dyn Read + Send>);
+                    let conn = stream.try_clone().map(Connection::Unix);
+                    (reader, Box::new(stream) as Box<dyn Write + Send>, conn)
+                })
+            }),
+        };
+
+        match accepted {
+            Ok(Ok((Ok(reader), writer, Ok(connection)))) => {
+                lock(connections).push(connection);
+                let state = Arc::clone(state);
+                std::thread::spawn(move || serve_connection(reader, writer, &state));
+            }
+            Ok(_) => log::warn!("Fake MPD server failed to set up connection"),
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+            Err(err) => log::warn!(err:?; "Fake MPD server failed to accept connection"),
+        }
+    }
+
+    if let Listener::Unix(_, path) = listener {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Channels a client is subscribed to and the messages it did not read yet
+#[derive(Debug)]
+struct Mailbox {
+    channels: BTreeSet<String>,
+    messages: Vec<(String, String)>,
+    pending_events: Arc<Mutex<BTreeSet<&'static str>>>,
+}
+
+struct Session {
+    pending_events: Arc<Mutex<BTreeSet<&'static str>>>,
+    mailbox: Arc<Mutex<Mailbox>>,
+    binary_limit: usize,
+    authenticated: bool,
+    /// Tags enabled by `tagtypes`, None when all tags are enabled
+    tag_types: Option<BTreeSet<&'static str>>,
+}
+
+impl Session {
+    /// Drops lines of disabled tags from a response
+    fn filter_tags(&self, response: Vec<u8>) -> Vec<u8> {
+        let Some(enabled) = &self.tag_types else {
+            return response;
+        };
+        let text = String::from_utf8_lossy(&response);
+        let is_disabled = |line: &str| {
+            line.split_once(": ")
+                .is_some_and(|(key, _)| tag_type(key).is_some_and(|tag| !enabled.contains(tag)))
+        };
+        text.split_inclusive('\n')
+            .filter(|line| !is_disabled(line))
+            .collect::<String>()
+            .into_bytes()
+    }
+}
+
+fn serve_connection(reader: Box<dyn Read + Send>, mut writer: Box<dyn Write + Send>, state: &Arc<Mutex<ServerState>>) {
+    // Lines are read on a separate thread so that `noidle` can be received while idling
+    let (line_sender, lines) = channel::<String>();
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let pending_events = Arc::new(Mutex::new(BTreeSet::new()));
+    let mailbox = Arc::new(Mutex::new(Mailbox {
+        channels: BTreeSet::new(),
+        messages: Vec::new(),
+        pending_events: Arc::clone(&pending_events),
+    }));
+    let (mut session, version) = {
+        let mut state = lock(state);
+        state.idle_listeners.push(Arc::downgrade(&pending_events));
+        state.mailboxes.push(Arc::downgrade(&mailbox));
+        let session = Session {
+            pending_events,
+            mailbox,
+            binary_limit: DEFAULT_BINARY_LIMIT,
+            authenticated: state.password.is_none(),
+            tag_types: None,
+        };
+        (session, state.version)
+    };
+
+    if writer.write_all(format!("OK MPD {version}\n").as_bytes()).is_err() {
+        return;
+    }
+
+    while let Ok(line) = lines.recv() {
+        let response = match line.as_str() {
+            "command_list_begin" | "command_list_ok_begin" => {
+                let with_ok = line == "command_list_ok_begin";
+                let commands = lines.iter().take_while(|line| line != "command_list_end").collect_vec();
+                execute_list(&commands, with_ok, state, &mut session)
+            }
+            "close" => return,
+            line if line == "idle" || line.starts_with("idle ") => {
+                lock(state).commands.push(line.to_owned());
+                match wait_for_events(&args_of(line)[1..], &lines, &session) {
+                    Some(response) => response,
+                    None => return,
+                }
+            }
+            line => match execute(line, state, &mut session) {
+                Ok(mut response) => {
+                    response.extend_from_slice(b"OK\n");
+                    response
+                }
+                Err(ack) => ack_response(&ack, 0, line),
+            },
+        };
+
+        if writer.write_all(&response).is_err() {
+            return;
+        }
+    }
+}
+
+fn execute_list(commands: &[String], with_ok: bool, state: &Mutex<ServerState>, session: &mut Session) -> Vec<u8> {
+    let mut result = Vec::new();
+    for (idx, command) in commands.iter().enumerate() {
+        match execute(command, state, session) {
+            Ok(response) => {
+                result.extend(response);
+                if with_ok {
+                    result.extend_from_slice(b"list_OK\n");
+                }
+            }
+            Err(ack) => {
+                result.extend(ack_response(&ack, idx, command));
+                return result;
+            }
+        }
+    }
+    result.extend_from_slice(b"OK\n");
+    result
+}
+
+fn ack_response(ack: &Ack, idx: usize, command: &str) -> Vec<u8> {
+    let name = command.split_whitespace().next().unwrap_or_default();
+    format!("ACK [{}@{idx}] {{{name}}} {}\n", ack.code, ack.message).into_bytes()
+}
+
+/// Returns None when the client disconnected
+fn wait_for_events(subsystems: &[String], lines: &Receiver<String>, session: &Session) -> Option<Vec<u8>> {
+    let take_events = || {
+        let mut pending = lock(&session.pending_events);
+        let events = pending
+            .iter()
+            .filter(|event| subsystems.is_empty() || subsystems.iter().any(|s| s == *event))
+            .copied()
+            .collect_vec();
+        for event in &events {
+            pending.remove(event);
+        }
+        events
+    };
+    let respond = |events: Vec<&str>| {
+        let mut out = Vec::new();
+        for event in events {
+            push_line(&mut out, "changed", event);
+        }
+        out.extend_from_slice(b"OK\n");
+        out
+    };
+
+    loop {
+        let events = take_events();
+        if !events.is_empty() {
+            return Some(respond(events));
+        }
+        match lines.recv_timeout(POLL_INTERVAL) {
+            Ok(line) if line == "noidle" => return Some(respond(take_events())),
+            Ok(line) => {
+                // Real MPD closes the connection when anything but noidle is sent while idling
+                log::warn!(line = line.as_str(); "Fake MPD server received command while idling");
+                return None;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+fn execute(line: &str, state: &Mutex<ServerState>, session: &mut Session) -> Result<Vec<u8>, Ack> {
+    let mut args = args_of(line);
+    if args.is_empty() {
+        return Err(Ack::unknown(""));
+    }
+    let command = args.remove(0);
+    let arg = |idx: usize| args.get(idx).map(String::as_str);
+    let required = |idx: usize| arg(idx).ok_or_else(|| Ack::argument("too few arguments"));
+    let mut state = lock(state);
+    state.commands.push(line.to_owned());
+    let mut out = Vec::new();
+
+    if state.failing_commands.iter().any(|failing| failing == line) {
+        return Err(Ack::argument("simulated failure"));
+    }
+
+    if !session.authenticated && !matches!(command.as_str(), "password" | "ping" | "close") {
+        return Err(Ack::permission("you don't have permission for this"));
+    }
+
+    match command.as_str() {
+        "password" => {
+            if state.password.as_deref() == Some(required(0)?) || state.password.is_none() {
+                session.authenticated = true;
+            } else {
+                return Err(Ack::password("incorrect password"));
+            }
+        }
+        // Partitions are not simulated, all of them share the same state
+        "partition" => {
+            required(0)?;
+        }
+        "binarylimit" => {
+            session.binary_limit = required(0)?.parse().map_err(|_| Ack::argument("Number expected"))?;
+        }
+        "ping" | "noidle" => {}
+        "commands" => {
+            for command in SUPPORTED_COMMANDS
+                .iter()
+                .filter(|command| !state.disabled_commands.contains(command))
+            {
+                push_line(&mut out, "command", command);
+            }
+        }
+        "notcommands" | "decoders" => {}
+        "tagtypes" => match arg(0) {
+            None => {
+                for tag in TAG_TYPES {
+                    if !matches!(&session.tag_types, Some(enabled) if !enabled.contains(tag)) {
+                        push_line(&mut out, "tagtype", tag);
+                    }
+                }
+            }
+            Some("all") => session.tag_types = None,
+            Some("clear") => session.tag_types = Some(BTreeSet::new()),
+            Some(action @ ("enable" | "disable")) => {
+                let tags = args[1..]
+                    .iter()
+                    .map(|tag| tag_type(tag).ok_or_else(|| Ack::argument(&format!("Unknown tag type: {tag}"))))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let enabled = session
+                    .tag_types
+                    .get_or_insert_with(|| TAG_TYPES.iter().copied().collect());
+                for tag in tags {
+                    if action == "enable" {
+                        enabled.insert(tag);
+                    } else {
+                        enabled.remove(tag);
+                    }
+                }
+            }
+            Some(_) => return Err(Ack::argument("Unknown sub command")),
+        },
+        "urlhandlers" => {
+            for handler in ["http://", "https://"] {
+                push_line(&mut out, "handler", handler);
+            }
+        }
+        // Status
+        "status" => state.write_status(&mut out),
+        "currentsong" => {
+            if let Some(current) = state.current {
+                state.write_queued_song(current, &mut out);
+            }
+        }
+        // Playback
+        "play" => match arg(0) {
+            Some(pos) => state.play(parse_usize(pos)?)?,
+            None if state.queue.is_empty() => {}
+            None => {
+                let pos = state.current.unwrap_or(0);
+                state.play(pos)?;
+            }
+        },
+        "playid" => {
+            let pos = state.position_of_id(required(0)?)?;
+            state.play(pos)?;
+        }
+        "pause" => {
+            if state.state != PlayState::Stop {
+                state.state = match arg(0) {
+                    Some("1") => PlayState::Pause,
+                    Some(_) => PlayState::Play,
+                    None if state.state == PlayState::Play => PlayState::Pause,
+                    None => PlayState::Play,
+                };
+                state.notify("player");
+            }
+        }
+        "stop" => {
+            state.state = PlayState::Stop;
+            state.elapsed = Duration::ZERO;
+            state.notify("player");
+        }
+        "next" | "previous" => {
+            let Some(current) = state.current else {
+                return Err(Ack::argument("Not playing"));
+            };
+            let target = if command == "next" {
+                current + 1
+            } else {
+                current.saturating_sub(1)
+            };
+            if target < state.queue.len() {
+                state.play(target)?;
+            } else {
+                state.current = None;
+                state.state = PlayState::Stop;
+                state.notify("player");
+            }
+        }
+        "setvol" => {
+            state.volume = required(0)?.parse().map_err(|_| Ack::argument("Integer expected"))?;
+            state.notify("mixer");
+        }
+        "volume" => {
+            let change: i16 = required(0)?.parse().map_err(|_| Ack::argument("Integer expected"))?;
+            state.volume = u8::try_from((i16::from(state.volume) + change).clamp(0, 100)).unwrap_or_default();
+            state.notify("mixer");
+        }
+        "repeat" | "random" | "single" | "consume" => {
+            let value = required(0)? != "0";
+            match command.as_str() {
+                "repeat" => state.repeat = value,
+                "random" => state.random = value,
+                "single" => state.single = value,
+                _ => state.consume = value,
+            }
+            state.notify("options");
+        }
+        "crossfade" => {
+            state.crossfade = required(0)?.parse().map_err(|_| Ack::argument("Integer expected"))?;
+            state.notify("options");
+        }
+        "mixrampdb" => {
+            state.mixramp_db = required(0)?.parse().map_err(|_| Ack::argument("Float expected"))?;
+            state.notify("options");
+        }
+        "mixrampdelay" => {
+            let delay: f32 = required(0)?.parse().map_err(|_| Ack::argument("Float expected"))?;
+            state.mixramp_delay = Some(delay).filter(|delay| *delay >= 0.0);
+            state.notify("options");
+        }
+        "replay_gain_mode" => {
+            let mode = required(0)?;
+            if !["off", "track", "album", "auto"].contains(&mode) {
+                return Err(Ack::argument("Unrecognized replay gain mode"));
+            }
+            state.replay_gain_mode = mode.to_owned();
+            state.notify("options");
+        }
+        "replay_gain_status" => push_line(&mut out, "replay_gain_mode", &state.replay_gain_mode),
+        // Queue
+        "add" => {
+            let uri = required(0)?;
+            let files = state
+                .library
+                .iter()
+                .filter(|song| uri.is_empty() || song.file == uri || song.file.starts_with(&format!("{uri}/")))
+                .map(|song| song.file.clone())
+                .collect_vec();
+            if files.is_empty() {
+                return Err(Ack::no_exist("No such directory"));
+            }
+            for file in files {
+                state.insert_into_queue(&file, None)?;
+            }
+        }
+        "addid" => {
+            let pos = arg(1).map(|pos| resolve_position(pos, &state)).transpose()?;
+            let id = state.insert_into_queue(required(0)?, pos)?;
+            push_line(&mut out, "Id", &id.to_string());
+        }
+        "clear" => {
+            state.queue.clear();
+            state.current = None;
+            state.state = PlayState::Stop;
+            state.bump_queue_version();
+        }
+        "delete" => {
+            let (start, end) = parse_range(required(0)?)?;
+            let end = end.unwrap_or(start + 1).min(state.queue.len());
+            if start >= end {
+                return Err(Ack::argument("Bad song index"));
+            }
+            for pos in (start..end).rev() {
+                state.delete_from_queue(pos);
+            }
+        }
+        "deleteid" => {
+            let pos = state.position_of_id(required(0)?)?;
+            state.delete_from_queue(pos);
+        }
+        "move" => {
+            let (start, end) = parse_range(required(0)?)?;
+            let to = resolve_position(required(1)?, &state)?;
+            state.move_range_in_queue(start, end.unwrap_or(start + 1), to)?;
+        }
+        "moveid" => {
+            let from = state.position_of_id(required(0)?)?;
+            let to = resolve_position(required(1)?, &state)?;
+            state.move_in_queue(from, to)?;
+        }
+        "swap" => {
+            let (first, second) = (parse_usize(required(0)?)?, parse_usize(required(1)?)?);
+            state.swap_in_queue(first, second)?;
+        }
+        "shuffle" => {
+            // Reverses the range instead of shuffling it to keep tests deterministic
+            let (start, end) = arg(0).map_or(Ok((0, None)), parse_range)?;
+            let end = end.unwrap_or(state.queue.len()).min(state.queue.len());
+            for offset in 0..end.saturating_sub(start) / 2 {
+                state.swap_in_queue(start + offset, end - 1 - offset)?;
+            }
+        }
+        "swapid" => {
+            let (first, second) = (state.position_of_id(required(0)?)?, state.position_of_id(required(1)?)?);
+            state.swap_in_queue(first, second)?;
+        }
+        "prio" | "prioid" => {
+            let priority: u8 = required(0)?
+                .parse()
+                .map_err(|_| Ack::argument("Priority out of range"))?;
+            required(1)?;
+            let positions: Vec<usize> = if command == "prio" {
+                args[1..]
+                    .iter()
+                    .map(|range| {
+                        let (start, end) = parse_range(range)?;
+                        Ok(start..end.unwrap_or(start + 1))
+                    })
+                    .flatten_ok()
+                    .try_collect()?
+            } else {
+                args[1..].iter().map(|id| state.position_of_id(id)).try_collect()?
+            };
+            state.set_priority(positions, priority)?;
+        }
+        "playlistinfo" => {
+            let (start, end) = arg(0).map_or(Ok((0, None)), parse_range)?;
+            let end = end.unwrap_or(if arg(0).is_some() { start + 1 } else { state.queue.len() });
+            for pos in start..end.min(state.queue.len()) {
+                state.write_queued_song(pos, &mut out);
+            }
+        }
+        "plchanges" | "plchangesposid" => {
+            let version: u32 = required(0)?.parse().map_err(|_| Ack::argument("Integer expected"))?;
+            let (start, end) = arg(1).map_or(Ok((0, None)), parse_range)?;
+            let end = end.unwrap_or(state.queue.len()).min(state.queue.len());
+            for pos in (start..end).filter(|pos| state.queue[*pos].version > version) {
+                if command == "plchanges" {
+                    state.write_queued_song(pos, &mut out);
+                } else {
+                    push_line(&mut out, "cpos", &pos.to_string());
+                    push_line(&mut out, "Id", &state.queue[pos].id.to_string());
+                }
+            }
+        }
+        // Database
+        "lsinfo" | "listfiles" => {
+            let dir = arg(0).unwrap_or_default().trim_end_matches('/');
+            if let Some(song) = state.find_song(dir) {
+                song.write_to(&mut out);
+            } else {
+                let (dirs, files) = state.list_dir(dir)?;
+                for dir in dirs {
+                    push_line(
+                        &mut out,
+                        "directory",
+                        dir.rsplit('/')
+                            .next()
+                            .filter(|_| command == "listfiles")
+                            .unwrap_or(&dir),
+                    );
+                }
+                for song in files {
+                    if command == "listfiles" {
+                        push_line(&mut out, "file", song.file.rsplit('/').next().unwrap_or(&song.file));
+                    } else {
+                        song.write_to(&mut out);
+                    }
+                }
+                if dir.is_empty() && command == "lsinfo" {
+                    for name in state.playlists.keys() {
+                        push_line(&mut out, "playlist", name);
+                    }
+                }
+            }
+        }
+        "find" | "search" | "findadd" | "searchadd" => {
+            let filters = parse_filter(required(0)?)?;
+            let ignore_case = command.starts_with("search");
+            let files = state
+                .library
+                .iter()
+                .filter(|song| filters.iter().all(|filter| filter.matches(song, ignore_case)))
+                .map(|song| song.file.clone())
+                .collect_vec();
+            if command.ends_with("add") {
+                let position = match (arg(1), arg(2)) {
+                    (Some("position"), Some(position)) => Some(resolve_position(position, &state)?),
+                    _ => None,
+                };
+                for (idx, file) in files.into_iter().enumerate() {
+                    state.insert_into_queue(&file, position.map(|position| position + idx))?;
+                }
+            } else {
+                for file in files {
+                    if let Some(song) = state.find_song(&file) {
+                        song.write_to(&mut out);
+                    }
+                }
+            }
+        }
+        "list" => {
+            let tag = required(0)?;
+            let filters = arg(1).map(parse_filter).transpose()?.unwrap_or_default();
+            let values: BTreeSet<&str> = state
+                .library
+                .iter()
+                .filter(|song| filters.iter().all(|filter| filter.matches(song, false)))
+                .filter_map(|song| song.tag(tag))
+                .collect();
+            for value in values {
+                push_line(&mut out, tag, value);
+            }
+        }
+        "update" | "rescan" => {
+            push_line(&mut out, "updating_db", "1");
+            state.notify("update");
+            state.notify("database");
+        }
+        // Stored playlists
+        "listplaylists" => {
+            for name in state.playlists.keys() {
+                push_line(&mut out, "playlist", name);
+            }
+        }
+        "listplaylist" | "listplaylistinfo" => {
+            let files = state
+                .playlists
+                .get(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such playlist"))?;
+            for file in files {
+                match state.find_song(file) {
+                    Some(song) if command == "listplaylistinfo" => song.write_to(&mut out),
+                    _ => push_line(&mut out, "file", file),
+                }
+            }
+        }
+        "load" => {
+            let files = state
+                .playlists
+                .get(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such playlist"))?
+                .clone();
+            for file in files {
+                state.insert_into_queue(&file, None)?;
+            }
+        }
+        "save" => {
+            let name = required(0)?.to_owned();
+            let files = state.queue.iter().map(|song| song.file.clone()).collect_vec();
+            match arg(1) {
+                Some("append") => state.playlists.entry(name).or_default().extend(files),
+                Some("replace") => {
+                    state.playlists.insert(name, files);
+                }
+                _ if state.playlists.contains_key(&name) => return Err(Ack::exist("Playlist already exists")),
+                _ => {
+                    state.playlists.insert(name, files);
+                }
+            }
+            state.notify("stored_playlist");
+        }
+        "rm" => {
+            state
+                .playlists
+                .remove(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such playlist"))?;
+            state.notify("stored_playlist");
+        }
+        "rename" => {
+            if state.playlists.contains_key(required(1)?) {
+                return Err(Ack::exist("Playlist already exists"));
+            }
+            let files = state
+                .playlists
+                .remove(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such playlist"))?;
+            state.playlists.insert(required(1)?.to_owned(), files);
+            state.notify("stored_playlist");
+        }
+        "playlistadd" => {
+            let file = required(1)?.to_owned();
+            if state.find_song(&file).is_none() {
+                return Err(Ack::no_exist("No such song"));
+            }
+            let playlist = state.playlists.entry(required(0)?.to_owned()).or_default();
+            match arg(2) {
+                Some(pos) => playlist.insert(parse_usize(pos)?.min(playlist.len()), file),
+                None => playlist.push(file),
+            }
+            state.notify("stored_playlist");
+        }
+        "playlistclear" => {
+            state.playlists.insert(required(0)?.to_owned(), Vec::new());
+            state.notify("stored_playlist");
+        }
+        "playlistdelete" => {
+            let playlist = state
+                .playlists
+                .get_mut(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such playlist"))?;
+            let (start, end) = parse_range(required(1)?)?;
+            let end = end.unwrap_or(start + 1).min(playlist.len());
+            if start >= end {
+                return Err(Ack::argument("Bad song index"));
+            }
+            playlist.drain(start..end);
+            state.notify("stored_playlist");
+        }
+        // Client to client messages
+        "subscribe" => {
+            if !lock(&session.mailbox).channels.insert(required(0)?.to_owned()) {
+                return Err(Ack::exist("already subscribed to this channel"));
+            }
+            state.notify("subscription");
+        }
+        "unsubscribe" => {
+            if !lock(&session.mailbox).channels.remove(required(0)?) {
+                return Err(Ack::no_exist("not subscribed to this channel"));
+            }
+            state.notify("subscription");
+        }
+        "channels" => {
+            let channels: BTreeSet<String> = state
+                .mailboxes()
+                .iter()
+                .flat_map(|mailbox| lock(mailbox).channels.clone())
+                .collect();
+            for channel in channels {
+                push_line(&mut out, "channel", &channel);
+            }
+        }
+        "sendmessage" => {
+            let (channel, message) = (required(0)?, required(1)?);
+            let receivers = state
+                .mailboxes()
+                .into_iter()
+                .filter(|mailbox| lock(mailbox).channels.contains(channel))
+                .collect_vec();
+            if receivers.is_empty() {
+                return Err(Ack::no_exist("No clients subscribed to this channel"));
+            }
+            for receiver in receivers {
+                let mut receiver = lock(&receiver);
+                receiver.messages.push((channel.to_owned(), message.to_owned()));
+                lock(&receiver.pending_events).insert("message");
+            }
+        }
+        "readmessages" => {
+            for (channel, message) in std::mem::take(&mut lock(&session.mailbox).messages) {
+                push_line(&mut out, "channel", &channel);
+                push_line(&mut out, "message", &message);
+            }
+        }
+        // Stickers
+        "sticker" => {
+            if required(1)? != "song" {
+                return Err(Ack::argument("unknown sticker domain"));
+            }
+            let uri = required(2)?;
+            match required(0)? {
+                "get" => {
+                    let name = required(3)?;
+                    let value = state
+                        .stickers
+                        .get(uri)
+                        .and_then(|stickers| stickers.get(name))
+                        .ok_or_else(|| Ack::no_exist("no such sticker"))?;
+                    push_line(&mut out, "sticker", &format!("{name}={value}"));
+                }
+                "set" => {
+                    let (name, value) = (required(3)?.to_owned(), required(4)?.to_owned());
+                    state.stickers.entry(uri.to_owned()).or_default().insert(name, value);
+                    state.notify("sticker");
+                }
+                "delete" => {
+                    let stickers = state
+                        .stickers
+                        .get_mut(uri)
+                        .ok_or_else(|| Ack::no_exist("no such sticker"))?;
+                    match arg(3) {
+                        Some(name) => {
+                            stickers.remove(name).ok_or_else(|| Ack::no_exist("no such sticker"))?;
+                        }
+                        None => stickers.clear(),
+                    }
+                    state.notify("sticker");
+                }
+                "list" => {
+                    for (name, value) in state.stickers.get(uri).into_iter().flatten() {
+                        push_line(&mut out, "sticker", &format!("{name}={value}"));
+                    }
+                }
+                "find" => {
+                    let name = required(3)?;
+                    for (file, stickers) in &state.stickers {
+                        if let Some(value) = stickers
+                            .get(name)
+                            .filter(|_| uri.is_empty() || file.starts_with(&format!("{uri}/")))
+                        {
+                            push_line(&mut out, "file", file);
+                            push_line(&mut out, "sticker", &format!("{name}={value}"));
+                        }
+                    }
+                }
+                _ => return Err(Ack::argument("bad request")),
+            }
+        }
+        // Album art
+        "albumart" | "readpicture" => {
+            let picture = state
+                .find_song(required(0)?)
+                .ok_or_else(|| Ack::no_exist("No such file"))?
+                .picture
+                .clone();
+            let offset = parse_usize(required(1)?)?;
+            match picture {
+                Some(picture) => {
+                    let end = picture.len().min(offset + session.binary_limit);
+                    let chunk = picture.get(offset..end).ok_or_else(|| Ack::argument("Bad offset"))?;
+                    push_line(&mut out, "size", &picture.len().to_string());
+                    push_line(&mut out, "binary", &chunk.len().to_string());
+                    out.extend_from_slice(chunk);
+                    out.push(b'\n');
+                }
+                // readpicture responds with empty OK, albumart with an error when nothing is found
+                None if command == "readpicture" => {}
+                None => return Err(Ack::no_exist("No file exists")),
+            }
+        }
+        // Outputs
+        "outputs" => {
+            for (id, (name, enabled)) in state.outputs.iter().enumerate() {
+                push_line(&mut out, "outputid", &id.to_string());
+                push_line(&mut out, "outputname", name);
+                push_line(&mut out, "outputenabled", bool_str(*enabled));
+            }
+        }
+        "enableoutput" | "disableoutput" | "toggleoutput" => {
+            let id = parse_usize(required(0)?)?;
+            let output = state
+                .outputs
+                .get_mut(id)
+                .ok_or_else(|| Ack::no_exist("No such audio output"))?;
+            output.1 = match command.as_str() {
+                "enableoutput" => true,
+                "disableoutput" => false,
+                _ => !output.1,
+            };
+            state.notify("output");
+        }
+        _ => return Err(Ack::unknown(&command)),
+    }
+
+    // Tag values returned by list are not affected by tagtypes
+    if command == "list" {
+        Ok(out)
+    } else {
+        Ok(session.filter_tags(out))
+    }
+}
+
+const TAG_TYPES: &[&str] = &[
+    "Artist",
+    "ArtistSort",
+    "Album",
+    "AlbumSort",
+    "AlbumArtist",
+    "AlbumArtistSort",
+    "Title",
+    "TitleSort",
+    "Track",
+    "Name",
+    "Genre",
+    "Date",
+    "OriginalDate",
+    "Composer",
+    "Performer",
+    "Comment",
+    "Disc",
+    "Label",
+];
+
+/// Canonical name of the tag, None when it is not a tag
+fn tag_type(name: &str) -> Option<&'static str> {
+    TAG_TYPES.iter().find(|tag| tag.eq_ignore_ascii_case(name)).copied()
+}
+
+fn push_line(out: &mut Vec<u8>, key: &str, value: &str) {
+    out.extend_from_slice(format!("{key}: {value}\n").as_bytes());
+}
+
+fn bool_str(value: bool) -> &'static str {
+    if value {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+fn parse_usize(value: &str) -> Result<usize, Ack> {
+    value.parse().map_err(|_| Ack::argument("Integer expected"))
+}
+
+/// Parses `START:END`, `START:` or a single position
+fn parse_range(value: &str) -> Result<(usize, Option<usize>), Ack> {
+    match value.split_once(':') {
+        Some((start, "")) => Ok((parse_usize(start)?, None)),
+        Some((start, end)) => Ok((parse_usize(start)?, Some(parse_usize(end)?))),
+        None => Ok((parse_usize(value)?, None)),
+    }
+}
+
+/// Resolves absolute and relative (`+N`, `-N`, relative to the current song) queue positions
+fn resolve_position(value: &str, state: &ServerState) -> Result<usize, Ack> {
+    let current = || state.current.ok_or_else(|| Ack::argument("No current song"));
+    if let Some(offset) = value.strip_prefix('+') {
+        Ok(current()? + parse_usize(offset)? + 1)
+    } else if let Some(offset) = value.strip_prefix('-') {
+        current()?
+            .checked_sub(parse_usize(offset)?)
+            .ok_or_else(|| Ack::argument("Bad song index"))
+    } else {
+        parse_usize(value)
+    }
+}
+
+/// Splits a command line into arguments, handling double quoted arguments with backslash escapes
+fn args_of(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => arg.extend(chars.next()),
+                    '"' => break,
+                    c => arg.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+    args
+}
+
+#[derive(Debug)]
+struct TagFilter {
+    tag: String,
+    operator: String,
+    value: String,
+}
+
+impl TagFilter {
+    fn matches(&self, song: &FakeSong, ignore_case: bool) -> bool {
+        if self.operator == "base" {
+            return self.value.is_empty() || song.file.starts_with(&format!("{}/", self.value));
+        }
+
+        let candidates = if self.tag.eq_ignore_ascii_case("any") {
+            std::iter::once(song.file.as_str())
+                .chain(song.tags.iter().map(|(_, value)| value.as_str()))
+                .collect_vec()
+        } else {
+            song.tag(&self.tag).into_iter().collect_vec()
+        };
+        let normalize = |value: &str| {
+            if ignore_case {
+                value.to_lowercase()
+            } else {
+                value.to_owned()
+            }
+        };
+        let expected = normalize(&self.value);
+
+        candidates
+            .into_iter()
+            .map(normalize)
+            .any(|value| match self.operator.as_str() {
+                "==" => value == expected,
+                "!=" => value != expected,
+                "contains" => value.contains(&expected),
+                "starts_with" => value.starts_with(&expected),
+                // Only the regex shapes rmpc generates are supported
+                "=~" => match (
+                    expected.strip_prefix(".*").and_then(|v| v.strip_suffix(".*")),
+                    expected.strip_prefix('^'),
+                ) {
+                    (Some(inner), _) => value.contains(inner),
+                    (None, Some(prefix)) => value.starts_with(prefix),
+                    (None, None) => value == expected,
+                },
+                _ => false,
+            })
+    }
+}
+
+/// Parses filter expressions like `((Artist == 'foo') AND (Album =~ '.*bar.*'))`. Only AND is
+/// supported as that is the only thing rmpc uses.
+fn parse_filter(expression: &str) -> Result<Vec<TagFilter>, Ack> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' => {}
+            c if c.is_whitespace() => {}
+            '\'' | '"' => {
+                let mut value = String::new();
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '\\' => value.extend(chars.next()),
+                        ch if ch == c => break,
+                        ch => value.push(ch),
+                    }
+                }
+                tokens.push(value);
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '(' && *c != ')') {
+                    word.push(c);
+                }
+                if word != "AND" {
+                    tokens.push(word);
+                }
+            }
+        }
+    }
+
+    let mut tokens = tokens.into_iter();
+    let mut filters = Vec::new();
+    while let Some(tag) = tokens.next() {
+        // `(base 'dir')` has no operator
+        let operator = if tag == "base" {
+            tag.clone()
+        } else {
+            tokens
+                .next()
+                .ok_or_else(|| Ack::argument("Invalid filter expression"))?
+        };
+        let value = tokens
+            .next()
+            .ok_or_else(|| Ack::argument("Invalid filter expression"))?;
+        filters.push(TagFilter { tag, operator, value });
+    }
+
+    if filters.is_empty() {
+        Err(Ack::argument("Invalid filter expression"))
+    } else {
+        Ok(filters)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use itertools::Itertools;
+
+    use crate::{
+        context::AppContext,
+        mpd::{
+            client::{CancelToken, Client},
+            commands::{messages::Message, IdleEvent, ReplayGainMode, State, Volume},
+            errors::{ErrorCode, MpdError, MpdFailureResponse},
+            mpd_client::{Filter, MpdClient, QueueMoveTarget, SingleOrRange, Tag},
+        },
+        tests::fixtures::app_context,
+    };
+
+    use super::{FakeMpdServer, FakeSong};
+
+    fn library() -> Vec<FakeSong> {
+        vec![
+            FakeSong::new("artist/album/01.flac")
+                .with_tag("Artist", "artist")
+                .with_tag("Album", "album")
+                .with_tag("Title", "first")
+                .with_duration(Duration::from_secs(185))
+                .with_picture((0..=255).cycle().take(20_000).collect()),
+            FakeSong::new("artist/album/02.flac")
+                .with_tag("Artist", "artist")
+                .with_tag("Album", "album")
+                .with_tag("Title", "second"),
+            FakeSong::new("other/03.flac")
+                .with_tag("Artist", "other")
+                .with_tag("Title", "third"),
+        ]
+    }
+
+    fn connect(server: &FakeMpdServer) -> Client<'static> {
+        Client::init(server.address(), None, "test", true).unwrap()
+    }
+
+    #[test]
+    fn queue_and_status() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+
+        client.add("artist/album").unwrap();
+        client.play_pos(1).unwrap();
+
+        let queue = client.playlist_info().unwrap().unwrap();
+        let status = client.get_status().unwrap();
+        assert_eq!(
+            queue.iter().map(|song| song.file.as_str()).collect_vec(),
+            ["artist/album/01.flac", "artist/album/02.flac"]
+        );
+        assert_eq!(status.state, State::Play);
+        assert_eq!(status.songid, Some(queue[1].id));
+        assert_eq!(status.playlistlength, 2);
+    }
+
+    #[test]
+    fn playback_options() {
+        let server = FakeMpdServer::start();
+        let mut client = connect(&server);
+
+        client.crossfade(5).unwrap();
+        client.mixrampdb(-17.5).unwrap();
+        client.mixrampdelay(Some(2.0)).unwrap();
+        client.replay_gain_mode(ReplayGainMode::Album).unwrap();
+        let status = client.get_status().unwrap();
+        let replay_gain = client.replay_gain_status().unwrap();
+        client.mixrampdelay(None).unwrap();
+
+        assert_eq!(status.xfade, Some(5));
+        assert_eq!(status.mixrampdb.as_deref(), Some("-17.500000"));
+        assert_eq!(status.mixrampdelay.as_deref(), Some("2.000000"));
+        assert_eq!(replay_gain.mode, ReplayGainMode::Album);
+        assert_eq!(client.get_status().unwrap().mixrampdelay, None);
+    }
+
+    #[test]
+    fn songs_are_inserted_after_current_song() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+        client.add("artist/album").unwrap();
+        client.play_pos(0).unwrap();
+
+        let ids = client
+            .add_ids(
+                &["other/03.flac", "artist/album/02.flac"],
+                Some(QueueMoveTarget::RelativeAdd(0)),
+            )
+            .unwrap();
+        client.prio_ids(255, &ids.0).unwrap();
+
+        let queue = client.playlist_info().unwrap().unwrap();
+        assert_eq!(
+            queue
+                .iter()
+                .map(|song| (song.file.as_str(), song.priority))
+                .collect_vec(),
+            [
+                ("artist/album/01.flac", None),
+                ("other/03.flac", Some(255)),
+                ("artist/album/02.flac", Some(255)),
+                ("artist/album/02.flac", None),
+            ]
+        );
+        assert_eq!(ids.0, [queue[1].id, queue[2].id]);
+    }
+
+    #[test]
+    fn songs_are_moved_swapped_and_deleted() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+        client.add_multiple(&["artist/album", "other/03.flac"]).unwrap();
+        let files = |client: &mut Client| {
+            client
+                .playlist_info()
+                .unwrap()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|song| song.file)
+                .collect_vec()
+        };
+        assert_eq!(
+            files(&mut client),
+            ["artist/album/01.flac", "artist/album/02.flac", "other/03.flac"]
+        );
+
+        client
+            .move_in_queue(&SingleOrRange::range(0, 2), QueueMoveTarget::Absolute(1))
+            .unwrap();
+        assert_eq!(
+            files(&mut client),
+            ["other/03.flac", "artist/album/01.flac", "artist/album/02.flac"]
+        );
+
+        client.swap(0, 2).unwrap();
+        assert_eq!(
+            files(&mut client),
+            ["artist/album/02.flac", "artist/album/01.flac", "other/03.flac"]
+        );
+
+        client.delete_from_queue(&SingleOrRange::single(1)).unwrap();
+        assert_eq!(files(&mut client), ["artist/album/02.flac", "other/03.flac"]);
+    }
+
+    #[test]
+    fn works_over_unix_socket() {
+        let server = FakeMpdServer::start_unix().with_library(library());
+        let mut client = connect(&server);
+
+        let songs = client
+            .find(&[Filter::new(Tag::Artist, "other")])
+            .unwrap()
+            .into_iter()
+            .map(|song| song.file)
+            .collect_vec();
+
+        assert_eq!(songs, ["other/03.flac"]);
+    }
+
+    #[test]
+    fn base_filter_does_not_match_sibling_directories() {
+        let server = FakeMpdServer::start().with_library([
+            FakeSong::new("Rock/01.flac"),
+            FakeSong::new("Rock Classics/02.flac"),
+            FakeSong::new("Rock+/03.flac"),
+        ]);
+        let mut client = connect(&server);
+
+        let songs = client
+            .find(&[Filter::new(Tag::Base, "Rock")])
+            .unwrap()
+            .into_iter()
+            .map(|song| song.file)
+            .collect_vec();
+        client.add_multiple(&["Rock+"]).unwrap();
+
+        assert_eq!(songs, ["Rock/01.flac"]);
+        assert_eq!(
+            server.state().queue.iter().map(|song| song.file.as_str()).collect_vec(),
+            ["Rock+/03.flac"]
+        );
+    }
+
+    #[test]
+    fn idle_is_notified_about_changes_of_other_clients() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut idle_client = connect(&server);
+        let mut client = connect(&server);
+        let idle = std::thread::spawn(move || idle_client.idle(&[]).unwrap());
+
+        client.add("other/03.flac").unwrap();
+
+        let events = idle.join().unwrap();
+        assert!(events.iter().any(|event| matches!(event, IdleEvent::Playlist)));
+    }
+
+    #[test]
+    fn idle_is_only_notified_about_requested_subsystems() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut idle_client = connect(&server);
+        let mut client = connect(&server);
+        let idle = std::thread::spawn(move || idle_client.idle(&[IdleEvent::Mixer, IdleEvent::Options]).unwrap());
+
+        client.add("other/03.flac").unwrap();
+        client.set_volume(Volume::new(30)).unwrap();
+
+        assert_eq!(idle.join().unwrap(), [IdleEvent::Mixer]);
+    }
+
+    #[test]
+    fn interrupt_before_idle_is_not_lost() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut idle_client = connect(&server);
+        let mut interrupter = idle_client.idle_interrupter().unwrap();
+
+        interrupter.interrupt().unwrap();
+        let events = idle_client.idle_unless(&[], || true).unwrap();
+
+        assert_eq!(events, None);
+        assert!(!server.state().commands.iter().any(|command| command.contains("idle")));
+    }
+
+    #[test]
+    fn interrupt_stops_idling() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut idle_client = connect(&server);
+        let mut interrupter = idle_client.idle_interrupter().unwrap();
+        let idle = std::thread::spawn(move || idle_client.idle_unless(&[], || false).unwrap());
+        while !server.state().commands.iter().any(|command| command == "idle") {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        interrupter.interrupt().unwrap();
+
+        assert_eq!(idle.join().unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn album_art_is_transferred_in_chunks() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+
+        let picture = client.find_album_art("artist/album/01.flac").unwrap();
+        let missing = client.find_album_art("artist/album/02.flac").unwrap();
+
+        assert_eq!(picture, library()[0].picture);
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn cancelled_album_art_transfer_stops() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+        let generation = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1));
+        client.set_cancel_token(Some(CancelToken::new(std::sync::Arc::clone(&generation), 0)));
+
+        let result = client.find_album_art("artist/album/01.flac");
+        client.set_cancel_token(None);
+
+        assert_eq!(result, Err(MpdError::Cancelled));
+        assert_eq!(client.get_status().unwrap().state, State::Stop);
+    }
+
+    #[test]
+    fn client_reconnects_after_connection_drop() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+        client.add("other/03.flac").unwrap();
+
+        server.drop_connections();
+
+        assert_eq!(client.playlist_info().unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn queue_is_synced_incrementally() {
+        let server = FakeMpdServer::start().with_library(library());
+        let mut client = connect(&server);
+        let mut context: AppContext = app_context();
+        client.add("artist/album").unwrap();
+        context.sync_queue(&mut client).unwrap();
+
+        client.add("other/03.flac").unwrap();
+        let first_id = context.queue[0].id;
+        client.move_id(first_id, QueueMoveTarget::Absolute(2)).unwrap();
+        client.delete_id(context.queue[1].id).unwrap();
+        context.sync_queue(&mut client).unwrap();
+
+        let expected = client.playlist_info().unwrap().unwrap();
+        assert_eq!(
+            context.queue.iter().map(|song| (song.id, &song.file)).collect_vec(),
+            expected.iter().map(|song| (song.id, &song.file)).collect_vec()
+        );
+        assert_eq!(context.queue_version, Some(server.state().queue_version()));
+    }
+
+    #[test]
+    fn messages_are_delivered_to_subscribers() {
+        let server = FakeMpdServer::start();
+        let mut receiver = connect(&server);
+        let mut sender = connect(&server);
+        receiver.subscribe("rmpc").unwrap();
+        receiver.subscribe("other").unwrap();
+
+        sender.send_message("rmpc", r#"say "hi" \o/"#).unwrap();
+        sender.send_message("other", "second").unwrap();
+        let unsubscribed = sender.send_message("nobody", "lost");
+
+        assert_eq!(sender.channels().unwrap().0, ["other", "rmpc"]);
+        assert_eq!(
+            receiver.read_messages().unwrap().0,
+            [
+                Message {
+                    channel: "rmpc".to_owned(),
+                    message: r#"say "hi" \o/"#.to_owned()
+                },
+                Message {
+                    channel: "other".to_owned(),
+                    message: "second".to_owned()
+                },
+            ]
+        );
+        assert!(receiver.read_messages().unwrap().0.is_empty());
+        assert!(matches!(
+            unsubscribed,
+            Err(MpdError::Mpd(MpdFailureResponse {
+                code: ErrorCode::NoExist,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn unsubscribed_client_gets_no_messages() {
+        let server = FakeMpdServer::start();
+        let mut receiver = connect(&server);
+        let mut sender = connect(&server);
+        receiver.subscribe("rmpc").unwrap();
+        receiver.subscribe("other").unwrap();
+
+        receiver.unsubscribe("rmpc").unwrap();
+        let result = sender.send_message("rmpc", "lost");
+
+        assert!(result.is_err());
+        assert_eq!(sender.channels().unwrap().0, ["other"]);
+        assert!(receiver.read_messages().unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn message_wakes_up_idling_subscriber() {
+        let server = FakeMpdServer::start();
+        let mut receiver = connect(&server);
+        let mut sender = connect(&server);
+        receiver.subscribe("rmpc").unwrap();
+        let idle = std::thread::spawn(move || {
+            let events = receiver.idle_unless(&[IdleEvent::Message], || false).unwrap();
+            (events, receiver.read_messages().unwrap())
+        });
+        while !server
+            .state()
+            .commands
+            .iter()
+            .any(|command| command.starts_with("idle"))
+        {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        sender.send_message("rmpc", "wake up").unwrap();
+
+        let (events, messages) = idle.join().unwrap();
+        assert_eq!(events, Some(vec![IdleEvent::Message]));
+        assert_eq!(messages.0.len(), 1);
+        assert_eq!(messages.0[0].message, "wake up");
+    }
+
+    #[test]
+    fn subscriptions_are_restored_on_reconnect() {
+        let server = FakeMpdServer::start();
+        let mut receiver = connect(&server);
+        let mut sender = connect(&server);
+        receiver.subscribe("rmpc").unwrap();
+
+        server.drop_connections();
+        assert!(receiver.read_messages().unwrap().0.is_empty());
+        sender.send_message("rmpc", "still there").unwrap();
+
+        assert_eq!(receiver.read_messages().unwrap().0[0].message, "still there");
+    }
+
+    #[test]
+    fn failed_subscription_is_restored_on_next_reconnect() {
+        let server = FakeMpdServer::start();
+        let mut receiver = connect(&server);
+        let mut sender = connect(&server);
+        receiver.subscribe("first").unwrap();
+        receiver.subscribe("second").unwrap();
+        server.state().failing_commands = vec![r#"subscribe "first""#.to_owned()];
+
+        server.drop_connections();
+        assert!(receiver.read_messages().unwrap().0.is_empty());
+        sender.send_message("second", "restored").unwrap();
+        assert_eq!(receiver.read_messages().unwrap().0[0].message, "restored");
+
+        server.state().failing_commands.clear();
+        server.drop_connections();
+        assert!(receiver.read_messages().unwrap().0.is_empty());
+        sender.send_message("first", "restored later").unwrap();
+        assert_eq!(receiver.read_messages().unwrap().0[0].message, "restored later");
+    }
+
+    /// Runs the fake server until killed so that rmpc can be pointed at it with `--address`
+    #[test]
+    #[ignore = "runs forever, start it manually"]
+    fn run_fake_mpd_server() {
+        let address = std::env::var("RMPC_FAKE_MPD_ADDRESS").unwrap_or_else(|_| "127.0.0.1:6601".to_owned());
+        let server = FakeMpdServer::start_tcp(&address).with_library(library());
+        println!("Fake MPD server listening on {:?}", server.address());
+        loop {
+            std::thread::park();
+        }
+    }
+}
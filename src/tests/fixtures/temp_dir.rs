@@ -0,0 +1,45 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique directory in the system temp dir which is removed together with its content when dropped
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "rmpc-test-{}-{}-{name}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("Test temp dir to be created");
+        Self { path }
+    }
+
+    /// Path of the given file inside of this directory
+    pub fn join(&self, file: impl AsRef<Path>) -> PathBuf {
+        self.path.join(file)
+    }
+
+    /// Writes the file inside of this directory, creating its parent directories, and returns its path
+    pub fn write(&self, file: impl AsRef<Path>, content: &str) -> PathBuf {
+        let path = self.path.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("Test file parent dir to be created");
+        }
+        std::fs::write(&path, content).expect("Test file to be written");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
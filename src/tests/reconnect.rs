@@ -0,0 +1,75 @@
+use std::{sync::mpsc::channel, time::Duration};
+
+use crate::{
+    idle_task,
+    mpd::{client::Client, commands::idle::IdleEvent, mpd_client::MpdClient},
+    tests::fixtures::{
+        app_context,
+        mpd_server::{FakeMpdServer, FakeSong},
+    },
+    AppEvent, IdleCommand,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn wait_for_idle(server: &FakeMpdServer, idle_count: usize) {
+    let start = std::time::Instant::now();
+    while server
+        .state()
+        .commands
+        .iter()
+        .filter(|command| *command == "idle")
+        .count()
+        < idle_count
+    {
+        assert!(start.elapsed() < TIMEOUT, "Idle client did not start idling");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn main_and_idle_clients_reconnect_after_connections_are_dropped() {
+    let server = FakeMpdServer::start().with_library([FakeSong::new("song.flac")]);
+    let config = app_context().config;
+    let mut client = Client::init(server.address(), None, "command", true).unwrap();
+    let idle_client = Client::init(server.address(), None, "idle", true).unwrap();
+    let (event_sender, event_receiver) = channel();
+    let (_command_sender, command_receiver) = channel();
+    std::thread::spawn(move || idle_task(idle_client, event_sender, command_receiver, config));
+    wait_for_idle(&server, 1);
+
+    server.drop_connections();
+
+    wait_for_idle(&server, 2);
+    client.add("song.flac").unwrap();
+    // The idle client also reports its channel subscriptions
+    while !matches!(
+        event_receiver.recv_timeout(TIMEOUT).unwrap(),
+        AppEvent::IdleEvent(IdleEvent::Playlist)
+    ) {}
+    assert_eq!(server.state().queue.len(), 1);
+}
+
+#[test]
+fn idle_client_follows_partition_switched_while_disconnected() {
+    let server = FakeMpdServer::start();
+    let config = app_context().config;
+    let idle_client = Client::disconnected(server.address(), None, "idle", true);
+    let (event_sender, _event_receiver) = channel();
+    let (command_sender, command_receiver) = channel();
+    std::thread::spawn(move || idle_task(idle_client, event_sender, command_receiver, config));
+
+    command_sender
+        .send(IdleCommand::SwitchPartition("other".to_owned()))
+        .unwrap();
+    command_sender.send(IdleCommand::Reconnect).unwrap();
+    wait_for_idle(&server, 1);
+
+    let commands = server.state().commands.clone();
+    let partition = commands.iter().position(|command| command == r#"partition "other""#);
+    let idle = commands.iter().position(|command| command == "idle");
+    assert!(
+        partition.is_some_and(|partition| Some(partition) < idle),
+        "{commands:?}"
+    );
+}
@@ -5,14 +5,13 @@ use ratatui::{prelude::Rect, widgets::ListItem};
 
 use crate::{
     cli::{create_env, run_external},
-    config::{
-        keys::{CommonAction, GlobalAction},
-        Config,
-    },
+    config::keys::{CommonAction, GlobalAction},
     context::AppContext,
     mpd::{commands::Song, mpd_client::MpdClient},
     shared::{
+        ext::mpd_client::MpdClientExt,
         key_event::KeyEvent,
+        macros::status_info,
         mouse_event::{MouseEvent, MouseEventKind},
     },
 };
@@ -42,9 +41,50 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>>;
+    /// Songs in the marked items, or in the selected item when nothing is marked
+    fn marked_or_selected_songs(&self, client: &mut impl MpdClient) -> Result<Vec<Song>> {
+        let current = self.stack().current();
+        if current.marked().is_empty() {
+            current
+                .selected()
+                .map_or_else(|| Ok(Vec::new()), |item| self.list_songs_in_item(client, item))
+        } else {
+            current
+                .marked_items()
+                .map(|item| self.list_songs_in_item(client, item))
+                .flatten_ok()
+                .try_collect()
+        }
+    }
     fn add(&self, item: &T, client: &mut impl MpdClient, context: &AppContext) -> Result<()>;
+    /// Adds all items to the queue at once
+    fn add_items(&self, items: &[&T], client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
+        let songs: Vec<_> = items
+            .iter()
+            .map(|item| self.list_songs_in_item(client, item))
+            .flatten_ok()
+            .try_collect()?;
+        let songs = songs.iter().map(|song| song.file.as_str()).collect_vec();
+
+        client.add_multiple(&songs)?;
+        status_info!("Added {} songs to queue", songs.len());
+
+        Ok(())
+    }
+    /// Adds songs in the marked items, or in the selected item when nothing is marked, to the
+    /// queue in random order
+    fn add_shuffled(&self, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
+        let songs = self.marked_or_selected_songs(client)?;
+        let songs = songs.iter().map(|song| song.file.as_str()).collect_vec();
+
+        client.add_shuffled(&songs)?;
+        status_info!("Added {} songs to queue in random order", songs.len());
+
+        context.render()?;
+        Ok(())
+    }
     fn add_all(&self, client: &mut impl MpdClient, context: &AppContext) -> Result<()>;
     fn open(&mut self, client: &mut impl MpdClient, context: &AppContext) -> Result<()>;
     fn delete(&self, item: &T, index: usize, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
@@ -68,7 +108,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
             Some(CommonAction::Close) => {
                 self.set_filter_input_mode_active(false);
                 self.stack_mut().current_mut().set_filter(None, config);
-                let preview = self.prepare_preview(client, config)?;
+                let preview = self.prepare_preview(client, context)?;
                 self.stack_mut().set_preview(preview);
                 context.render()?;
             }
@@ -82,7 +122,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                     KeyCode::Char(c) => {
                         self.stack_mut().current_mut().push_filter(c, config);
                         self.stack_mut().current_mut().jump_first_matching(config);
-                        let preview = self.prepare_preview(client, config)?;
+                        let preview = self.prepare_preview(client, context)?;
                         self.stack_mut().set_preview(preview);
                         context.render()?;
                     }
@@ -130,6 +170,24 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                     run_external(command, create_env(context, songs, client)?);
                 }
             }
+            GlobalAction::RateSelectedSong(rating) if !self.stack().current().marked().is_empty() => {
+                let songs: Vec<_> = self
+                    .stack()
+                    .current()
+                    .marked_items()
+                    .map(|item| self.list_songs_in_item(client, item))
+                    .flatten_ok()
+                    .try_collect()?;
+                client.rate_songs(&songs.iter().map(|song| song.file.as_str()).collect_vec(), rating)?;
+                status_info!("Rated {} songs", songs.len());
+            }
+            GlobalAction::RateSelectedSong(rating) => {
+                if let Some(selected) = self.stack().current().selected() {
+                    let songs = self.list_songs_in_item(client, selected)?;
+                    client.rate_songs(&songs.iter().map(|song| song.file.as_str()).collect_vec(), rating)?;
+                    status_info!("Rated {} songs", songs.len());
+                }
+            }
             _ => {
                 event.abandon();
             }
@@ -156,7 +214,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 }
                 self.stack_mut().pop();
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
@@ -168,7 +226,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 if let Some(idx_to_select) = self.stack().current().state.get_at_rendered_row(clicked_row) {
                     self.next(client, context)?;
                     let preview = self
-                        .prepare_preview(client, context.config)
+                        .prepare_preview(client, context)
                         .context("Cannot prepare preview")?;
                     self.stack_mut().set_preview(preview);
 
@@ -187,7 +245,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                     }
 
                     let preview = self
-                        .prepare_preview(client, context.config)
+                        .prepare_preview(client, context)
                         .context("Cannot prepare preview")?;
                     self.stack_mut().set_preview(preview);
 
@@ -202,7 +260,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                         .current_mut()
                         .select_idx(idx_to_select, context.config.scrolloff);
                     let preview = self
-                        .prepare_preview(client, context.config)
+                        .prepare_preview(client, context)
                         .context("Cannot prepare preview")?;
                     self.stack_mut().set_preview(preview);
                     context.render()?;
@@ -226,7 +284,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                     .select_idx(idx_to_select.unwrap_or_default(), 0);
 
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
@@ -235,7 +293,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
             MouseEventKind::ScrollUp if current_area.contains(position) => {
                 self.stack_mut().current_mut().prev(context.config.scrolloff, false);
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
@@ -244,7 +302,7 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
             MouseEventKind::ScrollDown if current_area.contains(position) => {
                 self.stack_mut().current_mut().next(context.config.scrolloff, false);
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
@@ -272,7 +330,9 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 self.stack_mut()
                     .current_mut()
                     .prev(config.scrolloff, config.wrap_navigation);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
@@ -281,7 +341,9 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 self.stack_mut()
                     .current_mut()
                     .next(config.scrolloff, config.wrap_navigation);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
@@ -296,7 +358,9 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 self.stack_mut()
                     .current_mut()
                     .next_half_viewport(context.config.scrolloff);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
@@ -305,33 +369,43 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 self.stack_mut()
                     .current_mut()
                     .prev_half_viewport(context.config.scrolloff);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
             }
             CommonAction::Bottom => {
                 self.stack_mut().current_mut().last();
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
             }
             CommonAction::Top => {
                 self.stack_mut().current_mut().first();
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
             }
             CommonAction::Right => {
                 self.next(client, context)?;
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
             }
             CommonAction::Left => {
                 self.stack_mut().pop();
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
@@ -344,14 +418,18 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
             }
             CommonAction::NextResult => {
                 self.stack_mut().current_mut().jump_next_matching(config);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
             }
             CommonAction::PreviousResult => {
                 self.stack_mut().current_mut().jump_previous_matching(config);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
@@ -361,16 +439,16 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 self.stack_mut()
                     .current_mut()
                     .next(context.config.scrolloff, context.config.wrap_navigation);
-                let preview = self.prepare_preview(client, config).context("Cannot prepare preview")?;
+                let preview = self
+                    .prepare_preview(client, context)
+                    .context("Cannot prepare preview")?;
                 self.stack_mut().set_preview(preview);
 
                 context.render()?;
             }
             CommonAction::Add if !self.stack().current().marked().is_empty() => {
-                for idx in self.stack().current().marked().iter().rev() {
-                    let item = &self.stack().current().items[*idx];
-                    self.add(item, client, context)?;
-                }
+                let items = self.stack().current().marked_items().collect_vec();
+                self.add_items(&items, client, context)?;
 
                 context.render()?;
             }
@@ -386,6 +464,24 @@ pub(in crate::ui) trait BrowserPane<T: DirStackItem + std::fmt::Debug>: Pane {
                 context.render()?;
             }
             CommonAction::AddAll => {}
+            CommonAction::InsertAfterCurrent => {
+                let songs = self.marked_or_selected_songs(client)?;
+                let songs = songs.iter().map(|song| song.file.as_str()).collect_vec();
+
+                client.insert_after_current(&songs, &context.status, &context.capabilities)?;
+                status_info!("Inserted {} songs after the current song", songs.len());
+
+                context.render()?;
+            }
+            CommonAction::AddWithPriority => {
+                let songs = self.marked_or_selected_songs(client)?;
+                let songs = songs.iter().map(|song| song.file.as_str()).collect_vec();
+
+                client.add_with_priority(&songs)?;
+                status_info!("Added {} songs to queue with high priority", songs.len());
+
+                context.render()?;
+            }
             CommonAction::Delete if !self.stack().current().marked().is_empty() => {
                 for idx in self.stack().current().marked().iter().rev() {
                     let item = &self.stack().current().items[*idx];
@@ -1,8 +1,6 @@
 use std::{collections::HashMap, io::Stdout, ops::AddAssign, time::Duration};
 
-#[cfg(debug_assertions)]
-use crate::config::tabs::PaneType;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, KeyCode},
     execute,
@@ -10,10 +8,14 @@ use crossterm::{
 };
 use enum_map::{enum_map, Enum, EnumMap};
 use itertools::Itertools;
-use modals::{decoders::DecodersModal, keybinds::KeybindsModal, outputs::OutputsModal, song_info::SongInfoModal};
+use modals::{
+    decoders::DecodersModal, keybinds::KeybindsModal, outputs::OutputsModal, partitions::PartitionsModal,
+    playback_options::PlaybackOptionsModal, servers::ServersModal, song_info::SongInfoModal,
+};
 use panes::{PaneContainer, Panes};
 #[cfg(debug_assertions)]
 use ratatui::style::Stylize;
+use serde::{Deserialize, Serialize};
 
 use ratatui::{
     layout::Rect,
@@ -21,7 +23,7 @@ use ratatui::{
     style::{Color, Style},
     symbols::border,
     text::Text,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame, Terminal,
 };
 use tab_screen::TabScreen;
@@ -32,7 +34,7 @@ use crate::{
     config::{
         cli::Args,
         keys::{CommonAction, GlobalAction},
-        tabs::TabName,
+        tabs::{PaneType, TabName},
         Config,
     },
     mpd::{
@@ -41,14 +43,23 @@ use crate::{
         mpd_client::{FilterKind, MpdClient, ValueChange},
     },
     shared::{
+        ext::mpd_client::MpdClientExt,
+        ipc::ControlMessage,
         key_event::KeyEvent,
         macros::{modal, status_error, status_info, status_warn, try_ret},
         mouse_event::{MouseEvent, MouseEventKind},
     },
 };
-use crate::{context::AppContext, mpd::version::Version};
+use crate::{
+    context::{AppContext, ConnectionState},
+    mpd::capabilities::Feature,
+};
 
-use self::{modals::Modal, panes::Pane, widgets::header::Header};
+use self::{
+    modals::{Modal, RectExt},
+    panes::Pane,
+    widgets::header::Header,
+};
 
 pub mod browser;
 pub mod dirstack;
@@ -58,7 +69,7 @@ pub mod panes;
 pub mod tab_screen;
 pub mod widgets;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum Level {
     Trace,
@@ -236,9 +247,43 @@ impl<'ui> Ui<'ui> {
             modal.render(frame, context)?;
         }
 
+        if let ConnectionState::Disconnected { retry_at, .. } = context.connection {
+            Self::render_disconnected_overlay(frame, context, retry_at);
+        }
+
         Ok(())
     }
 
+    #[allow(clippy::cast_possible_truncation)]
+    fn render_disconnected_overlay(frame: &mut Frame, context: &AppContext, retry_at: std::time::Instant) {
+        let remaining = retry_at.saturating_duration_since(std::time::Instant::now());
+        let text = if remaining.is_zero() {
+            "Disconnected from MPD, reconnecting...".to_owned()
+        } else {
+            format!(
+                "Disconnected from MPD, retrying in {}s",
+                remaining.as_millis().div_ceil(1000)
+            )
+        };
+
+        let popup_area = frame.area().centered_exact(text.chars().count() as u16 + 4, 3);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = context.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let paragraph = Paragraph::new(text)
+            .alignment(ratatui::prelude::Alignment::Center)
+            .style(context.config.as_text_style())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(border::ROUNDED)
+                    .border_style(context.config.as_border_style()),
+            );
+        frame.render_widget(paragraph, popup_area);
+    }
+
     pub fn handle_mouse_event(
         &mut self,
         event: MouseEvent,
@@ -324,6 +369,9 @@ impl<'ui> Ui<'ui> {
 
                 self.command = None;
                 match cmd {
+                    Ok(Args { command: Some(cmd), .. }) if cmd.runs_until_interrupted() => {
+                        bail!("Cannot use watch or follow commands here.");
+                    }
                     Ok(Args { command: Some(cmd), .. }) => {
                         cmd.execute(client, context.config, |request, _| {
                             if let Err(err) = context.work_sender.send(request) {
@@ -364,105 +412,227 @@ impl<'ui> Ui<'ui> {
         screen_call!(self, handle_action(key, client, context))?;
 
         if let Some(action) = key.as_global_action(context) {
-            match action {
-                GlobalAction::Command { command, .. } => {
-                    let cmd = command.parse();
-                    log::debug!("executing {:?}", cmd);
+            return self.handle_global_action(action, context, client);
+        }
 
-                    self.command = None;
-                    if let Ok(Args { command: Some(cmd), .. }) = cmd {
-                        cmd.execute(client, context.config, |request, _| {
-                            if let Err(err) = context.work_sender.send(request) {
-                                status_error!("Failed to send work request: {}", err);
-                            }
-                        })?;
+        Ok(KeyHandleResult::None)
+    }
+
+    pub fn handle_global_action(
+        &mut self,
+        action: GlobalAction,
+        context: &mut AppContext,
+        client: &mut Client<'_>,
+    ) -> Result<KeyHandleResult> {
+        match action {
+            GlobalAction::Command { command, .. } => {
+                let cmd = command.parse();
+                log::debug!("executing {:?}", cmd);
+
+                self.command = None;
+                if let Ok(Args { command: Some(cmd), .. }) = cmd {
+                    if cmd.runs_until_interrupted() {
+                        bail!("Cannot use watch or follow commands here.");
                     }
+                    cmd.execute(client, context.config, |request, _| {
+                        if let Err(err) = context.work_sender.send(request) {
+                            status_error!("Failed to send work request: {}", err);
+                        }
+                    })?;
                 }
-                GlobalAction::CommandMode => {
-                    self.command = Some(String::new());
+            }
+            GlobalAction::CommandMode => {
+                self.command = Some(String::new());
+                context.render()?;
+            }
+            GlobalAction::NextTrack if context.status.state == State::Play => client.next()?,
+            GlobalAction::PreviousTrack if context.status.state == State::Play => client.prev()?,
+            GlobalAction::Stop if context.status.state == State::Play => client.stop()?,
+            GlobalAction::ToggleRepeat => client.repeat(!context.status.repeat)?,
+            GlobalAction::ToggleRandom => client.random(!context.status.random)?,
+            GlobalAction::ToggleSingle if !context.capabilities.supports(Feature::SingleOneshot) => {
+                client.single(context.status.single.cycle_pre_mpd_24())?;
+            }
+            GlobalAction::ToggleSingle => client.single(context.status.single.cycle())?,
+            GlobalAction::ToggleConsume if !context.capabilities.supports(Feature::ConsumeOneshot) => {
+                client.consume(context.status.consume.cycle_pre_mpd_24())?;
+            }
+            GlobalAction::ToggleConsume => {
+                client.consume(context.status.consume.cycle())?;
+            }
+            GlobalAction::TogglePause if matches!(context.status.state, State::Play | State::Pause) => {
+                client.pause_toggle()?;
+            }
+            GlobalAction::TogglePause => {}
+            GlobalAction::VolumeUp => {
+                client.set_volume(*context.status.volume.inc_by(context.config.volume_step))?;
+            }
+            GlobalAction::VolumeDown => {
+                client.set_volume(*context.status.volume.dec_by(context.config.volume_step))?;
+            }
+            GlobalAction::SeekForward if matches!(context.status.state, State::Play | State::Pause) => {
+                client.seek_current(ValueChange::Increase(5))?;
+            }
+            GlobalAction::SeekBack if matches!(context.status.state, State::Play | State::Pause) => {
+                client.seek_current(ValueChange::Decrease(5))?;
+            }
+            GlobalAction::CrossfadeUp => {
+                client.crossfade(context.status.xfade.unwrap_or_default().saturating_add(1))?;
+            }
+            GlobalAction::CrossfadeDown => {
+                client.crossfade(context.status.xfade.unwrap_or_default().saturating_sub(1))?;
+            }
+            GlobalAction::CycleReplayGainMode => {
+                let mode = client.replay_gain_status()?.mode.cycle();
+                client.replay_gain_mode(mode)?;
+                status_info!("ReplayGain mode set to {mode}");
+            }
+            GlobalAction::NextTab => {
+                self.change_tab(context.config.next_screen(self.active_tab), client, context)?;
+                context.render()?;
+            }
+            GlobalAction::PreviousTab => {
+                self.change_tab(context.config.prev_screen(self.active_tab), client, context)?;
+                context.render()?;
+            }
+            GlobalAction::SwitchToTab(name) => {
+                if context.config.tabs.names.contains(&name) {
+                    self.change_tab(name, client, context)?;
                     context.render()?;
+                } else {
+                    status_error!("Tab with name '{}' does not exist. Check your configuration.", name);
                 }
-                GlobalAction::NextTrack if context.status.state == State::Play => client.next()?,
-                GlobalAction::PreviousTrack if context.status.state == State::Play => client.prev()?,
-                GlobalAction::Stop if context.status.state == State::Play => client.stop()?,
-                GlobalAction::ToggleRepeat => client.repeat(!context.status.repeat)?,
-                GlobalAction::ToggleRandom => client.random(!context.status.random)?,
-                GlobalAction::ToggleSingle if client.version() < Version::new(0, 21, 0) => {
-                    client.single(context.status.single.cycle_pre_mpd_24())?;
-                }
-                GlobalAction::ToggleSingle => client.single(context.status.single.cycle())?,
-                GlobalAction::ToggleConsume if client.version() < Version::new(0, 24, 0) => {
-                    client.consume(context.status.consume.cycle_pre_mpd_24())?;
-                }
-                GlobalAction::ToggleConsume => {
-                    client.consume(context.status.consume.cycle())?;
-                }
-                GlobalAction::TogglePause if matches!(context.status.state, State::Play | State::Pause) => {
-                    client.pause_toggle()?;
-                }
-                GlobalAction::TogglePause => {}
-                GlobalAction::VolumeUp => {
-                    client.set_volume(*context.status.volume.inc_by(context.config.volume_step))?;
-                }
-                GlobalAction::VolumeDown => {
-                    client.set_volume(*context.status.volume.dec_by(context.config.volume_step))?;
-                }
-                GlobalAction::SeekForward if matches!(context.status.state, State::Play | State::Pause) => {
-                    client.seek_current(ValueChange::Increase(5))?;
-                }
-                GlobalAction::SeekBack if matches!(context.status.state, State::Play | State::Pause) => {
-                    client.seek_current(ValueChange::Decrease(5))?;
+            }
+            GlobalAction::NextTrack => {}
+            GlobalAction::PreviousTrack => {}
+            GlobalAction::Stop => {}
+            GlobalAction::SeekBack => {}
+            GlobalAction::SeekForward => {}
+            GlobalAction::ExternalCommand { command, .. } => {
+                run_external(command, create_env(context, std::iter::empty::<&str>(), client)?);
+            }
+            GlobalAction::RateCurrentSong(rating) => {
+                if let Some((_, song)) = context.find_current_song_in_queue() {
+                    client.rate_song(&song.file, rating)?;
+                    status_info!("Rated '{}'", song.title().unwrap_or(&song.file));
+                } else {
+                    status_info!("No song is currently playing");
                 }
-                GlobalAction::NextTab => {
-                    self.change_tab(context.config.next_screen(self.active_tab), client, context)?;
-                    context.render()?;
+            }
+            // Panes with songs rate them on their own
+            GlobalAction::RateSelectedSong(_) => {
+                status_info!("There are no songs to rate in this pane");
+            }
+            GlobalAction::Quit => return Ok(KeyHandleResult::Quit),
+            GlobalAction::ShowHelp => {
+                let modal = KeybindsModal::new(context);
+                modal!(context, modal);
+            }
+            GlobalAction::ShowOutputs => {
+                modal!(context, OutputsModal::new(client.outputs()?.0));
+            }
+            GlobalAction::ShowDecoders => {
+                modal!(context, DecodersModal::new(client.decoders()?.0));
+            }
+            GlobalAction::ShowPlaybackOptions => {
+                let replay_gain_mode = client.replay_gain_status()?.mode;
+                modal!(context, PlaybackOptionsModal::new(&context.status, replay_gain_mode));
+            }
+            GlobalAction::ShowPartitions => {
+                modal!(
+                    context,
+                    PartitionsModal::new(client.list_partitions()?.0, &context.status.partition)
+                );
+            }
+            GlobalAction::ShowServers => {
+                if context.config.servers.is_empty() {
+                    status_info!("No server profiles are configured");
+                } else {
+                    modal!(context, ServersModal::new(context));
                 }
-                GlobalAction::PreviousTab => {
-                    self.change_tab(context.config.prev_screen(self.active_tab), client, context)?;
-                    context.render()?;
+            }
+            GlobalAction::ShowCurrentSongInfo => {
+                if let Some(current_song) = context.get_current_song(client)? {
+                    modal!(context, SongInfoModal::new(context.with_all_tags(current_song, client)));
+                } else {
+                    status_info!("No song is currently playing");
                 }
-                GlobalAction::SwitchToTab(name) => {
-                    if context.config.tabs.names.contains(&name) {
-                        self.change_tab(name, client, context)?;
-                        context.render()?;
-                    } else {
-                        status_error!("Tab with name '{}' does not exist. Check your configuration.", name);
+            }
+        }
+
+        Ok(KeyHandleResult::None)
+    }
+
+    pub fn handle_control_message(
+        &mut self,
+        message: ControlMessage,
+        context: &mut AppContext,
+        client: &mut Client<'_>,
+    ) -> Result<KeyHandleResult> {
+        match message {
+            ControlMessage::Action(action) => {
+                let action: GlobalAction = action.into();
+                match action.required_feature() {
+                    Some(feature) if !context.capabilities.supports(feature) => {
+                        status_warn!("{feature} is not supported by your MPD server");
+                        Ok(KeyHandleResult::None)
                     }
+                    _ => self.handle_global_action(action, context, client),
                 }
-                GlobalAction::NextTrack => {}
-                GlobalAction::PreviousTrack => {}
-                GlobalAction::Stop => {}
-                GlobalAction::SeekBack => {}
-                GlobalAction::SeekForward => {}
-                GlobalAction::ExternalCommand { command, .. } => {
-                    run_external(command, create_env(context, std::iter::empty::<&str>(), client)?);
-                }
-                GlobalAction::Quit => return Ok(KeyHandleResult::Quit),
-                GlobalAction::ShowHelp => {
-                    let modal = KeybindsModal::new(context);
-                    modal!(context, modal);
-                }
-                GlobalAction::ShowOutputs => {
-                    modal!(context, OutputsModal::new(client.outputs()?.0));
-                }
-                GlobalAction::ShowDecoders => {
-                    modal!(context, DecodersModal::new(client.decoders()?.0));
-                }
-                GlobalAction::ShowCurrentSongInfo => {
-                    if let Some(current_song) = context.get_current_song(client)? {
-                        modal!(context, SongInfoModal::new(current_song));
-                    } else {
-                        status_info!("No song is currently playing");
-                    }
+            }
+            ControlMessage::Key(key) => {
+                let key = crossterm::event::KeyEvent::new(key.key, key.modifiers);
+                self.handle_key(&mut key.into(), context, client)
+            }
+            ControlMessage::Status { message, level } => {
+                self.display_message(message, level);
+                Ok(KeyHandleResult::None)
+            }
+            ControlMessage::JumpToCurrent => {
+                let Some(tab) = context.config.tabs.names.iter().find(|name| {
+                    self.tabs
+                        .get(name)
+                        .is_some_and(|tab| tab.panes.panes_iter().any(|pane| pane.pane == PaneType::Queue))
+                }) else {
+                    status_error!("No tab contains the queue. Check your configuration.");
+                    return Ok(KeyHandleResult::None);
+                };
+                if *tab != self.active_tab {
+                    self.change_tab(*tab, client, context)?;
                 }
+                self.panes.queue.jump_to_current(context)?;
+                Ok(KeyHandleResult::None)
             }
-        };
+        }
+    }
 
-        Ok(KeyHandleResult::None)
+    /// Rebuilds tabs and panes after the config was reloaded. The active tab is kept if it still
+    /// exists.
+    pub fn on_config_changed(&mut self, context: &mut AppContext, client: &mut impl MpdClient) -> Result<()> {
+        screen_call!(self, on_hide(client, &context))?;
+
+        if !context.config.tabs.names.contains(&self.active_tab) {
+            self.active_tab = *context.config.tabs.names.first().context("Expected at least one tab")?;
+        }
+        self.tabs = context
+            .config
+            .tabs
+            .tabs
+            .iter()
+            .map(|(name, screen)| (*name, TabScreen::new(&screen.panes)))
+            .collect();
+        self.tab_bar = AppTabs::new(self.active_tab, context.config);
+        self.panes.reload(context);
+
+        if context.connection.is_disconnected() {
+            // Everything is shown once the connection is back
+            return Ok(());
+        }
+        self.before_show(context, client)
     }
 
     pub fn before_show(&mut self, context: &mut AppContext, client: &mut impl MpdClient) -> Result<()> {
-        self.current_song = try_ret!(client.get_current_song(), "Failed to get current song");
+        self.current_song = try_ret!(context.get_current_song(client), "Failed to get current song");
         screen_call!(self, before_show(client, &context))
     }
 
@@ -503,12 +673,16 @@ impl<'ui> Ui<'ui> {
     ) -> Result<()> {
         match event {
             UiEvent::Player => {
-                self.current_song = try_ret!(client.get_current_song(), "Failed get current song");
+                self.current_song = try_ret!(context.get_current_song(client), "Failed get current song");
             }
             UiEvent::Database => {
                 status_warn!("The music database has been updated. Some parts of the UI may have been reinitialized to prevent inconsistent behaviours.");
             }
             UiEvent::StoredPlaylist => {}
+            UiEvent::Sticker => {
+                self.current_song = try_ret!(context.get_current_song(client), "Failed get current song");
+                context.render()?;
+            }
             UiEvent::LogAdded(_) => {
                 #[cfg(debug_assertions)]
                 if self
@@ -519,9 +693,12 @@ impl<'ui> Ui<'ui> {
                     context.render()?;
                 }
             }
+            UiEvent::AlbumArt(_) => {}
             UiEvent::Resized { .. } => {}
             UiEvent::ModalOpened => {}
             UiEvent::ModalClosed => {}
+            UiEvent::Disconnected => {}
+            UiEvent::Reconnected => {}
             UiEvent::Exit => {}
         }
 
@@ -559,10 +736,19 @@ pub enum UiEvent {
     Player,
     Database,
     StoredPlaylist,
+    Sticker,
     LogAdded(Vec<u8>),
-    Resized { columns: u16, rows: u16 },
+    AlbumArt(Option<Vec<u8>>),
+    Resized {
+        columns: u16,
+        rows: u16,
+    },
     ModalOpened,
     ModalClosed,
+    /// Connection to MPD was lost, the UI is covered by the disconnected overlay
+    Disconnected,
+    /// Connection to MPD is back, context has already been synced
+    Reconnected,
     Exit,
 }
 
@@ -574,6 +760,7 @@ impl TryFrom<IdleEvent> for UiEvent {
             IdleEvent::Player => UiEvent::Player,
             IdleEvent::Database => UiEvent::Database,
             IdleEvent::StoredPlaylist => UiEvent::StoredPlaylist,
+            IdleEvent::Sticker => UiEvent::Sticker,
             _ => return Err(()),
         })
     }
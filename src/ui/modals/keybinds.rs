@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::{borrow::Cow, collections::HashMap, fmt::Display};
+use std::{borrow::Cow, fmt::Display};
 
 use crate::{
     config::keys::{CommonAction, Key, ToDescription},
@@ -32,14 +32,15 @@ pub struct KeybindsModal<'a> {
     table_area: Rect,
 }
 
-fn add_binds<'a, V: Display + ToDescription>(
+fn add_binds<'a, 'b, V: Display + ToDescription + 'b>(
     result: &mut Vec<Row<'a>>,
-    binds: &HashMap<Key, V>,
+    binds: impl IntoIterator<Item = (&'b Key, &'b V)>,
     name: &'a str,
     header_style: Style,
     add_empty_line: bool,
 ) {
-    if !binds.is_empty() {
+    let mut binds = binds.into_iter().peekable();
+    if binds.peek().is_some() {
         result.push(
             Row::new(vec![
                 Line::raw(Cow::<str>::Borrowed(" ")).patch_style(header_style),
@@ -66,7 +67,11 @@ impl KeybindsModal<'_> {
         let header_style = app.config.theme.current_item_style;
 
         let mut rows = Vec::new();
-        add_binds(&mut rows, &keybinds.global, "Global", header_style, false);
+        // Actions not supported by the server are disabled so there is no point in showing them
+        let global = keybinds.global.iter().filter(
+            |(_, action)| !matches!(action.required_feature(), Some(feature) if !app.capabilities.supports(feature)),
+        );
+        add_binds(&mut rows, global, "Global", header_style, false);
         add_binds(&mut rows, &keybinds.navigation, "Navigation", header_style, true);
         add_binds(&mut rows, &keybinds.albums, "Albums", header_style, true);
         add_binds(&mut rows, &keybinds.artists, "Artists", header_style, true);
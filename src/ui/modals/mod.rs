@@ -15,10 +15,15 @@ pub mod confirm_modal;
 pub mod confirm_playlist_delete;
 pub mod confirm_queue_clear;
 pub mod decoders;
+pub mod input_modal;
 pub mod keybinds;
+pub mod new_playlist;
 pub mod outputs;
+pub mod partitions;
+pub mod playback_options;
 pub mod rename_playlist;
 pub mod save_queue;
+pub mod servers;
 pub mod song_info;
 
 pub(super) trait Modal: std::fmt::Debug {
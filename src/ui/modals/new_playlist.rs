@@ -0,0 +1,51 @@
+use anyhow::Result;
+
+use crate::{
+    context::AppContext,
+    mpd::{client::Client, mpd_client::MpdClient},
+    shared::{
+        ext::mpd_client::MpdClientExt,
+        macros::{status_error, status_info},
+    },
+};
+
+use super::input_modal::{InputAction, InputModal};
+
+/// Creates an empty playlist or, when `source` is set, a copy of the `source` playlist
+#[derive(Debug)]
+pub struct NewPlaylist {
+    source: Option<String>,
+}
+
+impl NewPlaylist {
+    pub fn modal<'a>(source: Option<String>, context: &AppContext) -> InputModal<'a, Self> {
+        let (title, name) = match &source {
+            Some(source) => ("Duplicate playlist", format!("{source} (copy)")),
+            None => ("New playlist", String::new()),
+        };
+        InputModal::new(title, "Name:", name, Self { source }, context)
+    }
+}
+
+impl InputAction for NewPlaylist {
+    fn confirm(&self, name: &str, client: &mut Client<'_>) -> Result<()> {
+        if name.is_empty() {
+            status_error!("Playlist name cannot be empty");
+            return Ok(());
+        }
+        if client.list_playlists()?.iter().any(|playlist| playlist.name == name) {
+            status_error!("Playlist '{}' already exists", name);
+            return Ok(());
+        }
+
+        if let Some(source) = &self.source {
+            client.copy_playlist(source, name)?;
+            status_info!("Playlist '{}' duplicated as '{}'", source, name);
+        } else {
+            client.clear_playlist(name)?;
+            status_info!("Playlist '{}' created", name);
+        }
+
+        Ok(())
+    }
+}
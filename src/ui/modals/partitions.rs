@@ -0,0 +1,205 @@
+use anyhow::Result;
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    config::keys::CommonAction,
+    context::AppContext,
+    mpd::client::Client,
+    shared::{
+        key_event::KeyEvent,
+        macros::pop_modal,
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::dirstack::DirState,
+    AppEvent,
+};
+
+use super::{Modal, RectExt};
+
+#[derive(Debug)]
+pub struct PartitionsModal {
+    scrolling_state: DirState<TableState>,
+    partitions_table_area: Rect,
+    partitions: Vec<String>,
+}
+
+impl PartitionsModal {
+    pub fn new(partitions: Vec<String>, current_partition: &str) -> Self {
+        let mut result = Self {
+            scrolling_state: DirState::default(),
+            partitions_table_area: Rect::default(),
+            partitions,
+        };
+        result.scrolling_state.set_content_len(Some(result.partitions.len()));
+        match result.partitions.iter().position(|p| p == current_partition) {
+            Some(idx) => result.scrolling_state.select(Some(idx), 0),
+            None => result.scrolling_state.first(),
+        }
+
+        result
+    }
+
+    fn switch_to_selected_partition(&mut self, context: &AppContext) -> Result<()> {
+        let Some(partition) = self
+            .scrolling_state
+            .get_selected()
+            .and_then(|idx| self.partitions.get(idx))
+        else {
+            return Ok(());
+        };
+
+        if *partition != context.status.partition {
+            context
+                .app_event_sender
+                .send(AppEvent::SwitchPartition(partition.clone()))?;
+        }
+        pop_modal!(context);
+
+        Ok(())
+    }
+}
+
+impl Modal for PartitionsModal {
+    fn render(&mut self, frame: &mut ratatui::Frame, app: &mut AppContext) -> anyhow::Result<()> {
+        let popup_area = frame.area().centered_exact(60, 10);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = app.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(app.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Partitions");
+
+        let table_area = popup_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+
+        let rows = self.partitions.iter().map(|partition| {
+            Row::new([
+                Cell::from(partition.clone()),
+                Cell::from(if *partition == app.status.partition { "yes" } else { "" }),
+            ])
+        });
+
+        self.scrolling_state.set_viewport_len(Some(table_area.height.into()));
+
+        let table = Table::new(rows, [Constraint::Percentage(100), Constraint::Length(10)])
+            .column_spacing(0)
+            .style(app.config.as_text_style())
+            .header(Row::new(["Name", "Active"]))
+            .row_highlight_style(app.config.theme.current_item_style);
+
+        let table_area = table_area.inner(Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+        self.partitions_table_area = table_area;
+
+        frame.render_widget(block, popup_area);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+        frame.render_stateful_widget(
+            app.config.as_styled_scrollbar(),
+            popup_area.inner(Margin {
+                horizontal: 0,
+                vertical: 1,
+            }),
+            self.scrolling_state.as_scrollbar_state_ref(),
+        );
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut KeyEvent, _client: &mut Client<'_>, context: &mut AppContext) -> Result<()> {
+        if let Some(action) = key.as_common_action(context) {
+            match action {
+                CommonAction::DownHalf => {
+                    self.scrolling_state.next_half_viewport(context.config.scrolloff);
+
+                    context.render()?;
+                }
+                CommonAction::UpHalf => {
+                    self.scrolling_state.prev_half_viewport(context.config.scrolloff);
+
+                    context.render()?;
+                }
+                CommonAction::Up => {
+                    self.scrolling_state
+                        .prev(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Down => {
+                    self.scrolling_state
+                        .next(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+
+                    context.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+
+                    context.render()?;
+                }
+                CommonAction::Confirm => {
+                    self.switch_to_selected_partition(context)?;
+                }
+                CommonAction::Close => {
+                    pop_modal!(context);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        event: MouseEvent,
+        _client: &mut Client<'_>,
+        context: &mut AppContext,
+    ) -> Result<()> {
+        match event.kind {
+            MouseEventKind::LeftClick if self.partitions_table_area.contains(event.into()) => {
+                let y: usize = event.y.saturating_sub(self.partitions_table_area.y).into();
+                let y = y.saturating_sub(1); // Subtract one to account for table header
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), context.config.scrolloff);
+                    context.render()?;
+                }
+            }
+            MouseEventKind::DoubleClick if self.partitions_table_area.contains(event.into()) => {
+                self.switch_to_selected_partition(context)?;
+            }
+            MouseEventKind::MiddleClick => {}
+            MouseEventKind::RightClick => {}
+            MouseEventKind::ScrollDown if self.partitions_table_area.contains(event.into()) => {
+                self.scrolling_state.next(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::ScrollUp if self.partitions_table_area.contains(event.into()) => {
+                self.scrolling_state.prev(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::LeftClick => {}
+            MouseEventKind::DoubleClick => {}
+            MouseEventKind::ScrollDown => {}
+            MouseEventKind::ScrollUp => {}
+        }
+
+        Ok(())
+    }
+}
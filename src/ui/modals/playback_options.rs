@@ -0,0 +1,243 @@
+use anyhow::Result;
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    config::keys::CommonAction,
+    context::AppContext,
+    mpd::{
+        client::Client,
+        commands::{ReplayGainMode, Status},
+        mpd_client::MpdClient,
+    },
+    shared::{
+        key_event::KeyEvent,
+        macros::pop_modal,
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::dirstack::DirState,
+};
+
+use super::{Modal, RectExt};
+
+const CROSSFADE: usize = 0;
+const MIXRAMP_DB: usize = 1;
+const MIXRAMP_DELAY: usize = 2;
+const REPLAY_GAIN: usize = 3;
+const OPTIONS_COUNT: usize = 4;
+
+#[derive(Debug)]
+pub struct PlaybackOptionsModal {
+    scrolling_state: DirState<TableState>,
+    options_table_area: Rect,
+    crossfade: u32,
+    mixramp_db: f32,
+    /// None when mixramp is disabled
+    mixramp_delay: Option<f32>,
+    replay_gain_mode: ReplayGainMode,
+}
+
+impl PlaybackOptionsModal {
+    pub fn new(status: &Status, replay_gain_mode: ReplayGainMode) -> Self {
+        let mut result = Self {
+            scrolling_state: DirState::default(),
+            options_table_area: Rect::default(),
+            crossfade: status.xfade.unwrap_or_default(),
+            mixramp_db: status
+                .mixrampdb
+                .as_ref()
+                .and_then(|db| db.parse().ok())
+                .unwrap_or_default(),
+            mixramp_delay: status
+                .mixrampdelay
+                .as_ref()
+                .and_then(|delay| delay.parse::<f32>().ok())
+                .filter(|delay| *delay >= 0.0),
+            replay_gain_mode,
+        };
+        result.scrolling_state.set_content_len(Some(OPTIONS_COUNT));
+        result.scrolling_state.first();
+
+        result
+    }
+
+    /// Increases or decreases the value of the selected option and sends it to MPD
+    fn change_selected_option(&mut self, increase: bool, client: &mut Client<'_>) -> Result<()> {
+        match self.scrolling_state.get_selected() {
+            Some(CROSSFADE) => {
+                self.crossfade = if increase {
+                    self.crossfade.saturating_add(1)
+                } else {
+                    self.crossfade.saturating_sub(1)
+                };
+                client.crossfade(self.crossfade)?;
+            }
+            Some(MIXRAMP_DB) => {
+                self.mixramp_db += if increase { 1.0 } else { -1.0 };
+                client.mixrampdb(self.mixramp_db)?;
+            }
+            Some(MIXRAMP_DELAY) => {
+                self.mixramp_delay = match (self.mixramp_delay, increase) {
+                    (None, true) => Some(0.0),
+                    (None, false) => None,
+                    (Some(delay), true) => Some(delay + 0.5),
+                    (Some(delay), false) if delay >= 0.5 => Some(delay - 0.5),
+                    (Some(_), false) => None,
+                };
+                client.mixrampdelay(self.mixramp_delay)?;
+            }
+            Some(REPLAY_GAIN) => {
+                self.replay_gain_mode = if increase {
+                    self.replay_gain_mode.cycle()
+                } else {
+                    self.replay_gain_mode.cycle_back()
+                };
+                client.replay_gain_mode(self.replay_gain_mode)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn rows(&self) -> [(&'static str, String); OPTIONS_COUNT] {
+        [
+            ("Crossfade", format!("{}s", self.crossfade)),
+            ("MixRamp threshold", format!("{:.1}dB", self.mixramp_db)),
+            (
+                "MixRamp delay",
+                self.mixramp_delay
+                    .map_or_else(|| "Off".to_owned(), |delay| format!("{delay:.1}s")),
+            ),
+            ("ReplayGain mode", self.replay_gain_mode.to_string()),
+        ]
+    }
+}
+
+impl Modal for PlaybackOptionsModal {
+    fn render(&mut self, frame: &mut ratatui::Frame, app: &mut AppContext) -> anyhow::Result<()> {
+        let popup_area = frame.area().centered_exact(40, 6);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = app.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(app.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Playback options");
+
+        let table_area = popup_area.inner(Margin {
+            horizontal: 2,
+            vertical: 1,
+        });
+        self.options_table_area = table_area;
+        self.scrolling_state.set_viewport_len(Some(table_area.height.into()));
+
+        let rows = self
+            .rows()
+            .into_iter()
+            .map(|(name, value)| Row::new([Cell::from(name), Cell::from(value)]));
+
+        let table = Table::new(rows, [Constraint::Percentage(100), Constraint::Length(10)])
+            .column_spacing(0)
+            .style(app.config.as_text_style())
+            .row_highlight_style(app.config.theme.current_item_style);
+
+        frame.render_widget(block, popup_area);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut KeyEvent, client: &mut Client<'_>, context: &mut AppContext) -> Result<()> {
+        if let Some(action) = key.as_common_action(context) {
+            match action {
+                CommonAction::Up => {
+                    self.scrolling_state
+                        .prev(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Down => {
+                    self.scrolling_state
+                        .next(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+
+                    context.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+
+                    context.render()?;
+                }
+                CommonAction::Right | CommonAction::Confirm => {
+                    self.change_selected_option(true, client)?;
+
+                    context.render()?;
+                }
+                CommonAction::Left => {
+                    self.change_selected_option(false, client)?;
+
+                    context.render()?;
+                }
+                CommonAction::Close => {
+                    pop_modal!(context);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        event: MouseEvent,
+        client: &mut Client<'_>,
+        context: &mut AppContext,
+    ) -> Result<()> {
+        match event.kind {
+            MouseEventKind::LeftClick if self.options_table_area.contains(event.into()) => {
+                let y: usize = event.y.saturating_sub(self.options_table_area.y).into();
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), context.config.scrolloff);
+                    context.render()?;
+                }
+            }
+            MouseEventKind::DoubleClick if self.options_table_area.contains(event.into()) => {
+                self.change_selected_option(true, client)?;
+                context.render()?;
+            }
+            MouseEventKind::RightClick if self.options_table_area.contains(event.into()) => {
+                self.change_selected_option(false, client)?;
+                context.render()?;
+            }
+            MouseEventKind::ScrollDown if self.options_table_area.contains(event.into()) => {
+                self.scrolling_state.next(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::ScrollUp if self.options_table_area.contains(event.into()) => {
+                self.scrolling_state.prev(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::MiddleClick => {}
+            MouseEventKind::LeftClick => {}
+            MouseEventKind::DoubleClick => {}
+            MouseEventKind::RightClick => {}
+            MouseEventKind::ScrollDown => {}
+            MouseEventKind::ScrollUp => {}
+        }
+
+        Ok(())
+    }
+}
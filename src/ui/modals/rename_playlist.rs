@@ -1,230 +1,35 @@
 use anyhow::Result;
-use crossterm::event::KeyCode;
-use ratatui::{
-    layout::Rect,
-    prelude::{Constraint, Layout},
-    style::{Style, Stylize},
-    symbols::{self, border},
-    widgets::{Block, Borders, Clear},
-    Frame,
-};
 
 use crate::{
-    config::keys::CommonAction,
     context::AppContext,
     mpd::{client::Client, mpd_client::MpdClient},
-    shared::{
-        key_event::KeyEvent,
-        macros::{pop_modal, status_info},
-        mouse_event::{MouseEvent, MouseEventKind},
-    },
-    ui::widgets::{
-        button::{Button, ButtonGroup, ButtonGroupState},
-        input::Input,
-    },
+    shared::macros::status_info,
 };
 
-use super::RectExt;
-
-use super::Modal;
-
-const BUTTON_GROUP_SYMBOLS: symbols::border::Set = symbols::border::Set {
-    top_right: symbols::line::NORMAL.vertical_left,
-    top_left: symbols::line::NORMAL.vertical_right,
-    ..symbols::border::ROUNDED
-};
+use super::input_modal::{InputAction, InputModal};
 
 #[derive(Debug)]
-pub struct RenamePlaylistModal<'a> {
-    button_group_state: ButtonGroupState,
-    button_group: ButtonGroup<'a>,
-    input_focused: bool,
-    input_area: Rect,
+pub struct RenamePlaylist {
     playlist_name: String,
-    new_name: String,
 }
 
-impl RenamePlaylistModal<'_> {
-    pub fn new(playlist_name: String, context: &AppContext) -> Self {
-        let mut button_group_state = ButtonGroupState::default();
-        let buttons = vec![Button::default().label("Save"), Button::default().label("Cancel")];
-        button_group_state.set_button_count(buttons.len());
-
-        let button_group = ButtonGroup::default()
-            .buttons(buttons)
-            .inactive_style(context.config.as_text_style())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_set(BUTTON_GROUP_SYMBOLS)
-                    .border_style(context.config.as_border_style()),
-            );
-
-        Self {
-            new_name: playlist_name.clone(),
-            playlist_name,
-            button_group_state,
-            button_group,
-            input_focused: true,
-            input_area: Rect::default(),
-        }
+impl RenamePlaylist {
+    pub fn modal<'a>(playlist_name: String, context: &AppContext) -> InputModal<'a, Self> {
+        InputModal::new(
+            "Rename playlist",
+            "New name:",
+            playlist_name.clone(),
+            Self { playlist_name },
+            context,
+        )
     }
 }
 
-impl Modal for RenamePlaylistModal<'_> {
-    fn render(&mut self, frame: &mut Frame, app: &mut AppContext) -> Result<()> {
-        let block = Block::default()
-            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-            .border_set(border::ROUNDED)
-            .border_style(app.config.as_border_style())
-            .title_alignment(ratatui::prelude::Alignment::Center)
-            .title("Rename playlist");
-
-        let popup_area = frame.area().centered_exact(50, 7);
-        frame.render_widget(Clear, popup_area);
-        if let Some(bg_color) = app.config.theme.modal_background_color {
-            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
-        }
-        let [body_area, buttons_area] =
-            *Layout::vertical([Constraint::Length(4), Constraint::Max(3)]).split(popup_area)
-        else {
-            return Ok(());
-        };
-
-        let input = Input::default()
-            .set_label("New name:")
-            .set_label_style(app.config.as_text_style())
-            .set_text(&self.new_name)
-            .set_focused(self.input_focused)
-            .set_focused_style(app.config.theme.highlight_border_style)
-            .set_unfocused_style(app.config.as_border_style());
-
-        self.button_group.set_active_style(if self.input_focused {
-            Style::default().reversed()
-        } else {
-            app.config.theme.current_item_style
-        });
-
-        self.input_area = body_area;
-
-        frame.render_widget(input, block.inner(body_area));
-        frame.render_widget(block, body_area);
-        frame.render_stateful_widget(&mut self.button_group, buttons_area, &mut self.button_group_state);
-        Ok(())
-    }
-
-    fn handle_key(&mut self, key: &mut KeyEvent, client: &mut Client<'_>, context: &mut AppContext) -> Result<()> {
-        let action = key.as_common_action(context);
-        if self.input_focused {
-            if let Some(CommonAction::Close) = action {
-                self.input_focused = false;
-
-                context.render()?;
-                return Ok(());
-            } else if let Some(CommonAction::Confirm) = action {
-                if self.button_group_state.selected == 0 && self.playlist_name != self.new_name {
-                    client.rename_playlist(&self.playlist_name, &self.new_name)?;
-                    status_info!("Playlist '{}' renamed to '{}'", self.playlist_name, self.new_name);
-                }
-                pop_modal!(context);
-                return Ok(());
-            }
-
-            match key.code() {
-                KeyCode::Char(c) => {
-                    self.new_name.push(c);
-
-                    context.render()?;
-                }
-                KeyCode::Backspace => {
-                    self.new_name.pop();
-
-                    context.render()?;
-                }
-                _ => {}
-            }
-        } else if let Some(action) = action {
-            match action {
-                CommonAction::Down => {
-                    self.button_group_state.next();
-
-                    context.render()?;
-                }
-                CommonAction::Up => {
-                    self.button_group_state.next();
-
-                    context.render()?;
-                }
-                CommonAction::Close => {
-                    pop_modal!(context);
-                }
-                CommonAction::Confirm => {
-                    if self.button_group_state.selected == 0 && self.playlist_name != self.new_name {
-                        client.rename_playlist(&self.playlist_name, &self.new_name)?;
-                        status_info!("Playlist '{}' renamed to '{}'", self.playlist_name, self.new_name);
-                    }
-                    pop_modal!(context);
-                }
-                CommonAction::FocusInput => {
-                    self.input_focused = true;
-
-                    context.render()?;
-                }
-                _ => {}
-            }
-        };
-
-        Ok(())
-    }
-
-    fn handle_mouse_event(
-        &mut self,
-        event: MouseEvent,
-        client: &mut Client<'_>,
-        context: &mut AppContext,
-    ) -> Result<()> {
-        match event.kind {
-            MouseEventKind::LeftClick => {
-                if let Some(idx) = self.button_group.get_button_idx_at(event.into()) {
-                    self.button_group_state.select(idx);
-                    self.input_focused = false;
-                    context.render()?;
-                }
-            }
-            MouseEventKind::DoubleClick => {
-                match self.button_group.get_button_idx_at(event.into()) {
-                    Some(0) if self.playlist_name != self.new_name => {
-                        client.rename_playlist(&self.playlist_name, &self.new_name)?;
-                        status_info!("Playlist '{}' renamed to '{}'", self.playlist_name, self.new_name);
-                        pop_modal!(context);
-                    }
-                    Some(_) => {
-                        pop_modal!(context);
-                    }
-                    None => {
-                        if self.input_area.contains(event.into()) {
-                            self.input_focused = true;
-                            context.render()?;
-                        }
-                    }
-                };
-            }
-            MouseEventKind::MiddleClick => {}
-            MouseEventKind::RightClick => {}
-            MouseEventKind::ScrollUp => {
-                if self.button_group.get_button_idx_at(event.into()).is_some() {
-                    self.input_focused = false;
-                    self.button_group_state.prev();
-                    context.render()?;
-                }
-            }
-            MouseEventKind::ScrollDown => {
-                if self.button_group.get_button_idx_at(event.into()).is_some() {
-                    self.input_focused = false;
-                    self.button_group_state.next();
-                    context.render()?;
-                }
-            }
+impl InputAction for RenamePlaylist {
+    fn confirm(&self, new_name: &str, client: &mut Client<'_>) -> Result<()> {
+        if self.playlist_name != new_name {
+            client.rename_playlist(&self.playlist_name, new_name)?;
+            status_info!("Playlist '{}' renamed to '{}'", self.playlist_name, new_name);
         }
         Ok(())
     }
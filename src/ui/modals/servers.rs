@@ -0,0 +1,218 @@
+use anyhow::Result;
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+
+use crate::{
+    config::{keys::CommonAction, ServerProfile},
+    context::AppContext,
+    mpd::client::Client,
+    shared::{
+        key_event::KeyEvent,
+        macros::pop_modal,
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::dirstack::DirState,
+    AppEvent,
+};
+
+use super::{Modal, RectExt};
+
+#[derive(Debug)]
+pub struct ServersModal {
+    scrolling_state: DirState<TableState>,
+    servers_table_area: Rect,
+    servers: &'static [ServerProfile],
+}
+
+impl ServersModal {
+    pub fn new(context: &AppContext) -> Self {
+        let mut result = Self {
+            scrolling_state: DirState::default(),
+            servers_table_area: Rect::default(),
+            servers: &context.config.servers,
+        };
+        result.scrolling_state.set_content_len(Some(result.servers.len()));
+        match result
+            .servers
+            .iter()
+            .position(|s| s.name == context.config.server_name())
+        {
+            Some(idx) => result.scrolling_state.select(Some(idx), 0),
+            None => result.scrolling_state.first(),
+        }
+
+        result
+    }
+
+    fn switch_to_selected_server(&mut self, context: &AppContext) -> Result<()> {
+        let Some(server) = self
+            .scrolling_state
+            .get_selected()
+            .and_then(|idx| self.servers.get(idx))
+        else {
+            return Ok(());
+        };
+
+        if server.name != context.config.server_name() {
+            context
+                .app_event_sender
+                .send(AppEvent::SwitchServer(server.name.to_owned()))?;
+        }
+        pop_modal!(context);
+
+        Ok(())
+    }
+}
+
+impl Modal for ServersModal {
+    fn render(&mut self, frame: &mut ratatui::Frame, app: &mut AppContext) -> anyhow::Result<()> {
+        let popup_area = frame.area().centered_exact(70, 10);
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = app.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(app.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Servers");
+
+        let table_area = popup_area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+
+        let active = app.config.server_name();
+        let rows = self.servers.iter().map(|server| {
+            Row::new([
+                Cell::from(server.name),
+                Cell::from(server.address.as_str()),
+                Cell::from(if server.name == active { "yes" } else { "" }),
+            ])
+        });
+
+        self.scrolling_state.set_viewport_len(Some(table_area.height.into()));
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(60),
+                Constraint::Length(10),
+            ],
+        )
+        .column_spacing(0)
+        .style(app.config.as_text_style())
+        .header(Row::new(["Name", "Address", "Active"]))
+        .row_highlight_style(app.config.theme.current_item_style);
+
+        let table_area = table_area.inner(Margin {
+            horizontal: 1,
+            vertical: 0,
+        });
+        self.servers_table_area = table_area;
+
+        frame.render_widget(block, popup_area);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+        frame.render_stateful_widget(
+            app.config.as_styled_scrollbar(),
+            popup_area.inner(Margin {
+                horizontal: 0,
+                vertical: 1,
+            }),
+            self.scrolling_state.as_scrollbar_state_ref(),
+        );
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut KeyEvent, _client: &mut Client<'_>, context: &mut AppContext) -> Result<()> {
+        if let Some(action) = key.as_common_action(context) {
+            match action {
+                CommonAction::DownHalf => {
+                    self.scrolling_state.next_half_viewport(context.config.scrolloff);
+
+                    context.render()?;
+                }
+                CommonAction::UpHalf => {
+                    self.scrolling_state.prev_half_viewport(context.config.scrolloff);
+
+                    context.render()?;
+                }
+                CommonAction::Up => {
+                    self.scrolling_state
+                        .prev(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Down => {
+                    self.scrolling_state
+                        .next(context.config.scrolloff, context.config.wrap_navigation);
+
+                    context.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+
+                    context.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+
+                    context.render()?;
+                }
+                CommonAction::Confirm => {
+                    self.switch_to_selected_server(context)?;
+                }
+                CommonAction::Close => {
+                    pop_modal!(context);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        event: MouseEvent,
+        _client: &mut Client<'_>,
+        context: &mut AppContext,
+    ) -> Result<()> {
+        match event.kind {
+            MouseEventKind::LeftClick if self.servers_table_area.contains(event.into()) => {
+                let y: usize = event.y.saturating_sub(self.servers_table_area.y).into();
+                let y = y.saturating_sub(1); // Subtract one to account for table header
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), context.config.scrolloff);
+                    context.render()?;
+                }
+            }
+            MouseEventKind::DoubleClick if self.servers_table_area.contains(event.into()) => {
+                self.switch_to_selected_server(context)?;
+            }
+            MouseEventKind::MiddleClick => {}
+            MouseEventKind::RightClick => {}
+            MouseEventKind::ScrollDown if self.servers_table_area.contains(event.into()) => {
+                self.scrolling_state.next(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::ScrollUp if self.servers_table_area.contains(event.into()) => {
+                self.scrolling_state.prev(context.config.scrolloff, false);
+                context.render()?;
+            }
+            MouseEventKind::LeftClick => {}
+            MouseEventKind::DoubleClick => {}
+            MouseEventKind::ScrollDown => {}
+            MouseEventKind::ScrollUp => {}
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    config::ImageMethod,
     context::AppContext,
     mpd::mpd_client::MpdClient,
     shared::{image::ImageProtocol, key_event::KeyEvent, macros::try_skip},
@@ -13,7 +14,10 @@ use super::Pane;
 #[derive(Debug)]
 pub struct AlbumArtPane {
     album_art: AlbumArtFacade,
-    image_data: Option<Vec<u8>>,
+    method: ImageMethod,
+    /// File whose album art is displayed or being loaded
+    current_file: Option<String>,
+    is_loading: bool,
 }
 
 impl AlbumArtPane {
@@ -21,7 +25,9 @@ impl AlbumArtPane {
         let sender = context.app_event_sender.clone();
         let config = context.config;
         Self {
-            image_data: None,
+            current_file: None,
+            is_loading: false,
+            method: config.album_art.method,
             album_art: AlbumArtFacade::new(
                 config.album_art.method.into(),
                 config.theme.default_album_art,
@@ -36,42 +42,60 @@ impl AlbumArtPane {
         }
     }
 
-    fn fetch_album_art(client: &mut impl MpdClient, context: &AppContext) -> Result<Option<Vec<u8>>> {
-        if matches!(context.config.album_art.method.into(), ImageProtocol::None) {
-            return Ok(None);
-        };
+    /// Album art method the image backend was created for
+    pub fn method(&self) -> ImageMethod {
+        self.method
+    }
 
-        let Some(current_song) = context.queue.iter().find(|v| Some(v.id) == context.status.songid) else {
-            return Ok(None);
+    pub fn cleanup(&mut self) -> Result<()> {
+        self.album_art.cleanup()
+    }
+
+    /// Album art is fetched by the album art worker and arrives later as [`UiEvent::AlbumArt`].
+    /// Placeholder is shown until then.
+    fn request_album_art(&mut self, context: &AppContext) -> Result<()> {
+        if matches!(context.config.album_art.method.into(), ImageProtocol::None) {
+            return Ok(());
         };
 
         let disabled_protos = &context.config.album_art.disabled_protocols;
-        let song_uri = current_song.file.as_str();
-        if disabled_protos.iter().any(|proto| song_uri.starts_with(proto)) {
-            log::debug!(uri = song_uri; "Not downloading album art because the protocol is disabled");
-            return Ok(None);
+        let file = context
+            .queue
+            .iter()
+            .find(|v| Some(v.id) == context.status.songid)
+            .map(|song| song.file.as_str())
+            .filter(|song_uri| {
+                let is_disabled = disabled_protos.iter().any(|proto| song_uri.starts_with(proto));
+                if is_disabled {
+                    log::debug!(uri = song_uri; "Not downloading album art because the protocol is disabled");
+                }
+                !is_disabled
+            });
+
+        if file == self.current_file.as_deref() {
+            return Ok(());
         }
+        self.current_file = file.map(ToOwned::to_owned);
+        self.album_art.set_image(None)?;
 
-        let start = std::time::Instant::now();
-        log::debug!(file = song_uri; "Searching for album art");
-        let result = client.find_album_art(song_uri)?;
-        log::debug!(elapsed:? = start.elapsed(), size = result.as_ref().map(|v|v.len()); "Found album art");
+        if let Some(file) = file {
+            context
+                .album_art
+                .request(file.to_owned(), context.config.address, context.config.password)?;
+            self.is_loading = true;
+        } else {
+            context.album_art.cancel();
+            self.is_loading = false;
+        }
 
-        Ok(result)
+        Ok(())
     }
 }
 
 impl Pane for AlbumArtPane {
     fn render(&mut self, frame: &mut Frame, area: Rect, context: &AppContext) -> Result<()> {
-        if let Some(data) = self.image_data.take() {
-            self.album_art.set_size(area);
-            self.album_art.set_image(Some(data))?;
-            self.album_art.show();
-            self.album_art.render(frame, context.config)?;
-        } else {
-            self.album_art.set_size(area);
-            self.album_art.render(frame, context.config)?;
-        }
+        self.album_art.set_size(area);
+        self.album_art.render(frame, context.config)?;
         Ok(())
     }
 
@@ -94,16 +118,25 @@ impl Pane for AlbumArtPane {
         Ok(())
     }
 
-    fn before_show(&mut self, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
-        self.image_data = AlbumArtPane::fetch_album_art(client, context)?;
+    fn before_show(&mut self, _client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
+        // Events are not delivered while hidden so the pending album art might have been missed
+        if self.is_loading {
+            self.current_file = None;
+        }
+        self.request_album_art(context)?;
+        self.album_art.show();
         Ok(())
     }
 
-    fn on_event(&mut self, event: &mut UiEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
+    fn on_event(&mut self, event: &mut UiEvent, _client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         match event {
             UiEvent::Player => {
-                self.album_art
-                    .set_image(AlbumArtPane::fetch_album_art(client, context)?)?;
+                self.request_album_art(context)?;
+                context.render()?;
+            }
+            UiEvent::AlbumArt(data) => {
+                self.is_loading = false;
+                self.album_art.set_image(data.take())?;
                 context.render()?;
             }
             UiEvent::Resized { columns, rows } => {
@@ -121,6 +154,18 @@ impl Pane for AlbumArtPane {
 
                 context.render()?;
             }
+            UiEvent::Disconnected => {
+                self.album_art.hide(context.config.theme.background_color)?;
+
+                context.render()?;
+            }
+            UiEvent::Reconnected => {
+                // Album art is requested again in before_show which follows
+                self.current_file = None;
+                self.album_art.show();
+
+                context.render()?;
+            }
             UiEvent::Exit => {
                 self.album_art.cleanup()?;
             }
@@ -134,11 +179,15 @@ impl Pane for AlbumArtPane {
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
+    use std::sync::mpsc::channel;
+
+    use itertools::Itertools;
     use rstest::rstest;
 
     use crate::config::Config;
     use crate::config::Leak;
     use crate::mpd::commands::Song;
+    use crate::shared::album_art::AlbumArtLoader;
     use crate::tests::fixtures::app_context;
     use crate::tests::fixtures::mpd_client::client;
     use crate::tests::fixtures::mpd_client::TestMpdClient;
@@ -156,9 +205,9 @@ mod tests {
     #[case(ImageMethod::Sixel, true)]
     #[case(ImageMethod::Unsupported, false)]
     #[case(ImageMethod::None, false)]
-    fn searches_for_album_art_before_show(
+    fn requests_album_art_before_show(
         #[case] method: ImageMethod,
-        #[case] should_search: bool,
+        #[case] should_request: bool,
         mut app_context: AppContext,
         mut client: TestMpdClient,
     ) {
@@ -171,14 +220,13 @@ mod tests {
             ..Default::default()
         });
         app_context.status.songid = Some(selected_song_id);
+        let (tx, rx) = channel();
+        app_context.album_art = AlbumArtLoader::new(tx);
         let mut screen = AlbumArtPane::new(&app_context);
 
         screen.before_show(&mut client, &app_context).unwrap();
 
-        assert_eq!(
-            client.calls.get("find_album_art").map_or(0, |v| *v),
-            u32::from(should_search)
-        );
+        assert_eq!(rx.try_iter().count(), usize::from(should_request));
     }
 
     #[rstest]
@@ -189,9 +237,9 @@ mod tests {
     #[case(ImageMethod::Sixel, true)]
     #[case(ImageMethod::Unsupported, false)]
     #[case(ImageMethod::None, false)]
-    fn searches_for_album_art_on_event(
+    fn requests_album_art_on_event(
         #[case] method: ImageMethod,
-        #[case] should_search: bool,
+        #[case] should_request: bool,
         mut app_context: AppContext,
         mut client: TestMpdClient,
     ) {
@@ -204,15 +252,54 @@ mod tests {
             ..Default::default()
         });
         app_context.status.songid = Some(selected_song_id);
+        let (tx, rx) = channel();
+        app_context.album_art = AlbumArtLoader::new(tx);
+        let mut screen = AlbumArtPane::new(&app_context);
+
+        screen
+            .on_event(&mut UiEvent::Player, &mut client, &app_context)
+            .unwrap();
+
+        assert_eq!(rx.try_iter().count(), usize::from(should_request));
+    }
+
+    #[rstest]
+    fn requests_album_art_only_when_song_changes(mut app_context: AppContext, mut client: TestMpdClient) {
+        let mut config = Config::default();
+        config.album_art.method = ImageMethod::Kitty;
+        app_context.config = config.leak();
+        app_context.queue.push(Song {
+            id: 1,
+            file: "first".to_owned(),
+            ..Default::default()
+        });
+        app_context.queue.push(Song {
+            id: 2,
+            file: "second".to_owned(),
+            ..Default::default()
+        });
+        app_context.status.songid = Some(1);
+        let (tx, rx) = channel();
+        app_context.album_art = AlbumArtLoader::new(tx);
         let mut screen = AlbumArtPane::new(&app_context);
 
+        screen
+            .on_event(&mut UiEvent::Player, &mut client, &app_context)
+            .unwrap();
+        screen
+            .on_event(&mut UiEvent::Player, &mut client, &app_context)
+            .unwrap();
+        app_context.status.songid = Some(2);
         screen
             .on_event(&mut UiEvent::Player, &mut client, &app_context)
             .unwrap();
 
+        let requests = rx.try_iter().collect_vec();
         assert_eq!(
-            client.calls.get("find_album_art").map_or(0, |v| *v),
-            u32::from(should_search)
+            requests.iter().map(|r| r.file.as_str()).collect_vec(),
+            vec!["first", "second"]
         );
+        assert!(!app_context.album_art.is_current(requests[0].generation));
+        assert!(app_context.album_art.is_current(requests[1].generation));
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    config::Config,
+    config::keys::AlbumsActions,
     context::AppContext,
     mpd::{
         commands::Song as MpdSong,
@@ -16,7 +16,10 @@ use crate::{
     },
 };
 
-use super::{browser::DirOrSong, Pane};
+use super::{
+    browser::{fetch_ratings, DirOrSong},
+    Pane,
+};
 use anyhow::{anyhow, Context, Result};
 use itertools::Itertools;
 use ratatui::{
@@ -57,8 +60,9 @@ impl AlbumsPane {
                 }
             }
             [] => {
-                let res = list_titles(client, current.as_path())?;
-                self.stack.push(res.collect());
+                let mut res = list_titles(client, current.as_path())?.collect_vec();
+                fetch_ratings(&mut res, client, context);
+                self.stack.push(res);
                 context.render()?;
             }
             _ => {
@@ -93,7 +97,7 @@ impl Pane for AlbumsPane {
                     .collect::<Vec<_>>(),
             );
             let preview = self
-                .prepare_preview(client, context.config)
+                .prepare_preview(client, context)
                 .context("Cannot prepare preview")?;
             self.stack.set_preview(preview);
             self.initialized = true;
@@ -115,12 +119,16 @@ impl Pane for AlbumsPane {
                     .collect::<Vec<_>>(),
             );
             let preview = self
-                .prepare_preview(client, context.config)
+                .prepare_preview(client, context)
                 .context("Cannot prepare preview")?;
             self.stack.set_preview(preview);
 
             context.render()?;
         };
+        if let crate::ui::UiEvent::Sticker = event {
+            fetch_ratings(&mut self.stack.current_mut().items, client, context);
+            context.render()?;
+        }
         Ok(())
     }
 
@@ -135,6 +143,11 @@ impl Pane for AlbumsPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         self.handle_filter_input(event, client, context)?;
+        if let Some(action) = event.as_albums_action(context) {
+            match action {
+                AlbumsActions::AddShuffled => self.add_shuffled(client, context)?,
+            }
+        }
         self.handle_common_action(event, client, context)?;
         self.handle_global_action(event, client, context)?;
         Ok(())
@@ -235,25 +248,28 @@ impl BrowserPane<DirOrSong> for AlbumsPane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>> {
+        let config = context.config;
         self.stack()
             .current()
             .selected()
             .map(DirStackItem::as_path)
             .map_or(Ok(None), |current| -> Result<_> {
                 Ok(match self.stack.path() {
-                    [album] => Some(
-                        find_songs(client, album, current)?
-                            .first()
-                            .context(anyhow!(
-                                "Expected to find exactly one song: album: '{}', current: '{}'",
-                                album,
-                                current
-                            ))?
-                            .to_preview(&config.theme.symbols)
-                            .collect_vec(),
-                    ),
+                    [album] => {
+                        let song = find_songs(client, album, current)?.into_iter().next().context(anyhow!(
+                            "Expected to find exactly one song: album: '{}', current: '{}'",
+                            album,
+                            current
+                        ))?;
+                        Some(
+                            context
+                                .with_all_tags(song, client)
+                                .to_preview(&config.theme.symbols)
+                                .collect_vec(),
+                        )
+                    }
                     [] => Some(
                         list_titles(client, current)?
                             .map(|v| v.to_list_item_simple(config))
@@ -1,5 +1,5 @@
 use crate::{
-    config::Config,
+    config::keys::ArtistsActions,
     context::AppContext,
     mpd::{
         commands::Song,
@@ -15,7 +15,10 @@ use crate::{
     },
 };
 
-use super::{browser::DirOrSong, Pane};
+use super::{
+    browser::{fetch_ratings, DirOrSong},
+    Pane,
+};
 use anyhow::{anyhow, Context, Result};
 use itertools::Itertools;
 use ratatui::{
@@ -117,8 +120,9 @@ impl ArtistsPane {
                 }
             }
             [artist] => {
-                self.stack
-                    .push(self.list_titles(client, artist, current.as_path())?.collect());
+                let mut res = self.list_titles(client, artist, current.as_path())?.collect_vec();
+                fetch_ratings(&mut res, client, context);
+                self.stack.push(res);
 
                 context.render()?;
             }
@@ -160,7 +164,7 @@ impl Pane for ArtistsPane {
                     .collect::<Vec<_>>(),
             );
             let preview = self
-                .prepare_preview(client, context.config)
+                .prepare_preview(client, context)
                 .context("Cannot prepare preview")?;
             self.stack.set_preview(preview);
             self.initialized = true;
@@ -184,12 +188,16 @@ impl Pane for ArtistsPane {
                     .collect::<Vec<_>>(),
             );
             let preview = self
-                .prepare_preview(client, context.config)
+                .prepare_preview(client, context)
                 .context("Cannot prepare preview")?;
             self.stack.set_preview(preview);
 
             context.render()?;
         };
+        if let crate::ui::UiEvent::Sticker = event {
+            fetch_ratings(&mut self.stack.current_mut().items, client, context);
+            context.render()?;
+        }
         Ok(())
     }
 
@@ -204,6 +212,11 @@ impl Pane for ArtistsPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         self.handle_filter_input(event, client, context)?;
+        if let Some(action) = event.as_artists_action(context) {
+            match action {
+                ArtistsActions::AddShuffled => self.add_shuffled(client, context)?,
+            }
+        }
         self.handle_common_action(event, client, context)?;
         self.handle_global_action(event, client, context)?;
         Ok(())
@@ -311,26 +324,33 @@ impl BrowserPane<DirOrSong> for ArtistsPane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>> {
+        let config = context.config;
         self.stack
             .current()
             .selected()
             .map(DirStackItem::as_path)
             .map_or(Ok(None), |current| -> Result<_> {
                 Ok(match self.stack.path() {
-                    [artist, album] => Some(
-                        self.find_songs(client, artist, album, current)?
-                            .first()
+                    [artist, album] => {
+                        let song = self
+                            .find_songs(client, artist, album, current)?
+                            .into_iter()
+                            .next()
                             .context(anyhow!(
                                 "Expected to find exactly one song: artist: '{}', album: '{}', current: '{}'",
                                 artist,
                                 album,
                                 current
-                            ))?
-                            .to_preview(&config.theme.symbols)
-                            .collect_vec(),
-                    ),
+                            ))?;
+                        Some(
+                            context
+                                .with_all_tags(song, client)
+                                .to_preview(&config.theme.symbols)
+                                .collect_vec(),
+                        )
+                    }
                     [artist] => Some(
                         self.list_titles(client, artist, current)?
                             .map(|s| s.to_list_item_simple(config))
@@ -7,11 +7,11 @@ use ratatui::{
 };
 
 use crate::{
-    config::Config,
+    config::keys::DirectoriesActions,
     context::AppContext,
     mpd::{
         commands::{lsinfo::FileOrDir, Song},
-        mpd_client::{Filter, FilterKind, MpdClient, Tag},
+        mpd_client::{Filter, MpdClient, Tag},
     },
     shared::{ext::mpd_client::MpdClientExt, key_event::KeyEvent, macros::status_info, mouse_event::MouseEvent},
     ui::{
@@ -22,7 +22,10 @@ use crate::{
     },
 };
 
-use super::{browser::DirOrSong, Pane};
+use super::{
+    browser::{fetch_ratings, DirOrSong},
+    Pane,
+};
 
 #[derive(Debug)]
 pub struct DirectoriesPane {
@@ -42,6 +45,14 @@ impl DirectoriesPane {
         }
     }
 
+    /// Path of the selected directory or of the current one when a song is selected
+    fn selected_dir_path(&self) -> String {
+        match (self.stack.current().selected(), self.stack.next_path()) {
+            (Some(DirOrSong::Dir { .. }), Some(next_path)) => next_path.join("/"),
+            _ => self.stack.path().join("/"),
+        }
+    }
+
     fn open_or_play(&mut self, autoplay: bool, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         let Some(selected) = self.stack.current().selected() else {
             log::error!("Failed to move deeper inside dir. Current value is None");
@@ -55,7 +66,7 @@ impl DirectoriesPane {
         match selected {
             DirOrSong::Dir { .. } => {
                 let new_current = client.lsinfo(Some(next_path.join("/").to_string().as_str()))?;
-                let res = new_current
+                let mut res: Vec<_> = new_current
                     .into_iter()
                     .map(|v| match v {
                         FileOrDir::Dir(d) => DirOrSong::Dir {
@@ -66,6 +77,7 @@ impl DirectoriesPane {
                     })
                     .sorted()
                     .collect();
+                fetch_ratings(&mut res, client, context);
                 self.stack.push(res);
 
                 context.render()?;
@@ -93,15 +105,15 @@ impl Pane for DirectoriesPane {
 
     fn before_show(&mut self, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         if !self.initialized {
-            self.stack = DirStack::new(
-                client
-                    .lsinfo(None)?
-                    .into_iter()
-                    .map(Into::<DirOrSong>::into)
-                    .sorted()
-                    .collect::<Vec<_>>(),
-            );
-            let preview = self.prepare_preview(client, context.config)?;
+            let mut root = client
+                .lsinfo(None)?
+                .into_iter()
+                .map(Into::<DirOrSong>::into)
+                .sorted()
+                .collect::<Vec<_>>();
+            fetch_ratings(&mut root, client, context);
+            self.stack = DirStack::new(root);
+            let preview = self.prepare_preview(client, context)?;
             self.stack.set_preview(preview);
             self.initialized = true;
         }
@@ -111,18 +123,22 @@ impl Pane for DirectoriesPane {
 
     fn on_event(&mut self, event: &mut UiEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         if let crate::ui::UiEvent::Database = event {
-            self.stack = DirStack::new(
-                client
-                    .lsinfo(None)?
-                    .into_iter()
-                    .map(Into::<DirOrSong>::into)
-                    .collect::<Vec<_>>(),
-            );
-            let preview = self.prepare_preview(client, context.config)?;
+            let mut root = client
+                .lsinfo(None)?
+                .into_iter()
+                .map(Into::<DirOrSong>::into)
+                .collect::<Vec<_>>();
+            fetch_ratings(&mut root, client, context);
+            self.stack = DirStack::new(root);
+            let preview = self.prepare_preview(client, context)?;
             self.stack.set_preview(preview);
 
             context.render()?;
         };
+        if let crate::ui::UiEvent::Sticker = event {
+            fetch_ratings(&mut self.stack.current_mut().items, client, context);
+            context.render()?;
+        }
         Ok(())
     }
 
@@ -137,6 +153,20 @@ impl Pane for DirectoriesPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         self.handle_filter_input(event, client, context)?;
+        if let Some(action) = event.as_directories_action(context) {
+            let path = self.selected_dir_path();
+            match action {
+                DirectoriesActions::Update => {
+                    client.update(Some(&path))?;
+                    status_info!("Updating '{path}'");
+                }
+                DirectoriesActions::Rescan => {
+                    client.rescan(Some(&path))?;
+                    status_info!("Rescanning '{path}'");
+                }
+            }
+            context.render()?;
+        }
         self.handle_common_action(event, client, context)?;
         self.handle_global_action(event, client, context)?;
         Ok(())
@@ -162,13 +192,26 @@ impl BrowserPane<DirOrSong> for DirectoriesPane {
 
     fn list_songs_in_item(&self, client: &mut impl MpdClient, item: &DirOrSong) -> Result<Vec<Song>> {
         Ok(match item {
-            DirOrSong::Dir { full_path, .. } => {
-                client.find(&[Filter::new_with_kind(Tag::File, full_path, FilterKind::StartsWith)])?
-            }
+            DirOrSong::Dir { full_path, .. } => client.find(&[Filter::new(Tag::Base, full_path)])?,
             DirOrSong::Song(song) => vec![song.clone()],
         })
     }
 
+    fn add_items(&self, items: &[&DirOrSong], client: &mut impl MpdClient, _context: &AppContext) -> Result<()> {
+        let paths = items
+            .iter()
+            .map(|item| match item {
+                DirOrSong::Dir { full_path, .. } => full_path.as_str(),
+                DirOrSong::Song(song) => song.file.as_str(),
+            })
+            .collect_vec();
+
+        client.add_multiple(&paths)?;
+        status_info!("Added {} items to queue", paths.len());
+
+        Ok(())
+    }
+
     fn add(&self, item: &DirOrSong, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         match item {
             DirOrSong::Dir {
@@ -216,8 +259,9 @@ impl BrowserPane<DirOrSong> for DirectoriesPane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>> {
+        let config = context.config;
         match &self.stack.current().selected() {
             Some(DirOrSong::Dir { .. }) => {
                 let Some(next_path) = self.stack.next_path() else {
@@ -245,9 +289,12 @@ impl BrowserPane<DirOrSong> for DirectoriesPane {
                 .collect();
                 Ok(Some(res))
             }
-            Some(DirOrSong::Song(song)) => Ok(client
-                .find_one(&[Filter::new(Tag::File, &song.file)])?
-                .map(|v| v.to_preview(&config.theme.symbols).collect())),
+            Some(DirOrSong::Song(song)) => Ok(client.find_one(&[Filter::new(Tag::File, &song.file)])?.map(|v| {
+                context
+                    .with_all_tags(v, client)
+                    .to_preview(&config.theme.symbols)
+                    .collect()
+            })),
             None => Ok(None),
         }
     }
@@ -255,3 +302,35 @@ impl BrowserPane<DirOrSong> for DirectoriesPane {
         self.browser.areas
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::{
+        mpd::{capabilities::Capabilities, client::Client},
+        tests::fixtures::{
+            app_context,
+            mpd_server::{FakeMpdServer, FakeSong},
+        },
+        ui::panes::Pane,
+    };
+
+    use super::DirectoriesPane;
+
+    #[test]
+    fn preview_shows_tags_outside_of_the_tag_limit() {
+        let server = FakeMpdServer::start().with_library([FakeSong::new("01.flac")
+            .with_tag("Title", "first")
+            .with_tag("Genre", "Rock")]);
+        let mut client = Client::init(server.address(), None, "test", true).unwrap();
+        let mut context = app_context();
+        context.capabilities = Capabilities::fetch(&mut client).unwrap();
+        context.capabilities.limit_tag_types(&mut client, ["Title"]).unwrap();
+        let mut pane = DirectoriesPane::new(&context);
+
+        pane.before_show(&mut client, &context).unwrap();
+
+        let preview = format!("{:?}", pane.stack.preview().unwrap());
+        assert!(preview.contains("Rock"), "{preview}");
+    }
+}
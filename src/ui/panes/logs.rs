@@ -197,6 +197,8 @@ impl Pane for LogsPane {
                 CommonAction::Confirm => {}
                 CommonAction::FocusInput => {}
                 CommonAction::AddAll => {}
+                CommonAction::InsertAfterCurrent => {}
+                CommonAction::AddWithPriority => {}
                 CommonAction::PaneDown => {}
                 CommonAction::PaneUp => {}
                 CommonAction::PaneRight => {}
@@ -27,10 +27,10 @@ use crate::{
     },
     context::AppContext,
     mpd::{
-        commands::{status::OnOffOneshot, volume::Bound, Song, Status},
+        commands::{status::OnOffOneshot, stickers::MAX_RATING, volume::Bound, Song, Status},
         mpd_client::MpdClient,
     },
-    shared::{ext::duration::DurationExt, key_event::KeyEvent, mouse_event::MouseEvent},
+    shared::{ext::duration::DurationExt, key_event::KeyEvent, macros::try_skip, mouse_event::MouseEvent},
 };
 
 use super::{widgets::volume::Volume, UiEvent};
@@ -89,6 +89,23 @@ impl PaneContainer {
         }
     }
 
+    /// Recreates all panes with the current config. Album art keeps its image backend because the
+    /// image protocol is only chosen on startup, it is only recreated when album art was disabled
+    /// or enabled again.
+    pub fn reload(&mut self, context: &AppContext) {
+        self.queue = QueuePane::new(context);
+        self.directories = DirectoriesPane::new(context);
+        self.albums = AlbumsPane::new(context);
+        self.artists = ArtistsPane::new(ArtistsPaneMode::Artist, context);
+        self.album_artists = ArtistsPane::new(ArtistsPaneMode::AlbumArtist, context);
+        self.playlists = PlaylistsPane::new(context);
+        self.search = SearchPane::new(context);
+        if self.album_art.method() != context.config.album_art.method {
+            try_skip!(self.album_art.cleanup(), "Failed to clean up album art");
+            self.album_art = AlbumArtPane::new(context);
+        }
+    }
+
     pub fn get_mut(&mut self, screen: PaneType) -> Panes {
         match screen {
             PaneType::Queue => Panes::Queue(&mut self.queue),
@@ -144,6 +161,8 @@ pub mod dirstack {}
 pub(crate) mod browser {
     use std::{borrow::Cow, cmp::Ordering};
 
+    use itertools::Itertools;
+
     use ratatui::{
         style::{Color, Style},
         text::{Line, Span},
@@ -152,9 +171,36 @@ pub(crate) mod browser {
 
     use crate::{
         config::theme::SymbolsConfig,
-        mpd::commands::{lsinfo::FileOrDir, Song},
+        context::AppContext,
+        mpd::{
+            capabilities::Feature,
+            commands::{lsinfo::FileOrDir, Song},
+            mpd_client::MpdClient,
+        },
+        shared::ext::mpd_client::MpdClientExt,
     };
 
+    /// Fills in ratings of the songs among the items, but only if the browser displays them
+    pub(crate) fn fetch_ratings(items: &mut [DirOrSong], client: &mut impl MpdClient, context: &AppContext) {
+        if !context.config.theme.browser_song_format.uses_rating() || !context.capabilities.supports(Feature::Stickers)
+        {
+            return;
+        }
+        let songs = items
+            .iter_mut()
+            .filter_map(|item| match item {
+                DirOrSong::Song(song) => Some(song),
+                DirOrSong::Dir { .. } => None,
+            })
+            .collect_vec();
+        if songs.is_empty() {
+            return;
+        }
+        if let Err(err) = client.fetch_song_ratings(songs) {
+            log::warn!(error:? = err; "Failed to fetch song ratings");
+        }
+    }
+
     impl Song {
         pub(crate) fn to_preview(&self, _symbols: &SymbolsConfig) -> impl Iterator<Item = ListItem<'static>> {
             let key_style = Style::default().fg(Color::Yellow);
@@ -487,6 +533,18 @@ impl Song {
                 .get("track")
                 .map(|v| Cow::Owned(v.parse::<u32>().map_or_else(|_| v.clone(), |v| format!("{v:0>2}")))),
             SongProperty::Duration => self.duration.map(|d| Cow::Owned(d.to_string())),
+            SongProperty::Rating => self.rating().map(|rating| {
+                let rating = rating.min(MAX_RATING);
+                let full = usize::from(rating / 2);
+                let half = usize::from(rating % 2);
+                Cow::Owned(format!(
+                    "{}{}{}",
+                    "★".repeat(full),
+                    "½".repeat(half),
+                    "☆".repeat(usize::from(MAX_RATING / 2) - full - half)
+                ))
+            }),
+            SongProperty::Priority => self.priority.map(|priority| Cow::Owned(priority.to_string())),
             SongProperty::Other(name) => self.metadata.get(*name).map(|v| Cow::Borrowed(v.as_str())),
         }
     }
@@ -585,14 +643,16 @@ impl Property<'static, PropertyKind> {
         &self,
         song: Option<&'song Song>,
         status: &'song Status,
+        server: &'song str,
     ) -> Option<Either<Span<'s>, Vec<Span<'s>>>> {
-        self.default.and_then(|p| p.as_span(song, status))
+        self.default.and_then(|p| p.as_span(song, status, server))
     }
 
     pub fn as_span<'song: 's, 's>(
         &'s self,
         song: Option<&'song Song>,
         status: &'song Status,
+        server: &'song str,
     ) -> Option<Either<Span<'s>, Vec<Span<'s>>>> {
         let style = self.style.unwrap_or_default();
         match &self.kind {
@@ -600,11 +660,11 @@ impl Property<'static, PropertyKind> {
             PropertyKindOrText::Property(PropertyKind::Song(property)) => {
                 if let Some(song) = song {
                     song.format(property).map_or_else(
-                        || self.default_as_span(Some(song), status),
+                        || self.default_as_span(Some(song), status, server),
                         |s| Some(Either::Left(Span::styled(s, style))),
                     )
                 } else {
-                    self.default_as_span(song, status)
+                    self.default_as_span(song, status, server)
                 }
             }
             PropertyKindOrText::Property(PropertyKind::Status(s)) => match s {
@@ -623,13 +683,15 @@ impl Property<'static, PropertyKind> {
                 StatusProperty::Consume => Some(Either::Left(Span::styled(status.consume.to_string(), style))),
                 StatusProperty::Single => Some(Either::Left(Span::styled(status.single.to_string(), style))),
                 StatusProperty::Bitrate => status.bitrate.as_ref().map_or_else(
-                    || self.default_as_span(song, status),
+                    || self.default_as_span(song, status, server),
                     |v| Some(Either::Left(Span::styled(v.to_string(), Style::default()))),
                 ),
                 StatusProperty::Crossfade => status.xfade.as_ref().map_or_else(
-                    || self.default_as_span(song, status),
+                    || self.default_as_span(song, status, server),
                     |v| Some(Either::Left(Span::styled(v.to_string(), Style::default()))),
                 ),
+                StatusProperty::Partition => Some(Either::Left(Span::styled(status.partition.clone(), style))),
+                StatusProperty::Server => Some(Either::Left(Span::styled(server, style))),
             },
             PropertyKindOrText::Property(PropertyKind::Widget(w)) => match w {
                 WidgetProperty::Volume => Some(Either::Left(Span::styled(
@@ -663,7 +725,7 @@ impl Property<'static, PropertyKind> {
             PropertyKindOrText::Group(group) => {
                 let mut buf = Vec::new();
                 for format in *group {
-                    match format.as_span(song, status) {
+                    match format.as_span(song, status, server) {
                         Some(Either::Left(span)) => buf.push(span),
                         Some(Either::Right(spans)) => buf.extend(spans),
                         None => return None,
@@ -747,6 +809,8 @@ mod format_tests {
         #[test_case(SongProperty::Track, "123")]
         #[test_case(SongProperty::Duration, "2:03")]
         #[test_case(SongProperty::Other("track"), "123")]
+        #[test_case(SongProperty::Rating, "★★★½☆"; "rating")]
+        #[test_case(SongProperty::Priority, "5")]
         fn song_property_resolves_correctly(prop: SongProperty, expected: &str) {
             let format = Property::<'static, SongProperty> {
                 kind: PropertyKindOrText::Property(prop),
@@ -764,6 +828,8 @@ mod format_tests {
                     ("track".to_string(), "123".to_string()),
                     ("artist".to_string(), "artist".to_string()),
                 ]),
+                stickers: Some(HashMap::from([("rating".to_string(), "7".to_string())])),
+                priority: Some(5),
             };
 
             let result = format.as_string(Some(&song));
@@ -780,6 +846,8 @@ mod format_tests {
         #[test_case(StatusProperty::Duration, "2:03")]
         #[test_case(StatusProperty::Crossfade, "3")]
         #[test_case(StatusProperty::Bitrate, "123")]
+        #[test_case(StatusProperty::Partition, "livingroom")]
+        #[test_case(StatusProperty::Server, "nas")]
         fn status_property_resolves_correctly(prop: StatusProperty, expected: &str) {
             let format = Property::<'static, PropertyKind> {
                 kind: PropertyKindOrText::Property(PropertyKind::Status(prop)),
@@ -797,6 +865,8 @@ mod format_tests {
                     ("title".to_string(), "title".to_owned()),
                     ("track".to_string(), "123".to_string()),
                 ]),
+                stickers: None,
+                priority: None,
             };
             let status = Status {
                 volume: Volume::new(123),
@@ -809,10 +879,11 @@ mod format_tests {
                 duration: Duration::from_secs(123),
                 xfade: Some(3),
                 state: State::Play,
+                partition: "livingroom".to_owned(),
                 ..Default::default()
             };
 
-            let result = format.as_span(Some(&song), &status);
+            let result = format.as_span(Some(&song), &status, "nas");
 
             assert_eq!(
                 result,
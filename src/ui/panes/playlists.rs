@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Context, Result};
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 use ratatui::{
     prelude::Rect,
     widgets::{ListItem, StatefulWidget},
@@ -7,7 +7,7 @@ use ratatui::{
 };
 
 use crate::{
-    config::Config,
+    config::keys::PlaylistsActions,
     context::AppContext,
     mpd::{
         commands::Song,
@@ -22,13 +22,19 @@ use crate::{
     ui::{
         browser::{BrowserPane, MoveDirection},
         dirstack::{DirStack, DirStackItem},
-        modals::{confirm_playlist_delete::ConfirmPlaylistDeleteModal, rename_playlist::RenamePlaylistModal},
+        modals::{
+            confirm_playlist_delete::ConfirmPlaylistDeleteModal, new_playlist::NewPlaylist,
+            rename_playlist::RenamePlaylist,
+        },
         widgets::browser::Browser,
         UiEvent,
     },
 };
 
-use super::{browser::DirOrSong, Pane};
+use super::{
+    browser::{fetch_ratings, DirOrSong},
+    Pane,
+};
 
 #[cfg(test)]
 mod tests;
@@ -61,8 +67,13 @@ impl PlaylistsPane {
 
         match selected {
             DirOrSong::Dir { name: playlist, .. } => {
-                let info = client.list_playlist_info(playlist, None)?;
-                self.stack_mut().push(info.into_iter().map(DirOrSong::Song).collect());
+                let mut info = client
+                    .list_playlist_info(playlist, None)?
+                    .into_iter()
+                    .map(DirOrSong::Song)
+                    .collect_vec();
+                fetch_ratings(&mut info, client, context);
+                self.stack_mut().push(info);
 
                 context.render()?;
             }
@@ -101,7 +112,7 @@ impl Pane for PlaylistsPane {
                 .collect();
             self.stack = DirStack::new(playlists);
             let preview = self
-                .prepare_preview(client, context.config)
+                .prepare_preview(client, context)
                 .context("Cannot prepare preview")?;
             self.stack.set_preview(preview);
             self.initialized = true;
@@ -124,7 +135,7 @@ impl Pane for PlaylistsPane {
                     .collect();
                 self.stack = DirStack::new(playlists);
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack.set_preview(preview);
                 context.render()?;
@@ -197,12 +208,16 @@ impl Pane for PlaylistsPane {
                 }
 
                 let preview = self
-                    .prepare_preview(client, context.config)
+                    .prepare_preview(client, context)
                     .context("Cannot prepare preview")?;
                 self.stack.set_preview(preview);
 
                 context.render()?;
             }
+            UiEvent::Sticker => {
+                fetch_ratings(&mut self.stack.current_mut().items, client, context);
+                context.render()?;
+            }
             _ => {}
         };
 
@@ -220,6 +235,22 @@ impl Pane for PlaylistsPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         self.handle_filter_input(event, client, context)?;
+        if let Some(action) = event.as_playlists_action(context) {
+            match action {
+                PlaylistsActions::NewPlaylist => {
+                    modal!(context, NewPlaylist::modal(None, context));
+                }
+                PlaylistsActions::Duplicate => {
+                    let source = match (self.stack.path(), self.stack.current().selected()) {
+                        ([playlist], _) | ([], Some(DirOrSong::Dir { name: playlist, .. })) => Some(playlist),
+                        _ => None,
+                    };
+                    if let Some(source) = source {
+                        modal!(context, NewPlaylist::modal(Some(source.clone()), context));
+                    }
+                }
+            }
+        }
         self.handle_common_action(event, client, context)?;
         self.handle_global_action(event, client, context)?;
         Ok(())
@@ -277,9 +308,17 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
                 context.render()?;
             }
             [] => {
-                for playlist in &self.stack().current().items {
-                    self.add(playlist, client, context)?;
-                }
+                let playlists = self
+                    .stack()
+                    .current()
+                    .items
+                    .iter()
+                    .filter_map(|item| match item {
+                        DirOrSong::Dir { name, .. } => Some(name.as_str()),
+                        DirOrSong::Song(_) => None,
+                    })
+                    .collect_vec();
+                client.load_playlists(&playlists)?;
                 status_info!("All playlists added to queue");
 
                 context.render()?;
@@ -290,6 +329,23 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
         Ok(())
     }
 
+    fn add_items(&self, items: &[&DirOrSong], client: &mut impl MpdClient, _context: &AppContext) -> Result<()> {
+        let (playlists, songs): (Vec<_>, Vec<_>) = items.iter().partition_map(|item| match item {
+            DirOrSong::Dir { name, .. } => Either::Left(name.as_str()),
+            DirOrSong::Song(song) => Either::Right(song.file.as_str()),
+        });
+
+        client.load_playlists(&playlists)?;
+        client.add_multiple(&songs)?;
+        if songs.is_empty() {
+            status_info!("Added {} playlists to queue", playlists.len());
+        } else {
+            status_info!("Added {} songs to queue", songs.len());
+        }
+
+        Ok(())
+    }
+
     fn add(&self, item: &DirOrSong, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         match item {
             DirOrSong::Dir { name: d, .. } => {
@@ -314,7 +370,7 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
     fn rename(&self, item: &DirOrSong, _client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         match item {
             DirOrSong::Dir { name: d, .. } => {
-                modal!(context, RenamePlaylistModal::new(d.clone(), context));
+                modal!(context, RenamePlaylist::modal(d.clone(), context));
             }
             DirOrSong::Song(_) => {}
         };
@@ -356,8 +412,9 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>> {
+        let config = context.config;
         self.stack()
             .current()
             .selected()
@@ -369,11 +426,15 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
                         .map(DirOrSong::Song)
                         .map(|s| s.to_list_item_simple(config))
                         .collect_vec(),
-                    DirOrSong::Song(song) => client
-                        .find_one(&[Filter::new(Tag::File, &song.file)])?
-                        .context(anyhow!("File '{}' was listed but not found", song.file))?
-                        .to_preview(&config.theme.symbols)
-                        .collect_vec(),
+                    DirOrSong::Song(song) => {
+                        let song = client
+                            .find_one(&[Filter::new(Tag::File, &song.file)])?
+                            .context(anyhow!("File '{}' was listed but not found", song.file))?;
+                        context
+                            .with_all_tags(song, client)
+                            .to_preview(&config.theme.symbols)
+                            .collect_vec()
+                    }
                 }))
             })
     }
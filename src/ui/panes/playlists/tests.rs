@@ -277,6 +277,27 @@ mod on_idle_event {
     }
 }
 
+mod add_all {
+    use super::*;
+
+    #[rstest]
+    fn adds_songs_from_all_playlists_in_order(
+        screen: PlaylistsPane,
+        mut client: TestMpdClient,
+        app_context: AppContext,
+    ) {
+        let expected = client
+            .playlists
+            .iter()
+            .flat_map(|playlist| playlist.songs_indices.clone())
+            .collect::<Vec<_>>();
+
+        screen.add_all(&mut client, &app_context).unwrap();
+
+        assert_eq!(client.queue, expected);
+    }
+}
+
 #[fixture]
 fn screen_in_playlist_0(mut client: TestMpdClient, app_context: AppContext) -> PlaylistsPane {
     let mut screen = PlaylistsPane::new(&app_context);
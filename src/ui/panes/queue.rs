@@ -17,6 +17,7 @@ use crate::{
         mpd_client::{MpdClient, QueueMoveTarget},
     },
     shared::{
+        ext::mpd_client::MpdClientExt,
         key_event::KeyEvent,
         macros::{modal, status_error, status_info, status_warn},
         mouse_event::{MouseEvent, MouseEventKind},
@@ -326,14 +327,7 @@ impl Pane for QueuePane {
                         client.play_id(selected_song.id)?;
                     }
                 }
-                QueueActions::JumpToCurrent => {
-                    if let Some((idx, _)) = context.find_current_song_in_queue() {
-                        self.scrolling_state.select(Some(idx), context.config.scrolloff);
-                        context.render()?;
-                    } else {
-                        status_info!("No song is currently playing");
-                    }
-                }
+                QueueActions::JumpToCurrent => self.jump_to_current(context)?,
                 QueueActions::Save => {
                     modal!(context, SaveQueueModal::new(context));
                 }
@@ -361,7 +355,8 @@ impl Pane for QueuePane {
                         .get_selected()
                         .and_then(|idx| context.queue.get(idx))
                     {
-                        modal!(context, SongInfoModal::new(selected_song.clone()));
+                        let song = context.with_all_tags(selected_song.clone(), client);
+                        modal!(context, SongInfoModal::new(song));
                     } else {
                         status_error!("No song selected");
                     }
@@ -475,6 +470,8 @@ impl Pane for QueuePane {
                 CommonAction::Select => {}
                 CommonAction::Add => {}
                 CommonAction::AddAll => {}
+                CommonAction::InsertAfterCurrent => {}
+                CommonAction::AddWithPriority => {}
                 CommonAction::Delete => {}
                 CommonAction::Rename => {}
                 CommonAction::Close => {}
@@ -495,6 +492,16 @@ impl Pane for QueuePane {
 
                     run_external(command, create_env(context, song, client)?);
                 }
+                GlobalAction::RateSelectedSong(rating) => {
+                    if let Some(song) = self
+                        .scrolling_state
+                        .get_selected()
+                        .and_then(|idx| context.queue.get(idx))
+                    {
+                        client.rate_song(&song.file, rating)?;
+                        status_info!("Rated '{}'", song.title().unwrap_or(&song.file));
+                    }
+                }
                 _ => {
                     event.abandon();
                 }
@@ -506,6 +513,17 @@ impl Pane for QueuePane {
 }
 
 impl QueuePane {
+    pub fn jump_to_current(&mut self, context: &AppContext) -> Result<()> {
+        if let Some((idx, _)) = context.find_current_song_in_queue() {
+            self.scrolling_state.select(Some(idx), context.config.scrolloff);
+            context.render()?;
+        } else {
+            status_info!("No song is currently playing");
+        }
+
+        Ok(())
+    }
+
     pub fn jump_forward(&mut self, queue: &[Song], scrolloff: usize) {
         let Some(filter) = self.filter.as_ref() else {
             status_warn!("No filter set");
@@ -16,6 +16,7 @@ use ratatui::{
 use crate::cli::create_env;
 use crate::cli::run_external;
 use crate::config::keys::GlobalAction;
+use crate::config::keys::SearchActions;
 use crate::config::Config;
 use crate::config::Search;
 use crate::context::AppContext;
@@ -78,13 +79,28 @@ impl SearchPane {
         }
     }
 
+    /// Files of the marked songs, or of the selected song when nothing is marked
+    fn marked_or_selected_songs(&self) -> Vec<&str> {
+        if self.songs_dir.marked().is_empty() {
+            self.songs_dir
+                .selected()
+                .map(|song| song.file.as_str())
+                .into_iter()
+                .collect()
+        } else {
+            self.songs_dir.marked_items().map(|song| song.file.as_str()).collect()
+        }
+    }
+
     fn add_current(&mut self, autoplay: bool, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         if !self.songs_dir.marked().is_empty() {
-            for idx in self.songs_dir.marked() {
-                let item = &self.songs_dir.items[*idx];
-                client.add(&item.file)?;
-            }
-            status_info!("Added {} songs to queue", self.songs_dir.marked().len());
+            let songs = self
+                .songs_dir
+                .marked_items()
+                .map(|song| song.file.as_str())
+                .collect_vec();
+            client.add_multiple(&songs)?;
+            status_info!("Added {} songs to queue", songs.len());
 
             context.render()?;
         } else if let Some(item) = self.songs_dir.selected() {
@@ -153,8 +169,9 @@ impl SearchPane {
     fn prepare_preview(
         &mut self,
         client: &mut impl MpdClient,
-        config: &Config,
+        context: &AppContext,
     ) -> Result<Option<Vec<ListItem<'static>>>> {
+        let config = context.config;
         match &self.phase {
             Phase::SearchTextboxInput => Ok(None),
             Phase::Search => Ok(Some(self.songs_dir.to_list_items(config))),
@@ -163,10 +180,13 @@ impl SearchPane {
                     return Ok(None);
                 };
 
-                let preview = client
+                let song = client
                     .find(&[Filter::new(Tag::File, &current.file)])?
-                    .first()
-                    .context("Expected to find exactly one song")?
+                    .into_iter()
+                    .next()
+                    .context("Expected to find exactly one song")?;
+                let preview = context
+                    .with_all_tags(song, client)
                     .to_preview(&config.theme.symbols)
                     .collect_vec();
                 Ok(Some(preview))
@@ -375,7 +395,7 @@ impl SearchPane {
                 // Reset is the only button in this group at the moment
                 self.reset(&context.config.search);
                 self.songs_dir = Dir::default();
-                self.preview = self.prepare_preview(client, context.config)?;
+                self.preview = self.prepare_preview(client, context)?;
             }
             FocusedInputGroup::Filters(FilterInput {
                 variant: FilterInputVariant::SelectFilterKind { ref mut value },
@@ -383,7 +403,7 @@ impl SearchPane {
             }) => {
                 value.cycle();
                 self.songs_dir = Dir::new(self.search(client)?);
-                self.preview = self.prepare_preview(client, context.config)?;
+                self.preview = self.prepare_preview(client, context)?;
             }
             FocusedInputGroup::Filters(FilterInput {
                 variant: FilterInputVariant::SelectFilterCaseSensitive { ref mut value },
@@ -391,7 +411,7 @@ impl SearchPane {
             }) => {
                 *value = !*value;
                 self.songs_dir = Dir::new(self.search(client)?);
-                self.preview = self.prepare_preview(client, context.config)?;
+                self.preview = self.prepare_preview(client, context)?;
             }
         };
         Ok(())
@@ -502,7 +522,7 @@ impl Pane for SearchPane {
     fn on_event(&mut self, event: &mut UiEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         if let crate::ui::UiEvent::Database = event {
             self.songs_dir = Dir::default();
-            self.preview = self.prepare_preview(client, context.config)?;
+            self.preview = self.prepare_preview(client, context)?;
             self.phase = Phase::Search;
 
             status_warn!("The music database has been updated. The current tab has been reinitialized in the root directory to prevent inconsistent behaviours.");
@@ -527,7 +547,7 @@ impl Pane for SearchPane {
                 if let Some(input) = self.get_clicked_input(event) {
                     self.inputs.focused_idx = input;
                 }
-                self.preview = self.prepare_preview(client, context.config)?;
+                self.preview = self.prepare_preview(client, context)?;
 
                 context.render()?;
             }
@@ -544,7 +564,7 @@ impl Pane for SearchPane {
                             self.songs_dir.select_idx(idx_to_select, context.config.scrolloff);
                         }
 
-                        self.preview = self.prepare_preview(client, context.config)?;
+                        self.preview = self.prepare_preview(client, context)?;
 
                         context.render()?;
                     }
@@ -558,7 +578,7 @@ impl Pane for SearchPane {
                     if matches!(self.phase, Phase::SearchTextboxInput) {
                         self.phase = Phase::Search;
                         self.songs_dir = Dir::new(self.search(client)?);
-                        self.preview = self.prepare_preview(client, context.config)?;
+                        self.preview = self.prepare_preview(client, context)?;
                     }
 
                     if let Some(input) = self.get_clicked_input(event) {
@@ -571,7 +591,7 @@ impl Pane for SearchPane {
                     let clicked_row = event.y.saturating_sub(self.column_areas[1].y).into();
                     if let Some(idx) = self.songs_dir.state.get_at_rendered_row(clicked_row) {
                         self.songs_dir.select_idx(idx, context.config.scrolloff);
-                        self.preview = self.prepare_preview(client, context.config)?;
+                        self.preview = self.prepare_preview(client, context)?;
 
                         context.render()?;
                     }
@@ -594,7 +614,7 @@ impl Pane for SearchPane {
                     if matches!(self.phase, Phase::SearchTextboxInput) {
                         self.phase = Phase::Search;
                         self.songs_dir = Dir::new(self.search(client)?);
-                        self.preview = self.prepare_preview(client, context.config)?;
+                        self.preview = self.prepare_preview(client, context)?;
                     }
                     self.inputs.next_non_wrapping();
 
@@ -611,7 +631,7 @@ impl Pane for SearchPane {
                     if matches!(self.phase, Phase::SearchTextboxInput) {
                         self.phase = Phase::Search;
                         self.songs_dir = Dir::new(self.search(client)?);
-                        self.preview = self.prepare_preview(client, context.config)?;
+                        self.preview = self.prepare_preview(client, context)?;
                     }
 
                     self.inputs.prev_non_wrapping();
@@ -632,19 +652,33 @@ impl Pane for SearchPane {
 
     fn handle_action(&mut self, event: &mut KeyEvent, client: &mut impl MpdClient, context: &AppContext) -> Result<()> {
         let config = context.config;
+        if matches!(
+            self.phase,
+            Phase::Search | Phase::BrowseResults { filter_input_on: false }
+        ) {
+            if let Some(SearchActions::ResetForm) = event.as_search_action(context) {
+                self.reset(&config.search);
+                self.songs_dir = Dir::default();
+                self.phase = Phase::Search;
+                self.preview = self.prepare_preview(client, context)?;
+
+                context.render()?;
+                return Ok(());
+            }
+        }
         match &mut self.phase {
             Phase::SearchTextboxInput => match event.as_common_action(context) {
                 Some(CommonAction::Close) => {
                     self.phase = Phase::Search;
                     self.songs_dir = Dir::new(self.search(client)?);
-                    self.preview = self.prepare_preview(client, config)?;
+                    self.preview = self.prepare_preview(client, context)?;
 
                     context.render()?;
                 }
                 Some(CommonAction::Confirm) => {
                     self.phase = Phase::Search;
                     self.songs_dir = Dir::new(self.search(client)?);
-                    self.preview = self.prepare_preview(client, config)?;
+                    self.preview = self.prepare_preview(client, context)?;
 
                     context.render()?;
                 }
@@ -705,7 +739,7 @@ impl Pane for SearchPane {
                         CommonAction::UpHalf => {}
                         CommonAction::Right if !self.songs_dir.items.is_empty() => {
                             self.phase = Phase::BrowseResults { filter_input_on: false };
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
@@ -747,11 +781,13 @@ impl Pane for SearchPane {
                         }
                         CommonAction::FocusInput => {}
                         CommonAction::Add => {}
+                        CommonAction::InsertAfterCurrent => {}
+                        CommonAction::AddWithPriority => {}
                         CommonAction::Delete => match self.inputs.focused_mut() {
                             FocusedInputGroup::Textboxes(textbox) if !textbox.value.is_empty() => {
                                 textbox.value.clear();
                                 self.songs_dir = Dir::new(self.search(client)?);
-                                self.preview = self.prepare_preview(client, config)?;
+                                self.preview = self.prepare_preview(client, context)?;
 
                                 context.render()?;
                             }
@@ -770,7 +806,7 @@ impl Pane for SearchPane {
                 Some(CommonAction::Close) => {
                     *filter_input_on = false;
                     self.songs_dir.set_filter(None, config);
-                    self.preview = self.prepare_preview(client, config)?;
+                    self.preview = self.prepare_preview(client, context)?;
 
                     context.render()?;
                 }
@@ -785,7 +821,7 @@ impl Pane for SearchPane {
                         KeyCode::Char(c) => {
                             self.songs_dir.push_filter(c, config);
                             self.songs_dir.jump_first_matching(config);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
@@ -811,6 +847,21 @@ impl Pane for SearchPane {
                             let selected = self.songs_dir.selected().map(|s| s.file.as_str());
                             run_external(command, create_env(context, selected, client)?);
                         }
+                        GlobalAction::RateSelectedSong(rating) if !self.songs_dir.marked().is_empty() => {
+                            let songs = self
+                                .songs_dir
+                                .marked_items()
+                                .map(|song| song.file.as_str())
+                                .collect_vec();
+                            client.rate_songs(&songs, rating)?;
+                            status_info!("Rated {} songs", self.songs_dir.marked().len());
+                        }
+                        GlobalAction::RateSelectedSong(rating) => {
+                            if let Some(song) = self.songs_dir.selected() {
+                                client.rate_song(&song.file, rating)?;
+                                status_info!("Rated '{}'", song.title().unwrap_or(&song.file));
+                            }
+                        }
                         _ => {
                             event.abandon();
                         }
@@ -820,14 +871,14 @@ impl Pane for SearchPane {
                         CommonAction::Down => {
                             self.songs_dir
                                 .next(context.config.scrolloff, context.config.wrap_navigation);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::Up => {
                             self.songs_dir
                                 .prev(context.config.scrolloff, context.config.wrap_navigation);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
@@ -835,32 +886,32 @@ impl Pane for SearchPane {
                         CommonAction::MoveUp => {}
                         CommonAction::DownHalf => {
                             self.songs_dir.next_half_viewport(context.config.scrolloff);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::UpHalf => {
                             self.songs_dir.prev_half_viewport(context.config.scrolloff);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::Right => self.add_current(false, client, context)?,
                         CommonAction::Left => {
                             self.phase = Phase::Search;
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::Top => {
                             self.songs_dir.first();
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::Bottom => {
                             self.songs_dir.last();
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
@@ -872,13 +923,13 @@ impl Pane for SearchPane {
                         }
                         CommonAction::NextResult => {
                             self.songs_dir.jump_next_matching(config);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
                         CommonAction::PreviousResult => {
                             self.songs_dir.jump_previous_matching(config);
-                            self.preview = self.prepare_preview(client, config)?;
+                            self.preview = self.prepare_preview(client, context)?;
 
                             context.render()?;
                         }
@@ -898,6 +949,20 @@ impl Pane for SearchPane {
                         }
                         CommonAction::FocusInput => {}
                         CommonAction::Add => self.add_current(false, client, context)?,
+                        CommonAction::InsertAfterCurrent => {
+                            let songs = self.marked_or_selected_songs();
+                            client.insert_after_current(&songs, &context.status, &context.capabilities)?;
+                            status_info!("Inserted {} songs after the current song", songs.len());
+
+                            context.render()?;
+                        }
+                        CommonAction::AddWithPriority => {
+                            let songs = self.marked_or_selected_songs();
+                            client.add_with_priority(&songs)?;
+                            status_info!("Added {} songs to queue with high priority", songs.len());
+
+                            context.render()?;
+                        }
                         CommonAction::AddAll => {
                             self.search_add(client)?;
                             status_info!("All found songs added to queue");
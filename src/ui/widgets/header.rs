@@ -43,15 +43,19 @@ impl Widget for Header<'_> {
                 return;
             };
             let template = PropertyTemplates(config.theme.header.rows[row].left);
-            let widget = template.format(self.song, self.status).left_aligned();
+            let widget = template
+                .format(self.song, self.status, config.server_name())
+                .left_aligned();
             widget.render(left, buf);
 
             let template = PropertyTemplates(config.theme.header.rows[row].center);
-            let widget = template.format(self.song, self.status).centered();
+            let widget = template.format(self.song, self.status, config.server_name()).centered();
             widget.render(center, buf);
 
             let template = PropertyTemplates(config.theme.header.rows[row].right);
-            let widget = template.format(self.song, self.status).right_aligned();
+            let widget = template
+                .format(self.song, self.status, config.server_name())
+                .right_aligned();
             widget.render(right, buf);
         }
     }
@@ -59,9 +63,9 @@ impl Widget for Header<'_> {
 
 struct PropertyTemplates<'a>(&'a [&'a Property<'static, PropertyKind>]);
 impl<'a> PropertyTemplates<'a> {
-    fn format(&'a self, song: Option<&'a Song>, status: &'a Status) -> Line<'a> {
+    fn format(&'a self, song: Option<&'a Song>, status: &'a Status, server: &'a str) -> Line<'a> {
         Line::from(self.0.iter().fold(Vec::new(), |mut acc, val| {
-            match val.as_span(song, status) {
+            match val.as_span(song, status, server) {
                 Some(Either::Left(span)) => acc.push(span),
                 Some(Either::Right(ref mut spans)) => acc.append(spans),
                 None => {}